@@ -0,0 +1,726 @@
+use crate::components::{ConfirmationDialog, ConfirmationOutcome, ThemedButton};
+use crate::models::{
+    parse_role_list, ConnectionEntry, Privilege, Resource, RoleInfo, RoleRef, UserInfo,
+};
+use crate::services::DatabaseService;
+use crate::theme::Theme;
+use crate::utils::error::Result;
+use crate::utils::task::BackgroundTask;
+use egui::{CollapsingHeader, Context, Grid, RichText, TextEdit, Ui, Window};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+const BUILT_IN_ROLES_HINT: &str = "Comma-separated, e.g. readWrite, read@reporting. \
+    Built-in roles include read, readWrite, dbAdmin, userAdmin and dbOwner.";
+
+/// The database whose users and roles were asked for.
+pub struct AdminRequest {
+    pub connection_id: String,
+    pub database: String,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum AdminSection {
+    Users,
+    Roles,
+}
+
+/// A change to a database's users or roles.
+enum AdminCommand {
+    CreateUser {
+        user: String,
+        password: String,
+        roles: Vec<RoleRef>,
+    },
+    DropUser(String),
+    ChangePassword {
+        user: String,
+        password: String,
+    },
+    GrantRoles {
+        user: String,
+        roles: Vec<RoleRef>,
+    },
+    RevokeRoles {
+        user: String,
+        roles: Vec<RoleRef>,
+    },
+    CreateRole {
+        role: String,
+        privileges: Vec<Privilege>,
+        roles: Vec<RoleRef>,
+    },
+    DropRole(String),
+}
+
+impl AdminCommand {
+    /// The dialog to confirm the command with, for those that take away
+    /// access.
+    fn confirmation(&self, database: &str, theme: &Arc<Theme>) -> Option<ConfirmationDialog> {
+        let theme = Arc::clone(theme);
+        match self {
+            AdminCommand::DropUser(user) => Some(
+                ConfirmationDialog::new(
+                    "Drop user",
+                    format!(
+                        "Drop user '{}' from '{}'? They can no longer log in.",
+                        user, database
+                    ),
+                    theme,
+                )
+                .require_typed_name(user),
+            ),
+            AdminCommand::DropRole(role) => Some(
+                ConfirmationDialog::new(
+                    "Drop role",
+                    format!(
+                        "Drop role '{}' from '{}'? Users that have it lose its privileges.",
+                        role, database
+                    ),
+                    theme,
+                )
+                .require_typed_name(role),
+            ),
+            AdminCommand::RevokeRoles { user, roles } => Some(ConfirmationDialog::new(
+                "Revoke roles",
+                format!("Revoke {} from '{}'?", role_list(roles), user),
+                theme,
+            )),
+            AdminCommand::ChangePassword { user, .. } => Some(ConfirmationDialog::new(
+                "Change password",
+                format!(
+                    "Change the password of '{}'? The old one stops working.",
+                    user
+                ),
+                theme,
+            )),
+            _ => None,
+        }
+    }
+
+    fn done_message(&self) -> String {
+        match self {
+            AdminCommand::CreateUser { user, .. } => format!("Created user '{}'", user),
+            AdminCommand::DropUser(user) => format!("Dropped user '{}'", user),
+            AdminCommand::ChangePassword { user, .. } => {
+                format!("Changed the password of '{}'", user)
+            }
+            AdminCommand::GrantRoles { user, roles } => {
+                format!("Granted {} to '{}'", role_list(roles), user)
+            }
+            AdminCommand::RevokeRoles { user, roles } => {
+                format!("Revoked {} from '{}'", role_list(roles), user)
+            }
+            AdminCommand::CreateRole { role, .. } => format!("Created role '{}'", role),
+            AdminCommand::DropRole(role) => format!("Dropped role '{}'", role),
+        }
+    }
+
+    async fn run(self, service: Arc<DatabaseService>, database: String) -> Result<String> {
+        let message = self.done_message();
+        match self {
+            AdminCommand::CreateUser {
+                user,
+                password,
+                roles,
+            } => {
+                service
+                    .create_user(&database, &user, &password, &roles)
+                    .await?
+            }
+            AdminCommand::DropUser(user) => service.drop_user(&database, &user).await?,
+            AdminCommand::ChangePassword { user, password } => {
+                service.change_password(&database, &user, &password).await?
+            }
+            AdminCommand::GrantRoles { user, roles } => {
+                service.grant_roles(&database, &user, &roles).await?
+            }
+            AdminCommand::RevokeRoles { user, roles } => {
+                service.revoke_roles(&database, &user, &roles).await?
+            }
+            AdminCommand::CreateRole {
+                role,
+                privileges,
+                roles,
+            } => {
+                service
+                    .create_role(&database, &role, &privileges, &roles)
+                    .await?
+            }
+            AdminCommand::DropRole(role) => service.drop_role(&database, &role).await?,
+        }
+        Ok(message)
+    }
+}
+
+fn role_list(roles: &[RoleRef]) -> String {
+    roles
+        .iter()
+        .map(RoleRef::to_string)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+struct PendingCommand {
+    command: AdminCommand,
+    dialog: ConfirmationDialog,
+}
+
+/// A privilege of the role being defined.
+#[derive(Default)]
+struct PrivilegeRow {
+    cluster: bool,
+    db: String,
+    collection: String,
+    /// Comma-separated.
+    actions: String,
+}
+
+impl PrivilegeRow {
+    fn privilege(&self) -> std::result::Result<Privilege, String> {
+        let actions: Vec<String> = self
+            .actions
+            .split(',')
+            .map(str::trim)
+            .filter(|action| !action.is_empty())
+            .map(str::to_string)
+            .collect();
+        if actions.is_empty() {
+            return Err("Every privilege needs at least one action".to_string());
+        }
+        let resource = if self.cluster {
+            Resource::Cluster
+        } else {
+            Resource::Namespace {
+                db: self.db.trim().to_string(),
+                collection: self.collection.trim().to_string(),
+            }
+        };
+        Ok(Privilege { resource, actions })
+    }
+}
+
+/// Window listing a database's users and roles, with forms to create users
+/// and custom roles, change passwords and grant or revoke roles.
+pub struct AdminWindow {
+    service: Option<Arc<DatabaseService>>,
+    read_only: bool,
+    title: String,
+    database: String,
+    section: AdminSection,
+    users: Vec<UserInfo>,
+    users_task: Option<BackgroundTask<Result<Vec<UserInfo>>>>,
+    roles: Vec<RoleInfo>,
+    roles_task: Option<BackgroundTask<Result<Vec<RoleInfo>>>>,
+    show_built_in: bool,
+    command_task: Option<BackgroundTask<Result<String>>>,
+    pending: Option<PendingCommand>,
+    /// Roles typed to grant, by user.
+    grant_text: HashMap<String, String>,
+    /// New passwords typed, by user.
+    password_text: HashMap<String, String>,
+    new_user: String,
+    new_user_password: String,
+    new_user_roles: String,
+    new_role: String,
+    new_role_inherits: String,
+    new_role_privileges: Vec<PrivilegeRow>,
+    /// The outcome of the last change or refresh, and whether it failed.
+    message: Option<(String, bool)>,
+    theme: Arc<Theme>,
+}
+
+impl AdminWindow {
+    pub fn new(theme: Arc<Theme>) -> Self {
+        Self {
+            service: None,
+            read_only: false,
+            title: String::new(),
+            database: String::new(),
+            section: AdminSection::Users,
+            users: Vec::new(),
+            users_task: None,
+            roles: Vec::new(),
+            roles_task: None,
+            show_built_in: false,
+            command_task: None,
+            pending: None,
+            grant_text: HashMap::new(),
+            password_text: HashMap::new(),
+            new_user: String::new(),
+            new_user_password: String::new(),
+            new_user_roles: String::new(),
+            new_role: String::new(),
+            new_role_inherits: String::new(),
+            new_role_privileges: Vec::new(),
+            message: None,
+            theme,
+        }
+    }
+
+    pub fn open(&mut self, ctx: &Context, connection: &ConnectionEntry, database: String) {
+        self.title = format!("Users & Roles · {} · {}", connection.name, database);
+        self.service = Some(Arc::clone(&connection.service));
+        self.read_only = connection.read_only();
+        self.database = database;
+        self.users.clear();
+        self.roles.clear();
+        self.grant_text.clear();
+        self.password_text.clear();
+        self.new_user.clear();
+        self.new_user_password.clear();
+        self.new_user_roles.clear();
+        self.new_role.clear();
+        self.new_role_inherits.clear();
+        self.new_role_privileges = vec![self.privilege_row()];
+        self.pending = None;
+        self.command_task = None;
+        self.message = None;
+        self.refresh(ctx);
+    }
+
+    fn refresh(&mut self, ctx: &Context) {
+        let Some(service) = self.service.clone() else {
+            return;
+        };
+        let database = self.database.clone();
+        self.users_task = Some(BackgroundTask::spawn(ctx, {
+            let service = Arc::clone(&service);
+            let database = database.clone();
+            async move { service.users(&database).await }
+        }));
+        self.roles_task = Some(BackgroundTask::spawn(ctx, async move {
+            service.roles(&database).await
+        }));
+    }
+
+    /// Runs the command, or asks first when it takes away access.
+    fn submit(&mut self, ctx: &Context, command: AdminCommand) {
+        if let Some(dialog) = command.confirmation(&self.database, &self.theme) {
+            self.pending = Some(PendingCommand { command, dialog });
+        } else {
+            self.run(ctx, command);
+        }
+    }
+
+    fn run(&mut self, ctx: &Context, command: AdminCommand) {
+        let Some(service) = self.service.clone() else {
+            return;
+        };
+        let database = self.database.clone();
+        self.message = None;
+        self.command_task = Some(BackgroundTask::spawn(ctx, command.run(service, database)));
+    }
+
+    pub fn show(&mut self, ctx: &Context) {
+        if self.service.is_none() {
+            return;
+        }
+        self.poll(ctx);
+
+        let mut open = true;
+        let mut refresh = false;
+        let mut command = None;
+        Window::new(&self.title)
+            .id(egui::Id::new("admin_window"))
+            .open(&mut open)
+            .default_size([600.0, 520.0])
+            .vscroll(true)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.selectable_value(&mut self.section, AdminSection::Users, "Users");
+                    ui.selectable_value(&mut self.section, AdminSection::Roles, "Roles");
+                    ui.add_space(10.0);
+                    refresh = ui
+                        .add_enabled(
+                            self.users_task.is_none() && self.roles_task.is_none(),
+                            ThemedButton::new("Refresh", Arc::clone(&self.theme)),
+                        )
+                        .clicked();
+                    if self.users_task.is_some()
+                        || self.roles_task.is_some()
+                        || self.command_task.is_some()
+                    {
+                        ui.spinner();
+                    }
+                });
+                if let Some((message, failed)) = &self.message {
+                    let color = if *failed {
+                        self.theme.danger_color
+                    } else {
+                        self.theme.accent_color
+                    };
+                    ui.label(RichText::new(message).color(color));
+                }
+                if self.read_only {
+                    ui.label(
+                        RichText::new("Read-only connection: users and roles can't be changed.")
+                            .weak(),
+                    );
+                }
+                ui.separator();
+                command = match self.section {
+                    AdminSection::Users => self.render_users(ui),
+                    AdminSection::Roles => self.render_roles(ui),
+                };
+            });
+
+        if !open {
+            self.service = None;
+            self.users_task = None;
+            self.roles_task = None;
+            self.pending = None;
+            return;
+        }
+        if refresh {
+            self.message = None;
+            self.refresh(ctx);
+        }
+        match command {
+            Some(Ok(command)) => self.submit(ctx, command),
+            Some(Err(e)) => self.message = Some((e, true)),
+            None => {}
+        }
+        self.show_confirmation(ctx);
+    }
+
+    fn poll(&mut self, ctx: &Context) {
+        if let Some(result) = self.users_task.as_mut().and_then(|task| task.poll()) {
+            self.users_task = None;
+            match result {
+                Ok(users) => self.users = users,
+                Err(e) => self.message = Some((e.to_string(), true)),
+            }
+        }
+        if let Some(result) = self.roles_task.as_mut().and_then(|task| task.poll()) {
+            self.roles_task = None;
+            match result {
+                Ok(roles) => self.roles = roles,
+                Err(e) => self.message = Some((e.to_string(), true)),
+            }
+        }
+        if let Some(result) = self.command_task.as_mut().and_then(|task| task.poll()) {
+            self.command_task = None;
+            match result {
+                Ok(message) => {
+                    self.message = Some((message, false));
+                    self.refresh(ctx);
+                }
+                Err(e) => self.message = Some((e.to_string(), true)),
+            }
+        }
+    }
+
+    fn show_confirmation(&mut self, ctx: &Context) {
+        let Some(pending) = &mut self.pending else {
+            return;
+        };
+        match pending.dialog.show(ctx) {
+            ConfirmationOutcome::Confirmed => {
+                if let Some(pending) = self.pending.take() {
+                    self.run(ctx, pending.command);
+                }
+            }
+            ConfirmationOutcome::Cancelled => self.pending = None,
+            ConfirmationOutcome::Pending => {}
+        }
+    }
+
+    fn writable(&self) -> bool {
+        !self.read_only && self.command_task.is_none() && self.pending.is_none()
+    }
+
+    fn render_users(&mut self, ui: &mut Ui) -> Option<std::result::Result<AdminCommand, String>> {
+        let writable = self.writable();
+        let mut command = None;
+
+        if self.users.is_empty() && self.users_task.is_none() {
+            ui.label(RichText::new("No users in this database.").weak());
+        }
+        for user in &self.users {
+            let mut header = format!("{}@{}", user.user, user.db);
+            if !user.mechanisms.is_empty() {
+                header.push_str(&format!(" · {}", user.mechanisms.join(", ")));
+            }
+            CollapsingHeader::new(header)
+                .id_source(("admin_user", &user.user))
+                .show(ui, |ui| {
+                    ui.label(RichText::new("Roles").strong());
+                    if user.roles.is_empty() {
+                        ui.label(RichText::new("None").weak());
+                    }
+                    for role in &user.roles {
+                        ui.horizontal(|ui| {
+                            ui.label(role.to_string());
+                            if ui
+                                .add_enabled(writable, egui::Button::new("Revoke").small())
+                                .clicked()
+                            {
+                                command = Some(Ok(AdminCommand::RevokeRoles {
+                                    user: user.user.clone(),
+                                    roles: vec![role.clone()],
+                                }));
+                            }
+                        });
+                    }
+
+                    ui.horizontal(|ui| {
+                        let text = self.grant_text.entry(user.user.clone()).or_default();
+                        ui.add(
+                            TextEdit::singleline(text)
+                                .hint_text("role or role@database")
+                                .desired_width(220.0),
+                        )
+                        .on_hover_text(BUILT_IN_ROLES_HINT);
+                        if ui
+                            .add_enabled(
+                                writable && !text.trim().is_empty(),
+                                ThemedButton::new("Grant", Arc::clone(&self.theme)),
+                            )
+                            .clicked()
+                        {
+                            command = Some(parse_role_list(text, &self.database).map(|roles| {
+                                AdminCommand::GrantRoles {
+                                    user: user.user.clone(),
+                                    roles,
+                                }
+                            }));
+                            text.clear();
+                        }
+                    });
+
+                    ui.horizontal(|ui| {
+                        let password = self.password_text.entry(user.user.clone()).or_default();
+                        ui.add(
+                            TextEdit::singleline(password)
+                                .password(true)
+                                .hint_text("New password")
+                                .desired_width(220.0),
+                        );
+                        if ui
+                            .add_enabled(
+                                writable && !password.is_empty(),
+                                ThemedButton::new("Change Password", Arc::clone(&self.theme)),
+                            )
+                            .clicked()
+                        {
+                            command = Some(Ok(AdminCommand::ChangePassword {
+                                user: user.user.clone(),
+                                password: std::mem::take(password),
+                            }));
+                        }
+                    });
+
+                    if ui
+                        .add_enabled(
+                            writable,
+                            ThemedButton::new("Drop User…", Arc::clone(&self.theme)),
+                        )
+                        .clicked()
+                    {
+                        command = Some(Ok(AdminCommand::DropUser(user.user.clone())));
+                    }
+                });
+        }
+
+        ui.separator();
+        CollapsingHeader::new("New User")
+            .id_source("admin_new_user")
+            .show(ui, |ui| {
+                Grid::new("admin_new_user_form")
+                    .num_columns(2)
+                    .show(ui, |ui| {
+                        ui.label("Name:");
+                        ui.text_edit_singleline(&mut self.new_user);
+                        ui.end_row();
+                        ui.label("Password:");
+                        ui.add(TextEdit::singleline(&mut self.new_user_password).password(true));
+                        ui.end_row();
+                        ui.label("Roles:");
+                        ui.text_edit_singleline(&mut self.new_user_roles)
+                            .on_hover_text(BUILT_IN_ROLES_HINT);
+                        ui.end_row();
+                    });
+                let ready = !self.new_user.trim().is_empty() && !self.new_user_password.is_empty();
+                if ui
+                    .add_enabled(
+                        writable && ready,
+                        ThemedButton::new("Create User", Arc::clone(&self.theme)),
+                    )
+                    .clicked()
+                {
+                    command = Some(parse_role_list(&self.new_user_roles, &self.database).map(
+                        |roles| AdminCommand::CreateUser {
+                            user: self.new_user.trim().to_string(),
+                            password: std::mem::take(&mut self.new_user_password),
+                            roles,
+                        },
+                    ));
+                    if let Some(Ok(_)) = command {
+                        self.new_user.clear();
+                        self.new_user_roles.clear();
+                    }
+                }
+            });
+        command
+    }
+
+    fn render_roles(&mut self, ui: &mut Ui) -> Option<std::result::Result<AdminCommand, String>> {
+        let writable = self.writable();
+        let mut command = None;
+
+        ui.checkbox(&mut self.show_built_in, "Show built-in roles");
+        for role in self
+            .roles
+            .iter()
+            .filter(|role| self.show_built_in || !role.built_in)
+        {
+            let header = if role.built_in {
+                format!("{}@{} · built-in", role.role, role.db)
+            } else {
+                format!("{}@{}", role.role, role.db)
+            };
+            CollapsingHeader::new(header)
+                .id_source(("admin_role", &role.role))
+                .show(ui, |ui| {
+                    if !role.roles.is_empty() {
+                        ui.label(format!("Inherits: {}", role_list(&role.roles)));
+                    }
+                    ui.label(RichText::new("Privileges").strong());
+                    render_privileges(ui, ("admin_privileges", &role.role), &role.privileges);
+                    if role.inherited_privileges != role.privileges {
+                        ui.label(RichText::new("Including inherited").strong());
+                        render_privileges(
+                            ui,
+                            ("admin_inherited_privileges", &role.role),
+                            &role.inherited_privileges,
+                        );
+                    }
+                    if !role.built_in
+                        && ui
+                            .add_enabled(
+                                writable,
+                                ThemedButton::new("Drop Role…", Arc::clone(&self.theme)),
+                            )
+                            .clicked()
+                    {
+                        command = Some(Ok(AdminCommand::DropRole(role.role.clone())));
+                    }
+                });
+        }
+        if !self.roles.iter().any(|role| !role.built_in) && self.roles_task.is_none() {
+            ui.label(RichText::new("No custom roles in this database.").weak());
+        }
+
+        ui.separator();
+        CollapsingHeader::new("New Role")
+            .id_source("admin_new_role")
+            .show(ui, |ui| {
+                Grid::new("admin_new_role_form")
+                    .num_columns(2)
+                    .show(ui, |ui| {
+                        ui.label("Name:");
+                        ui.text_edit_singleline(&mut self.new_role);
+                        ui.end_row();
+                        ui.label("Inherits:");
+                        ui.text_edit_singleline(&mut self.new_role_inherits)
+                            .on_hover_text(BUILT_IN_ROLES_HINT);
+                        ui.end_row();
+                    });
+                ui.label(RichText::new("Privileges").strong());
+                let mut removed = None;
+                Grid::new("admin_new_role_privileges")
+                    .num_columns(5)
+                    .show(ui, |ui| {
+                        for header in ["Cluster", "Database", "Collection", "Actions", ""] {
+                            ui.label(RichText::new(header).strong());
+                        }
+                        ui.end_row();
+                        for (index, row) in self.new_role_privileges.iter_mut().enumerate() {
+                            ui.checkbox(&mut row.cluster, "");
+                            ui.add_enabled(
+                                !row.cluster,
+                                TextEdit::singleline(&mut row.db)
+                                    .hint_text("any")
+                                    .desired_width(100.0),
+                            );
+                            ui.add_enabled(
+                                !row.cluster,
+                                TextEdit::singleline(&mut row.collection)
+                                    .hint_text("any")
+                                    .desired_width(100.0),
+                            );
+                            ui.add(
+                                TextEdit::singleline(&mut row.actions)
+                                    .hint_text("find, insert, update")
+                                    .desired_width(180.0),
+                            );
+                            if ui.small_button("✕").clicked() {
+                                removed = Some(index);
+                            }
+                            ui.end_row();
+                        }
+                    });
+                if let Some(index) = removed {
+                    self.new_role_privileges.remove(index);
+                }
+                ui.horizontal(|ui| {
+                    if ui.button("Add Privilege").clicked() {
+                        self.new_role_privileges.push(self.privilege_row());
+                    }
+                    if ui
+                        .add_enabled(
+                            writable && !self.new_role.trim().is_empty(),
+                            ThemedButton::new("Create Role", Arc::clone(&self.theme)),
+                        )
+                        .clicked()
+                    {
+                        command = Some(self.new_role_command());
+                    }
+                });
+            });
+        command
+    }
+
+    fn privilege_row(&self) -> PrivilegeRow {
+        PrivilegeRow {
+            db: self.database.clone(),
+            ..Default::default()
+        }
+    }
+
+    fn new_role_command(&self) -> std::result::Result<AdminCommand, String> {
+        let privileges = self
+            .new_role_privileges
+            .iter()
+            .map(PrivilegeRow::privilege)
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        let roles = parse_role_list(&self.new_role_inherits, &self.database)?;
+        if privileges.is_empty() && roles.is_empty() {
+            return Err("A role needs privileges or roles to inherit".to_string());
+        }
+        Ok(AdminCommand::CreateRole {
+            role: self.new_role.trim().to_string(),
+            privileges,
+            roles,
+        })
+    }
+
+    pub fn update_theme(&mut self, theme: Arc<Theme>) {
+        self.theme = theme;
+    }
+}
+
+fn render_privileges(ui: &mut Ui, id: impl std::hash::Hash, privileges: &[Privilege]) {
+    if privileges.is_empty() {
+        ui.label(RichText::new("None").weak());
+        return;
+    }
+    Grid::new(id).num_columns(2).striped(true).show(ui, |ui| {
+        for privilege in privileges {
+            ui.label(RichText::new(privilege.resource.to_string()).monospace());
+            ui.label(privilege.actions.join(", "));
+            ui.end_row();
+        }
+    });
+}
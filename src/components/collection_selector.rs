@@ -6,6 +6,7 @@ use std::sync::Arc;
 pub struct CollectionSelector {
     selected_collection: String,
     collections: Vec<String>,
+    refresh_requested: bool,
     theme: Arc<Theme>,
 }
 
@@ -14,9 +15,31 @@ impl CollectionSelector {
         Self {
             selected_collection: String::new(),
             collections: Vec::new(),
+            refresh_requested: false,
             theme,
         }
     }
+
+    /// Replaces the listed collections, keeping the selection only if it is
+    /// still among them.
+    pub fn set_collections(&mut self, collections: Vec<String>) {
+        if !collections.contains(&self.selected_collection) {
+            self.selected_collection.clear();
+        }
+        self.collections = collections;
+    }
+
+    pub fn selected(&self) -> &str {
+        &self.selected_collection
+    }
+
+    pub fn select(&mut self, collection: String) {
+        self.selected_collection = collection;
+    }
+
+    pub fn take_refresh_request(&mut self) -> bool {
+        std::mem::take(&mut self.refresh_requested)
+    }
 }
 
 impl Component for CollectionSelector {
@@ -44,7 +67,7 @@ impl Component for CollectionSelector {
                 .ui(ui)
                 .clicked()
             {
-                // Refresh collection list
+                self.refresh_requested = true;
             }
         });
     }
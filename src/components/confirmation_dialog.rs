@@ -0,0 +1,80 @@
+use crate::components::ThemedButton;
+use crate::theme::Theme;
+use egui::{Align, Context, Layout, RichText, Widget, Window};
+use std::sync::Arc;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ConfirmationOutcome {
+    Pending,
+    Confirmed,
+    Cancelled,
+}
+
+/// Modal confirmation for destructive actions. When a name is required the
+/// confirm button stays disabled until the user has typed it exactly.
+pub struct ConfirmationDialog {
+    title: String,
+    message: String,
+    required_name: Option<String>,
+    typed_name: String,
+    theme: Arc<Theme>,
+}
+
+impl ConfirmationDialog {
+    pub fn new(title: impl Into<String>, message: impl Into<String>, theme: Arc<Theme>) -> Self {
+        Self {
+            title: title.into(),
+            message: message.into(),
+            required_name: None,
+            typed_name: String::new(),
+            theme,
+        }
+    }
+
+    pub fn require_typed_name(mut self, name: impl Into<String>) -> Self {
+        self.required_name = Some(name.into());
+        self
+    }
+
+    pub fn show(&mut self, ctx: &Context) -> ConfirmationOutcome {
+        let mut outcome = ConfirmationOutcome::Pending;
+        Window::new(&self.title)
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.label(RichText::new(&self.message).color(self.theme.text_color));
+                let confirmed_name = match &self.required_name {
+                    Some(name) => {
+                        ui.add_space(5.0);
+                        ui.label(
+                            RichText::new(format!("Type '{}' to confirm:", name))
+                                .color(self.theme.danger_color)
+                                .strong(),
+                        );
+                        ui.text_edit_singleline(&mut self.typed_name);
+                        self.typed_name == *name
+                    }
+                    None => true,
+                };
+                ui.add_space(5.0);
+                ui.with_layout(Layout::right_to_left(Align::TOP), |ui| {
+                    if ui
+                        .add_enabled(
+                            confirmed_name,
+                            ThemedButton::new("Yes", Arc::clone(&self.theme)),
+                        )
+                        .clicked()
+                    {
+                        outcome = ConfirmationOutcome::Confirmed;
+                    }
+                    if ThemedButton::new("No", Arc::clone(&self.theme))
+                        .ui(ui)
+                        .clicked()
+                    {
+                        outcome = ConfirmationOutcome::Cancelled;
+                    }
+                });
+            });
+        outcome
+    }
+}
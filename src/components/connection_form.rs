@@ -0,0 +1,381 @@
+use crate::components::{Component, ThemedButton};
+use crate::models::{
+    AuthMechanism, ConnectionSettings, HostEntry, ReadPreferenceMode, DEFAULT_PORT,
+};
+use crate::theme::Theme;
+use crate::utils::connection_string::{
+    mask_password, unmask_password, validate_connection_string, ConnectionDiagnostic,
+    DiagnosticField,
+};
+use crate::utils::tls;
+use egui::{ComboBox, DragValue, Grid, RichText, Ui, Widget};
+use std::sync::Arc;
+
+/// Editor for a connection string that shows both the raw URI and a
+/// structured form. Editing either side keeps the other in sync.
+pub struct ConnectionForm {
+    uri: String,
+    settings: ConnectionSettings,
+    /// Whether `settings` were parsed from `uri`. When it can't be parsed,
+    /// the structured form is read-only so it can't overwrite what was typed.
+    parsed: bool,
+    diagnostics: Vec<ConnectionDiagnostic>,
+    theme: Arc<Theme>,
+}
+
+impl ConnectionForm {
+    pub fn new(theme: Arc<Theme>) -> Self {
+        Self {
+            uri: String::new(),
+            settings: ConnectionSettings::default(),
+            parsed: true,
+            diagnostics: Vec::new(),
+            theme,
+        }
+    }
+
+    pub fn uri(&self) -> &str {
+        &self.uri
+    }
+
+    pub fn set_uri(&mut self, uri: &str) {
+        self.uri = uri.to_string();
+        self.sync_from_uri();
+    }
+
+    /// Whether the current connection string can be saved.
+    pub fn is_valid(&self) -> bool {
+        !self.uri.trim().is_empty() && self.diagnostics.is_empty()
+    }
+
+    fn sync_from_uri(&mut self) {
+        self.diagnostics = validate_connection_string(&self.uri);
+        let parsed = if self.uri.trim().is_empty() {
+            Ok(ConnectionSettings::default())
+        } else {
+            ConnectionSettings::from_uri(&self.uri)
+        };
+        self.parsed = parsed.is_ok();
+        if let Ok(settings) = parsed {
+            self.settings = settings;
+        }
+    }
+
+    fn render_diagnostics(&self, ui: &mut Ui, fields: &[DiagnosticField]) {
+        for diagnostic in self
+            .diagnostics
+            .iter()
+            .filter(|d| fields.contains(&d.field))
+        {
+            ui.label(RichText::new(&diagnostic.message).color(self.theme.danger_color));
+        }
+    }
+
+    fn render_label(&self, ui: &mut Ui, text: &str) {
+        ui.label(RichText::new(text).color(self.theme.text_color));
+    }
+
+    fn render_hosts(&mut self, ui: &mut Ui, id_prefix: &str) {
+        let srv = self.settings.srv;
+        let mut remove = None;
+        let host_count = self.settings.hosts.len();
+        ui.vertical(|ui| {
+            for (index, entry) in self.settings.hosts.iter_mut().enumerate() {
+                ui.horizontal(|ui| {
+                    ui.add(
+                        egui::TextEdit::singleline(&mut entry.host)
+                            .desired_width(180.0)
+                            .id_source(format!("{}_host_{}", id_prefix, index)),
+                    );
+                    if !srv {
+                        let mut port = entry.port.unwrap_or(DEFAULT_PORT);
+                        if ui.add(DragValue::new(&mut port).speed(1.0)).changed() {
+                            entry.port = Some(port);
+                        }
+                        if host_count > 1 && ui.small_button("✖").clicked() {
+                            remove = Some(index);
+                        }
+                    }
+                });
+                if srv {
+                    break;
+                }
+            }
+            if !srv
+                && ThemedButton::new("Add Host", Arc::clone(&self.theme))
+                    .ui(ui)
+                    .clicked()
+            {
+                self.settings.hosts.push(HostEntry::default());
+            }
+        });
+        if let Some(index) = remove {
+            self.settings.hosts.remove(index);
+        }
+    }
+
+    fn render_timeout(ui: &mut Ui, value: &mut Option<u64>, default_ms: u64) {
+        ui.horizontal(|ui| {
+            let mut enabled = value.is_some();
+            if ui.checkbox(&mut enabled, "").changed() {
+                *value = enabled.then_some(default_ms);
+            }
+            if let Some(ms) = value {
+                ui.add(DragValue::new(ms).speed(100.0).suffix(" ms"));
+            } else {
+                ui.weak("driver default");
+            }
+        });
+    }
+
+    fn render_settings(&mut self, ui: &mut Ui, id_prefix: &str) {
+        Grid::new(format!("{}_settings_grid", id_prefix))
+            .num_columns(2)
+            .spacing([10.0, 6.0])
+            .show(ui, |ui| {
+                self.render_label(ui, "Scheme:");
+                ui.horizontal(|ui| {
+                    ui.radio_value(&mut self.settings.srv, false, "mongodb://");
+                    ui.radio_value(&mut self.settings.srv, true, "mongodb+srv://");
+                });
+                ui.end_row();
+
+                self.render_label(
+                    ui,
+                    if self.settings.srv {
+                        "SRV host:"
+                    } else {
+                        "Hosts:"
+                    },
+                );
+                ui.vertical(|ui| {
+                    self.render_hosts(ui, id_prefix);
+                    self.render_diagnostics(ui, &[DiagnosticField::Hosts]);
+                });
+                ui.end_row();
+
+                self.render_label(ui, "Authentication:");
+                ComboBox::from_id_source(format!("{}_auth_mechanism", id_prefix))
+                    .selected_text(self.settings.auth_mechanism.label())
+                    .show_ui(ui, |ui| {
+                        for mechanism in AuthMechanism::ALL {
+                            ui.selectable_value(
+                                &mut self.settings.auth_mechanism,
+                                mechanism,
+                                mechanism.label(),
+                            );
+                        }
+                    });
+                ui.end_row();
+
+                if self.settings.auth_mechanism != AuthMechanism::None {
+                    self.render_label(ui, "Username:");
+                    ui.text_edit_singleline(&mut self.settings.username);
+                    ui.end_row();
+
+                    if self.settings.auth_mechanism != AuthMechanism::X509 {
+                        self.render_label(ui, "Password:");
+                        ui.add(
+                            egui::TextEdit::singleline(&mut self.settings.password).password(true),
+                        )
+                        .on_hover_text(
+                            "Use ${VAR} or ${file:/path} to read the password from an \
+                             environment variable or file when connecting",
+                        );
+                        ui.end_row();
+                    }
+
+                    self.render_label(ui, "Auth Source:");
+                    ui.vertical(|ui| {
+                        ui.add(
+                            egui::TextEdit::singleline(&mut self.settings.auth_source)
+                                .hint_text("admin"),
+                        );
+                        self.render_diagnostics(ui, &[DiagnosticField::AuthSource]);
+                    });
+                    ui.end_row();
+                }
+
+                self.render_label(ui, "Default Database:");
+                ui.text_edit_singleline(&mut self.settings.default_database);
+                ui.end_row();
+
+                self.render_label(ui, "Replica Set:");
+                ui.text_edit_singleline(&mut self.settings.replica_set);
+                ui.end_row();
+
+                self.render_label(ui, "Read Preference:");
+                ComboBox::from_id_source(format!("{}_read_preference", id_prefix))
+                    .selected_text(
+                        self.settings
+                            .read_preference
+                            .map(|mode| mode.uri_value())
+                            .unwrap_or("(default)"),
+                    )
+                    .show_ui(ui, |ui| {
+                        ui.selectable_value(&mut self.settings.read_preference, None, "(default)");
+                        for mode in ReadPreferenceMode::ALL {
+                            ui.selectable_value(
+                                &mut self.settings.read_preference,
+                                Some(mode),
+                                mode.uri_value(),
+                            );
+                        }
+                    });
+                ui.end_row();
+
+                self.render_label(ui, "TLS:");
+                ui.vertical(|ui| {
+                    let mut enabled = self.settings.tls.enabled.unwrap_or(self.settings.srv);
+                    if ui.checkbox(&mut enabled, "Enable TLS").changed() {
+                        self.settings.tls.enabled = Some(enabled);
+                    }
+                    if enabled {
+                        ui.checkbox(
+                            &mut self.settings.tls.allow_invalid_certificates,
+                            "Allow invalid certificates",
+                        );
+                        ui.checkbox(
+                            &mut self.settings.tls.allow_invalid_hostnames,
+                            "Allow invalid hostnames",
+                        )
+                        .on_hover_text(
+                            "The TLS library checks host names as part of the certificate, \
+                             so this needs \"Allow invalid certificates\" as well",
+                        );
+                        if let Err(e) = tls::check_verification(&self.settings.tls) {
+                            ui.label(RichText::new(e.to_string()).color(self.theme.danger_color));
+                        }
+                        ui.horizontal(|ui| {
+                            ui.label("CA file:");
+                            ui.text_edit_singleline(&mut self.settings.tls.ca_file);
+                        });
+                        ui.horizontal(|ui| {
+                            ui.label("Certificate/key file:");
+                            ui.text_edit_singleline(&mut self.settings.tls.certificate_key_file);
+                        });
+                        if !self.settings.tls.certificate_key_file.is_empty() {
+                            ui.horizontal(|ui| {
+                                ui.label("Key password:");
+                                ui.add(
+                                    egui::TextEdit::singleline(
+                                        &mut self.settings.tls.certificate_key_file_password,
+                                    )
+                                    .password(true)
+                                    .hint_text("if the key is encrypted"),
+                                )
+                                .on_hover_text(
+                                    "Use ${VAR} or ${file:/path} to read the password from an \
+                                     environment variable or file when connecting",
+                                );
+                            });
+                            if !self.settings.tls.certificate_key_file_password.is_empty() {
+                                ui.label(
+                                    RichText::new(
+                                        "An encrypted key is decrypted into a temporary file only \
+                                         you can read while connecting, and deleted once the \
+                                         driver has loaded it",
+                                    )
+                                    .small()
+                                    .weak(),
+                                );
+                            }
+                        }
+                    }
+                });
+                ui.end_row();
+
+                self.render_label(ui, "App Name:");
+                ui.text_edit_singleline(&mut self.settings.app_name);
+                ui.end_row();
+
+                self.render_label(ui, "Connect Timeout:");
+                Self::render_timeout(ui, &mut self.settings.connect_timeout_ms, 10_000);
+                ui.end_row();
+
+                self.render_label(ui, "Server Selection Timeout:");
+                Self::render_timeout(ui, &mut self.settings.server_selection_timeout_ms, 30_000);
+                ui.end_row();
+
+                self.render_label(ui, "Socket Timeout:");
+                Self::render_timeout(ui, &mut self.settings.socket_timeout_ms, 0);
+                ui.end_row();
+
+                self.render_label(ui, "Direct Connection:");
+                let mut direct = self.settings.direct_connection.unwrap_or(false);
+                if ui.checkbox(&mut direct, "").changed() {
+                    self.settings.direct_connection = Some(direct);
+                }
+                ui.end_row();
+            });
+    }
+}
+
+impl Component for ConnectionForm {
+    fn render(&mut self, ui: &mut Ui, id_prefix: &str) {
+        ui.vertical(|ui| {
+            ui.horizontal(|ui| {
+                ui.add_sized(
+                    [100.0, 20.0],
+                    egui::Label::new(
+                        RichText::new("Connection String:").color(self.theme.text_color),
+                    ),
+                );
+                // The password is never shown in the raw URI; the mask is swapped
+                // back for the real password when the field is edited.
+                let mut display = mask_password(&self.uri);
+                let response = ui.add(
+                    egui::TextEdit::singleline(&mut display)
+                        .desired_width(f32::INFINITY)
+                        .id_source(format!("{}_uri", id_prefix)),
+                );
+                if response.changed() {
+                    self.uri = unmask_password(&display, &self.uri);
+                    self.sync_from_uri();
+                }
+            });
+            self.render_diagnostics(ui, &[DiagnosticField::Uri, DiagnosticField::Options]);
+
+            ui.add_space(5.0);
+            egui::CollapsingHeader::new("Connection Settings")
+                .id_source(format!("{}_settings", id_prefix))
+                .default_open(true)
+                .show(ui, |ui| {
+                    if !self.parsed {
+                        ui.label(
+                            RichText::new("Fix the connection string to edit these settings")
+                                .color(self.theme.warning_color),
+                        );
+                    }
+                    let before = self.settings.clone();
+                    ui.add_enabled_ui(self.parsed, |ui| self.render_settings(ui, id_prefix));
+                    if self.parsed && self.settings != before {
+                        self.uri = self.settings.to_uri();
+                        self.diagnostics = validate_connection_string(&self.uri);
+                    }
+                });
+        });
+    }
+
+    fn update_theme(&mut self, theme: Arc<Theme>) {
+        self.theme = theme;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unparsable_uri_keeps_the_form_from_writing_back() {
+        let mut form = ConnectionForm::new(Arc::new(Theme::google_theme()));
+        form.set_uri("mongodb://db1:27017/shop");
+        assert!(form.parsed);
+
+        form.set_uri("mongodb://db1:port/shop");
+        assert!(!form.parsed);
+        assert_eq!(form.uri(), "mongodb://db1:port/shop");
+        // The last settings that parsed stay on screen, read-only
+        assert_eq!(form.settings.default_database, "shop");
+    }
+}
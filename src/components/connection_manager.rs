@@ -1,45 +1,403 @@
-use crate::components::{Component, ThemedButton};
-use crate::models::{ConnectionProfile, ConnectionProfileManager};
+use crate::components::{
+    Component, ConfirmationDialog, ConfirmationOutcome, ConnectionForm, PasswordPrompt,
+    PasswordPromptOutcome, ProfileTransferDialog, ThemedButton,
+};
+use crate::models::{
+    ConnectionProfile, ConnectionProfileManager, ConnectionSettings, Environment, SshAuthMethod,
+    SshTunnelSettings,
+};
+use crate::services::{
+    check_host, tunnel_target, ConnectionTestReport, ConnectionTestService, StepOutcome, TestStep,
+};
 use crate::theme::Theme;
-use egui::{Align, Context, Layout, RichText, Ui, Widget, Window};
+use crate::utils::connection_string::{
+    mask_password, resolve_placeholders, strip_password, unmask_password,
+};
+use crate::utils::error::{MongoLiteError, Result};
+use crate::utils::task::BackgroundTask;
+use crate::utils::tls;
+use egui::{Align, Color32, Context, Layout, RichText, Sense, Ui, Vec2, Widget, Window};
 use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
 use std::rc::Rc;
 use std::sync::Arc;
+use std::time::Duration;
 use uuid::Uuid;
 
+const TEST_STEP_TIMEOUT: Duration = Duration::from_secs(5);
+/// Suggested when a profile's query timeout is turned on.
+const DEFAULT_QUERY_TIMEOUT_MS: u64 = 30_000;
+
+pub struct ConnectRequest {
+    pub connection_string: String,
+    /// The selected profile, whose safety settings apply to the connection.
+    pub profile: Option<ConnectionProfile>,
+    /// Entered in the password prompt, for profiles that don't save it.
+    pub password: Option<String>,
+}
+
+/// Problems with the profile store, shown in a dialog rather than crashing
+/// the app.
+enum StorageAlert {
+    Error(MongoLiteError),
+    BackedUp(PathBuf),
+}
+
+/// Profiles grouped by their slash-separated folder path.
+#[derive(Default)]
+struct FolderNode<'a> {
+    children: BTreeMap<String, FolderNode<'a>>,
+    profiles: Vec<&'a ConnectionProfile>,
+}
+
+impl<'a> FolderNode<'a> {
+    fn build(profiles: impl Iterator<Item = &'a ConnectionProfile>) -> Self {
+        let mut root = FolderNode::default();
+        for profile in profiles {
+            let mut node = &mut root;
+            for segment in profile.folder.split('/').filter(|s| !s.trim().is_empty()) {
+                node = node.children.entry(segment.trim().to_string()).or_default();
+            }
+            node.profiles.push(profile);
+        }
+        root
+    }
+
+    fn is_empty(&self) -> bool {
+        self.profiles.is_empty() && self.children.is_empty()
+    }
+}
+
+/// What a submitted password prompt goes on to do.
+enum PasswordUse {
+    Connect,
+    Test,
+}
+
 pub struct ConnectionManager {
     connection_string: String,
     profile_manager: Rc<RefCell<ConnectionProfileManager>>,
     theme: Arc<Theme>,
     show_dialog: bool,
     new_profile: ConnectionProfile,
+    tags_input: String,
+    profile_filter: String,
+    connection_form: ConnectionForm,
     edit_mode: bool,
     selected_profile: Option<ConnectionProfile>,
-    delete_confirmation: Option<String>, // Stores the ID of the profile to be deleted
+    delete_confirmation: Option<(String, ConfirmationDialog)>, // ID of the profile to be deleted
+    connect_request: Option<ConnectRequest>,
+    connection_test: Option<BackgroundTask<ConnectionTestReport>>,
+    test_report: Option<ConnectionTestReport>,
+    tls_error: Option<String>,
+    profile_transfer: ProfileTransferDialog,
+    storage_alert: Option<StorageAlert>,
+    password_prompt: Option<(ConnectionProfile, PasswordUse, PasswordPrompt)>,
+    /// Passwords the user chose to remember until the app closes, by profile id.
+    session_passwords: HashMap<String, String>,
 }
 
 impl ConnectionManager {
-    pub fn new(theme: Arc<Theme>) -> Self {
+    pub fn new(theme: Arc<Theme>, data_dir: &Path) -> Self {
+        let profile_manager = ConnectionProfileManager::new(data_dir);
+        let storage_alert = {
+            let mut manager = profile_manager.borrow_mut();
+            manager
+                .migrate_from_working_dir()
+                .and_then(|_| manager.open())
+                .err()
+                .map(StorageAlert::Error)
+        };
         Self {
             connection_string: String::new(),
-            profile_manager: ConnectionProfileManager::new(),
+            profile_transfer: ProfileTransferDialog::new(
+                Rc::clone(&profile_manager),
+                Arc::clone(&theme),
+            ),
+            profile_manager,
+            connection_form: ConnectionForm::new(Arc::clone(&theme)),
             theme,
             show_dialog: false,
-            new_profile: ConnectionProfile {
-                id: String::new(),
-                name: String::new(),
-                connection_string: String::new(),
-            },
+            new_profile: ConnectionProfile::default(),
+            tags_input: String::new(),
+            profile_filter: String::new(),
             edit_mode: false,
             selected_profile: None,
             delete_confirmation: None,
+            connect_request: None,
+            connection_test: None,
+            test_report: None,
+            tls_error: None,
+            storage_alert,
+            password_prompt: None,
+            session_passwords: HashMap::new(),
+        }
+    }
+
+    /// Tests the form's connection the way Connect would use it, asking for
+    /// the password first if the profile wants one.
+    fn request_connection_test(&mut self, ctx: &Context) {
+        if !self.new_profile.ask_password {
+            self.start_connection_test(ctx, None);
+            return;
+        }
+        if let Some(password) = self.session_passwords.get(&self.new_profile.id) {
+            let password = password.clone();
+            self.start_connection_test(ctx, Some(password));
+            return;
+        }
+        let mut profile = self.new_profile.clone();
+        profile.connection_string = self.connection_form.uri().to_string();
+        self.prompt_for_password(profile, PasswordUse::Test);
+    }
+
+    fn start_connection_test(&mut self, ctx: &Context, password: Option<String>) {
+        let connection_string = self.connection_form.uri().to_string();
+        let ssh_tunnel = self.new_profile.ssh_tunnel.clone();
+        self.test_report = None;
+        self.connection_test = Some(BackgroundTask::spawn(ctx, async move {
+            ConnectionTestService::new()
+                .run(
+                    &connection_string,
+                    password,
+                    ssh_tunnel.as_ref(),
+                    TEST_STEP_TIMEOUT,
+                )
+                .await
+        }));
+    }
+
+    fn render_test_report(&mut self, ui: &mut Ui) {
+        if let Some(task) = &mut self.connection_test {
+            if let Some(report) = task.poll() {
+                self.test_report = Some(report);
+                self.connection_test = None;
+            }
+        }
+
+        if self.connection_test.is_some() {
+            ui.horizontal(|ui| {
+                ui.spinner();
+                ui.label(RichText::new("Testing connection...").color(self.theme.text_color));
+            });
+            return;
+        }
+
+        let Some(report) = &self.test_report else {
+            return;
+        };
+
+        ui.add_space(10.0);
+        egui::Grid::new("connection_test_report")
+            .num_columns(3)
+            .spacing([10.0, 4.0])
+            .show(ui, |ui| {
+                for step in TestStep::ALL {
+                    let (icon, color) = match report.result(step).map(|r| r.outcome) {
+                        Some(StepOutcome::Passed) => ("✔", self.theme.accent_color),
+                        Some(StepOutcome::Failed) => ("✖", self.theme.danger_color),
+                        Some(StepOutcome::Skipped) => ("–", self.theme.text_color),
+                        None => ("…", self.theme.separator_color),
+                    };
+                    ui.label(RichText::new(icon).color(color).strong());
+                    ui.label(RichText::new(step.label()).color(self.theme.text_color));
+                    match report.result(step) {
+                        Some(result) => {
+                            let detail = match result.error_code {
+                                Some(code) => format!("[code {}] {}", code, result.detail),
+                                None => result.detail.clone(),
+                            };
+                            ui.label(RichText::new(detail).color(color));
+                        }
+                        None => {
+                            ui.label(RichText::new("not run").color(self.theme.separator_color));
+                        }
+                    }
+                    ui.end_row();
+                }
+            });
+        if let Some(failed) = report.failed_step() {
+            ui.label(
+                RichText::new(format!("Failed at: {}", failed.step.label()))
+                    .color(self.theme.danger_color)
+                    .strong(),
+            );
+        }
+    }
+
+    fn reset_form(&mut self) {
+        self.edit_mode = false;
+        self.new_profile = ConnectionProfile::default();
+        self.tags_input.clear();
+        self.connection_form.set_uri("");
+        self.test_report = None;
+        self.tls_error = None;
+    }
+
+    fn begin_edit(&mut self, profile: &ConnectionProfile) {
+        self.new_profile = profile.clone();
+        self.tags_input = profile.tags.join(", ");
+        self.connection_form.set_uri(&profile.connection_string);
+        self.edit_mode = true;
+        self.tls_error = None;
+    }
+
+    /// Checks the TLS files the form's connection string points at. Files
+    /// named by placeholders that can't be resolved yet are checked when
+    /// connecting instead.
+    fn check_tls_files(&self) -> Result<()> {
+        let Ok(resolved) = resolve_placeholders(self.connection_form.uri()) else {
+            return Ok(());
+        };
+        tls::validate_files(&ConnectionSettings::from_uri(&resolved)?.tls)
+    }
+
+    fn save_form(&mut self) {
+        if let Err(e) = self.check_tls_files() {
+            self.tls_error = Some(e.to_string());
+            return;
+        }
+        self.tls_error = None;
+        if !self.edit_mode {
+            self.new_profile.id = Uuid::new_v4().to_string();
+        }
+        self.new_profile.connection_string = if self.new_profile.ask_password {
+            strip_password(self.connection_form.uri())
+        } else {
+            self.connection_form.uri().to_string()
+        };
+        self.new_profile.folder = self
+            .new_profile
+            .folder
+            .split('/')
+            .map(str::trim)
+            .filter(|segment| !segment.is_empty())
+            .collect::<Vec<_>>()
+            .join("/");
+        self.new_profile.tags = self
+            .tags_input
+            .split(',')
+            .map(|tag| tag.trim().to_string())
+            .filter(|tag| !tag.is_empty())
+            .collect();
+        let saved = self
+            .profile_manager
+            .borrow_mut()
+            .save_profile(&self.new_profile);
+        if let Err(e) = saved {
+            self.storage_alert = Some(StorageAlert::Error(e));
+            return;
+        }
+        if self.selected_profile.as_ref().map(|p| &p.id) == Some(&self.new_profile.id) {
+            self.selected_profile = Some(self.new_profile.clone());
+            self.connection_string = self.new_profile.connection_string.clone();
+        }
+        self.reset_form();
+    }
+
+    fn render_profile_row(&mut self, ui: &mut Ui, profile: &ConnectionProfile) {
+        ui.horizontal(|ui| {
+            let swatch = profile
+                .color
+                .map(|[r, g, b]| Color32::from_rgb(r, g, b))
+                .unwrap_or(Color32::TRANSPARENT);
+            let (rect, _) = ui.allocate_exact_size(Vec2::new(10.0, 10.0), Sense::hover());
+            ui.painter().circle_filled(rect.center(), 5.0, swatch);
+
+            ui.label(RichText::new(&profile.name).color(self.theme.text_color));
+            if profile.read_only {
+                ui.label(RichText::new("🔒").color(self.theme.text_color))
+                    .on_hover_text("Read-only");
+            }
+            if let Some(color) = self.theme.environment_color(profile.environment) {
+                ui.label(
+                    RichText::new(profile.environment.label())
+                        .color(color)
+                        .small()
+                        .strong(),
+                );
+            }
+            for tag in &profile.tags {
+                ui.label(
+                    RichText::new(format!("#{}", tag))
+                        .color(self.theme.separator_color)
+                        .small(),
+                );
+            }
+
+            ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
+                if ThemedButton::new("Delete", Arc::clone(&self.theme))
+                    .ui(ui)
+                    .clicked()
+                {
+                    self.request_delete(profile);
+                }
+                if ThemedButton::new("Edit", Arc::clone(&self.theme))
+                    .ui(ui)
+                    .clicked()
+                {
+                    self.begin_edit(profile);
+                }
+                if ThemedButton::new("Select", Arc::clone(&self.theme))
+                    .ui(ui)
+                    .clicked()
+                {
+                    self.selected_profile = Some(profile.clone());
+                    self.connection_string = profile.connection_string.clone();
+                }
+            });
+        });
+    }
+
+    fn render_folder(&mut self, ui: &mut Ui, node: &FolderNode, path: &str, force_open: bool) {
+        for (name, child) in &node.children {
+            let child_path = if path.is_empty() {
+                name.clone()
+            } else {
+                format!("{}/{}", path, name)
+            };
+            let mut header = egui::CollapsingHeader::new(
+                RichText::new(format!("📁 {}", name)).color(self.theme.text_color),
+            )
+            .id_source(format!("profile_folder_{}", child_path))
+            .default_open(true);
+            if force_open {
+                header = header.open(Some(true));
+            }
+            header.show(ui, |ui| {
+                self.render_folder(ui, child, &child_path, force_open);
+            });
+        }
+        for profile in &node.profiles {
+            self.render_profile_row(ui, profile);
+            ui.add_space(5.0);
         }
     }
 
     fn render_dialog_content(&mut self, ui: &mut Ui) {
         let profiles = self.profile_manager.borrow().get_profiles().to_vec();
 
-        ui.heading(RichText::new("Manage Connection Profiles").color(self.theme.text_color));
+        ui.horizontal(|ui| {
+            ui.heading(RichText::new("Manage Connection Profiles").color(self.theme.text_color));
+            ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
+                if ui
+                    .add_enabled(
+                        !profiles.is_empty(),
+                        ThemedButton::new("Export...", Arc::clone(&self.theme)),
+                    )
+                    .clicked()
+                {
+                    self.profile_transfer.open_export();
+                }
+                if ThemedButton::new("Import...", Arc::clone(&self.theme))
+                    .ui(ui)
+                    .clicked()
+                {
+                    self.profile_transfer.open_import();
+                }
+            });
+        });
         ui.add_space(10.0);
 
         if profiles.is_empty() {
@@ -53,37 +411,34 @@ impl ConnectionManager {
                 ui.add_space(20.0);
             });
         } else {
-            // Render profile list
-            egui::ScrollArea::vertical().show(ui, |ui| {
-                for profile in profiles.iter() {
-                    ui.horizontal(|ui| {
-                        ui.label(RichText::new(&profile.name).color(self.theme.text_color));
-                        ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
-                            if ThemedButton::new("Delete", Arc::clone(&self.theme))
-                                .ui(ui)
-                                .clicked()
-                            {
-                                self.delete_confirmation = Some(profile.id.clone());
-                            }
-                            if ThemedButton::new("Edit", Arc::clone(&self.theme))
-                                .ui(ui)
-                                .clicked()
-                            {
-                                self.new_profile = profile.clone();
-                                self.edit_mode = true;
-                            }
-                            if ThemedButton::new("Select", Arc::clone(&self.theme))
-                                .ui(ui)
-                                .clicked()
-                            {
-                                self.selected_profile = Some(profile.clone());
-                                self.connection_string = profile.connection_string.clone();
-                            }
-                        });
-                    });
-                    ui.add_space(5.0);
-                }
+            ui.horizontal(|ui| {
+                ui.label(RichText::new("Search:").color(self.theme.text_color));
+                ui.add(
+                    egui::TextEdit::singleline(&mut self.profile_filter)
+                        .hint_text("name, folder, tag or environment"),
+                );
             });
+            ui.add_space(5.0);
+
+            // Render profile tree
+            let searching = !self.profile_filter.trim().is_empty();
+            let tree = FolderNode::build(
+                profiles
+                    .iter()
+                    .filter(|profile| profile.matches_filter(&self.profile_filter)),
+            );
+            egui::ScrollArea::vertical()
+                .id_source("profile_tree")
+                .max_height(250.0)
+                .show(ui, |ui| {
+                    if tree.is_empty() {
+                        ui.label(
+                            RichText::new("No profiles match the search.")
+                                .color(self.theme.text_color),
+                        );
+                    }
+                    self.render_folder(ui, &tree, "", searching);
+                });
         }
 
         ui.add_space(10.0);
@@ -91,140 +446,458 @@ impl ConnectionManager {
         ui.add_space(10.0);
 
         // Render add/edit form
-        ui.horizontal(|ui| {
-            ui.add_sized(
-                [100.0, 20.0],
-                egui::Label::new(RichText::new("Name:").color(self.theme.text_color)),
-            );
-            ui.add_sized(
-                [200.0, 20.0],
-                egui::TextEdit::singleline(&mut self.new_profile.name),
-            );
-        });
+        egui::Grid::new("profile_details_grid")
+            .num_columns(2)
+            .spacing([10.0, 6.0])
+            .show(ui, |ui| {
+                ui.label(RichText::new("Name:").color(self.theme.text_color));
+                ui.text_edit_singleline(&mut self.new_profile.name);
+                ui.end_row();
+
+                ui.label(RichText::new("Folder:").color(self.theme.text_color));
+                ui.add(
+                    egui::TextEdit::singleline(&mut self.new_profile.folder)
+                        .hint_text("e.g. Clients/Acme"),
+                );
+                ui.end_row();
+
+                ui.label(RichText::new("Tags:").color(self.theme.text_color));
+                ui.add(
+                    egui::TextEdit::singleline(&mut self.tags_input).hint_text("comma separated"),
+                );
+                ui.end_row();
+
+                ui.label(RichText::new("Environment:").color(self.theme.text_color));
+                egui::ComboBox::from_id_source("profile_environment")
+                    .selected_text(self.new_profile.environment.label())
+                    .show_ui(ui, |ui| {
+                        for environment in Environment::ALL {
+                            ui.selectable_value(
+                                &mut self.new_profile.environment,
+                                environment,
+                                environment.label(),
+                            );
+                        }
+                    });
+                ui.end_row();
+
+                ui.label(RichText::new("Safety:").color(self.theme.text_color));
+                ui.checkbox(&mut self.new_profile.read_only, "Read-only")
+                    .on_hover_text(
+                        "Block inserts, updates, deletes, index changes, drops and admin commands",
+                    );
+                ui.end_row();
+
+                ui.label(RichText::new("Password:").color(self.theme.text_color));
+                ui.checkbox(&mut self.new_profile.ask_password, "Ask when connecting")
+                    .on_hover_text("Don't save the password; ask for it on every connect");
+                ui.end_row();
+
+                ui.label(RichText::new("Query timeout:").color(self.theme.text_color));
+                ui.horizontal(|ui| {
+                    let mut has_timeout = self.new_profile.default_max_time_ms.is_some();
+                    if ui
+                        .checkbox(&mut has_timeout, "")
+                        .on_hover_text("maxTimeMS for queries that don't set their own")
+                        .changed()
+                    {
+                        self.new_profile.default_max_time_ms =
+                            has_timeout.then_some(DEFAULT_QUERY_TIMEOUT_MS);
+                    }
+                    if let Some(max_time_ms) = &mut self.new_profile.default_max_time_ms {
+                        ui.add(egui::DragValue::new(max_time_ms).suffix(" ms"));
+                    }
+                });
+                ui.end_row();
+
+                ui.label(RichText::new("Color:").color(self.theme.text_color));
+                ui.horizontal(|ui| {
+                    let mut has_color = self.new_profile.color.is_some();
+                    if ui.checkbox(&mut has_color, "").changed() {
+                        self.new_profile.color = has_color.then_some([66, 133, 244]);
+                    }
+                    if let Some(color) = &mut self.new_profile.color {
+                        ui.color_edit_button_srgb(color);
+                    }
+                });
+                ui.end_row();
+            });
         ui.add_space(5.0);
-        ui.horizontal(|ui| {
-            ui.add_sized(
-                [100.0, 20.0],
-                egui::Label::new(RichText::new("Connection String:").color(self.theme.text_color)),
-            );
-            ui.add_sized(
-                [200.0, 20.0],
-                egui::TextEdit::singleline(&mut self.new_profile.connection_string),
-            );
-        });
+        self.connection_form.render(ui, "profile_form");
+        ui.add_space(5.0);
+        self.render_ssh_tunnel(ui);
 
         ui.add_space(10.0);
 
+        let can_save = self.connection_form.is_valid()
+            && self
+                .new_profile
+                .ssh_tunnel
+                .as_ref()
+                .is_none_or(|ssh_tunnel| !ssh_tunnel.host.trim().is_empty());
         ui.with_layout(Layout::right_to_left(Align::TOP), |ui| {
-            if self.edit_mode {
-                if ThemedButton::new("Update Profile", Arc::clone(&self.theme))
-                    .ui(ui)
-                    .clicked()
-                {
-                    self.profile_manager
-                        .borrow_mut()
-                        .save_profile(&self.new_profile);
-                    self.edit_mode = false;
-                    self.new_profile = ConnectionProfile {
-                        id: String::new(),
-                        name: String::new(),
-                        connection_string: String::new(),
-                    };
-                }
+            let save_label = if self.edit_mode {
+                "Update Profile"
             } else {
-                if ThemedButton::new("Add New Profile", Arc::clone(&self.theme))
-                    .ui(ui)
-                    .clicked()
-                {
-                    self.new_profile.id = Uuid::new_v4().to_string();
-                    self.profile_manager
-                        .borrow_mut()
-                        .save_profile(&self.new_profile);
-                    self.new_profile = ConnectionProfile {
-                        id: String::new(),
-                        name: String::new(),
-                        connection_string: String::new(),
-                    };
-                }
+                "Add New Profile"
+            };
+            if ui
+                .add_enabled(
+                    can_save,
+                    ThemedButton::new(save_label, Arc::clone(&self.theme)),
+                )
+                .clicked()
+            {
+                self.save_form();
+            }
+            if ui
+                .add_enabled(
+                    can_save && self.connection_test.is_none(),
+                    ThemedButton::new("Test Connection", Arc::clone(&self.theme)),
+                )
+                .clicked()
+            {
+                self.request_connection_test(ui.ctx());
             }
             if ThemedButton::new("Cancel", Arc::clone(&self.theme))
                 .ui(ui)
                 .clicked()
             {
-                self.edit_mode = false;
-                self.new_profile = ConnectionProfile {
-                    id: String::new(),
-                    name: String::new(),
-                    connection_string: String::new(),
-                };
+                self.reset_form();
             }
         });
+
+        if let Some(error) = &self.tls_error {
+            ui.label(RichText::new(error).color(self.theme.danger_color));
+        }
+        self.render_test_report(ui);
+    }
+
+    fn render_ssh_tunnel(&mut self, ui: &mut Ui) {
+        let mut enabled = self.new_profile.ssh_tunnel.is_some();
+        if ui
+            .checkbox(&mut enabled, "Connect through an SSH tunnel")
+            .changed()
+        {
+            self.new_profile.ssh_tunnel = enabled.then(SshTunnelSettings::default);
+        }
+        let theme = Arc::clone(&self.theme);
+        let Some(ssh_tunnel) = &mut self.new_profile.ssh_tunnel else {
+            return;
+        };
+
+        let secret_hint = "Use ${VAR} or ${file:/path} to read it from an environment variable \
+                           or file when connecting";
+        egui::Grid::new("profile_ssh_tunnel")
+            .num_columns(2)
+            .spacing([10.0, 6.0])
+            .show(ui, |ui| {
+                ui.label(RichText::new("SSH Host:").color(theme.text_color));
+                ui.horizontal(|ui| {
+                    ui.add(egui::TextEdit::singleline(&mut ssh_tunnel.host).hint_text("bastion"));
+                    ui.label(RichText::new("Port:").color(theme.text_color));
+                    ui.add(egui::DragValue::new(&mut ssh_tunnel.port).range(1..=u16::MAX));
+                });
+                ui.end_row();
+
+                ui.label(RichText::new("SSH User:").color(theme.text_color));
+                ui.text_edit_singleline(&mut ssh_tunnel.user);
+                ui.end_row();
+
+                ui.label(RichText::new("SSH Auth:").color(theme.text_color));
+                egui::ComboBox::from_id_source("profile_ssh_auth_method")
+                    .selected_text(ssh_tunnel.auth_method.label())
+                    .show_ui(ui, |ui| {
+                        for method in SshAuthMethod::ALL {
+                            ui.selectable_value(
+                                &mut ssh_tunnel.auth_method,
+                                method,
+                                method.label(),
+                            );
+                        }
+                    });
+                ui.end_row();
+
+                match ssh_tunnel.auth_method {
+                    SshAuthMethod::Password => {
+                        ui.label(RichText::new("SSH Password:").color(theme.text_color));
+                        ui.add(egui::TextEdit::singleline(&mut ssh_tunnel.password).password(true))
+                            .on_hover_text(secret_hint);
+                        ui.end_row();
+                    }
+                    SshAuthMethod::PrivateKey => {
+                        ui.label(RichText::new("Key File:").color(theme.text_color));
+                        ui.add(
+                            egui::TextEdit::singleline(&mut ssh_tunnel.key_file)
+                                .hint_text("default keys and agent"),
+                        );
+                        ui.end_row();
+
+                        ui.label(RichText::new("Passphrase:").color(theme.text_color));
+                        ui.add(
+                            egui::TextEdit::singleline(&mut ssh_tunnel.key_passphrase)
+                                .password(true)
+                                .hint_text("if the key is encrypted"),
+                        )
+                        .on_hover_text(secret_hint);
+                        ui.end_row();
+                    }
+                }
+            });
+
+        // Same restrictions `DatabaseService` enforces when connecting
+        let host_problem = (!ssh_tunnel.host.trim().is_empty())
+            .then(|| check_host(&ssh_tunnel.host).err())
+            .flatten();
+        let target_problem = ConnectionSettings::from_uri(self.connection_form.uri())
+            .ok()
+            .and_then(|settings| tunnel_target(&settings).err());
+        for problem in host_problem.into_iter().chain(target_problem) {
+            ui.label(RichText::new(problem.to_string()).color(theme.danger_color));
+        }
+        ui.label(
+            RichText::new(
+                "Uses the system's ssh client, OpenSSH 8.4 or newer for passwords and \
+                 passphrases. The bastion's host key must already be in known_hosts. The \
+                 connection goes to a local port, so TLS can't verify the server through the \
+                 tunnel.",
+            )
+            .color(theme.separator_color)
+            .small(),
+        );
+    }
+
+    fn request_delete(&mut self, profile: &ConnectionProfile) {
+        let mut dialog = ConfirmationDialog::new(
+            "Confirm Deletion",
+            format!(
+                "Are you sure you want to delete the profile '{}'?",
+                profile.name
+            ),
+            Arc::clone(&self.theme),
+        );
+        if profile.requires_typed_confirmation() {
+            dialog = dialog.require_typed_name(profile.name.clone());
+        }
+        self.delete_confirmation = Some((profile.id.clone(), dialog));
     }
 
     fn show_delete_confirmation(&mut self, ctx: &Context) {
-        let mut delete_confirmed = false;
-        let mut cancel_confirmed = false;
-
-        if let Some(profile_id) = &self.delete_confirmation {
-            let profile_name = self
-                .profile_manager
-                .borrow()
-                .get_profiles()
-                .iter()
-                .find(|p| p.id == *profile_id)
-                .map(|p| p.name.clone())
-                .unwrap_or_else(|| "Unknown".to_string());
-
-            Window::new("Confirm Deletion")
-                .collapsible(false)
-                .resizable(false)
-                .show(ctx, |ui| {
+        let Some((profile_id, dialog)) = &mut self.delete_confirmation else {
+            return;
+        };
+
+        match dialog.show(ctx) {
+            ConfirmationOutcome::Confirmed => {
+                let deleted = self.profile_manager.borrow_mut().delete_profile(profile_id);
+                if let Err(e) = deleted {
+                    self.storage_alert = Some(StorageAlert::Error(e));
+                } else if self.selected_profile.as_ref().map(|p| &p.id) == Some(profile_id) {
+                    self.selected_profile = None;
+                }
+                self.delete_confirmation = None;
+            }
+            ConfirmationOutcome::Cancelled => self.delete_confirmation = None,
+            ConfirmationOutcome::Pending => {}
+        }
+    }
+
+    fn show_storage_alert(&mut self, ctx: &Context) {
+        let Some(alert) = &self.storage_alert else {
+            return;
+        };
+
+        let mut next = None;
+        let mut close = false;
+        Window::new("Profile Storage")
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| match alert {
+                StorageAlert::Error(error) => {
+                    let (path, is_open) = {
+                        let manager = self.profile_manager.borrow();
+                        (manager.path().display().to_string(), manager.is_open())
+                    };
                     ui.label(
                         RichText::new(format!(
-                            "Are you sure you want to delete the profile '{}'?",
-                            profile_name
+                            "The profile store at '{}' could not be used.",
+                            path
                         ))
                         .color(self.theme.text_color),
                     );
+                    ui.label(RichText::new(error.to_string()).color(self.theme.danger_color));
+                    if !is_open {
+                        ui.add_space(5.0);
+                        ui.label(
+                            RichText::new(
+                                "Until this is fixed, profiles can't be loaded or saved.",
+                            )
+                            .color(self.theme.text_color),
+                        );
+                    }
+                    ui.add_space(5.0);
                     ui.with_layout(Layout::right_to_left(Align::TOP), |ui| {
-                        if ThemedButton::new("Yes", Arc::clone(&self.theme))
+                        if ThemedButton::new("Dismiss", Arc::clone(&self.theme))
                             .ui(ui)
                             .clicked()
                         {
-                            delete_confirmed = true;
+                            close = true;
                         }
-                        if ThemedButton::new("No", Arc::clone(&self.theme))
+                        if matches!(error, MongoLiteError::StorageCorrupted(_))
+                            && ThemedButton::new("Back Up and Start Fresh", Arc::clone(&self.theme))
+                                .ui(ui)
+                                .clicked()
+                        {
+                            next = Some(
+                                match self.profile_manager.borrow_mut().back_up_and_reset() {
+                                    Ok(backup) => StorageAlert::BackedUp(backup),
+                                    Err(e) => StorageAlert::Error(e),
+                                },
+                            );
+                        }
+                        if !is_open
+                            && ThemedButton::new("Retry", Arc::clone(&self.theme))
+                                .ui(ui)
+                                .clicked()
+                        {
+                            match self.profile_manager.borrow_mut().open() {
+                                Ok(()) => close = true,
+                                Err(e) => next = Some(StorageAlert::Error(e)),
+                            }
+                        }
+                    });
+                }
+                StorageAlert::BackedUp(backup) => {
+                    ui.label(
+                        RichText::new(format!(
+                            "The damaged store was moved to '{}' and an empty one was created.",
+                            backup.display()
+                        ))
+                        .color(self.theme.text_color),
+                    );
+                    ui.add_space(5.0);
+                    ui.with_layout(Layout::right_to_left(Align::TOP), |ui| {
+                        if ThemedButton::new("OK", Arc::clone(&self.theme))
                             .ui(ui)
                             .clicked()
                         {
-                            cancel_confirmed = true;
+                            close = true;
                         }
                     });
-                });
+                }
+            });
+
+        if close {
+            self.storage_alert = None;
+        } else if next.is_some() {
+            self.storage_alert = next;
         }
+    }
+
+    fn request_connect(&mut self) {
+        let profile = self.selected_profile.clone();
+        let Some(prompt_profile) = profile.as_ref().filter(|profile| profile.ask_password) else {
+            self.connect_request = Some(ConnectRequest {
+                connection_string: self.connection_string.clone(),
+                profile,
+                password: None,
+            });
+            return;
+        };
+
+        if let Some(password) = self.session_passwords.get(&prompt_profile.id) {
+            self.connect_request = Some(ConnectRequest {
+                connection_string: self.connection_string.clone(),
+                password: Some(password.clone()),
+                profile,
+            });
+            return;
+        }
+
+        self.prompt_for_password(prompt_profile.clone(), PasswordUse::Connect);
+    }
+
+    fn prompt_for_password(&mut self, profile: ConnectionProfile, password_use: PasswordUse) {
+        let username = ConnectionSettings::from_uri(&profile.connection_string)
+            .map(|settings| settings.username)
+            .unwrap_or_default();
+        let target = if profile.name.trim().is_empty() {
+            "this connection".to_string()
+        } else {
+            format!("'{}'", profile.name)
+        };
+        let message = if username.is_empty() {
+            format!("Enter the password for {}.", target)
+        } else {
+            format!("Enter the password for '{}' on {}.", username, target)
+        };
+        self.password_prompt = Some((
+            profile,
+            password_use,
+            PasswordPrompt::new(message, Arc::clone(&self.theme)),
+        ));
+    }
 
-        // Handle the confirmation outside of the closure
-        if delete_confirmed {
-            if let Some(profile_id) = &self.delete_confirmation {
-                self.profile_manager.borrow_mut().delete_profile(profile_id);
+    fn show_password_prompt(&mut self, ctx: &Context) {
+        let Some((profile, password_use, prompt)) = &mut self.password_prompt else {
+            return;
+        };
+
+        match prompt.show(ctx) {
+            PasswordPromptOutcome::Submitted { password, remember } => {
+                // A profile that hasn't been saved yet has no id to remember by
+                if remember && !profile.id.is_empty() {
+                    self.session_passwords
+                        .insert(profile.id.clone(), password.clone());
+                }
+                match password_use {
+                    PasswordUse::Connect => {
+                        self.connect_request = Some(ConnectRequest {
+                            connection_string: self.connection_string.clone(),
+                            profile: Some(profile.clone()),
+                            password: Some(password),
+                        });
+                    }
+                    PasswordUse::Test => self.start_connection_test(ctx, Some(password)),
+                }
+                self.password_prompt = None;
             }
-            self.delete_confirmation = None;
-        } else if cancel_confirmed {
-            self.delete_confirmation = None;
+            PasswordPromptOutcome::Cancelled => self.password_prompt = None,
+            PasswordPromptOutcome::Pending => {}
         }
     }
 
+    /// Drops a remembered password, e.g. after it was rejected by the server.
+    pub fn forget_session_password(&mut self, profile_id: &str) {
+        self.session_passwords.remove(profile_id);
+    }
+
+    /// Returns the connection the user asked for with the Connect button, if
+    /// any, clearing the request.
+    pub fn take_connect_request(&mut self) -> Option<ConnectRequest> {
+        self.connect_request.take()
+    }
+
+    /// The profile picked with "Select", whose environment is shown app-wide.
+    pub fn active_profile(&self) -> Option<&ConnectionProfile> {
+        self.selected_profile.as_ref()
+    }
+
     pub fn show(&mut self, ctx: &Context) {
         let mut show_dialog = self.show_dialog;
         Window::new("Connection Profiles")
             .open(&mut show_dialog)
-            .default_size([400.0, 300.0])
+            .default_size([520.0, 600.0])
+            .vscroll(true)
             .show(ctx, |ui| {
                 self.render_dialog_content(ui);
             });
         self.show_dialog = show_dialog;
 
         self.show_delete_confirmation(ctx);
+        self.profile_transfer.show(ctx);
+        self.show_storage_alert(ctx);
+        self.show_password_prompt(ctx);
     }
 }
 
@@ -238,13 +911,30 @@ impl Component for ConnectionManager {
             )
             .on_hover_text("Select or manage database connections");
 
-            ui.text_edit_singleline(&mut self.connection_string);
+            let mut display = mask_password(&self.connection_string);
+            if ui.text_edit_singleline(&mut display).changed() {
+                self.connection_string = unmask_password(&display, &self.connection_string);
+                // An edited string is no longer the selected profile's, so it
+                // must not connect with that profile's tunnel, password or
+                // environment
+                if self
+                    .selected_profile
+                    .as_ref()
+                    .is_some_and(|profile| profile.connection_string != self.connection_string)
+                {
+                    self.selected_profile = None;
+                }
+            }
 
-            if ThemedButton::new("Connect", Arc::clone(&self.theme))
-                .ui(ui)
+            let can_connect = !self.connection_string.trim().is_empty();
+            if ui
+                .add_enabled(
+                    can_connect,
+                    ThemedButton::new("Connect", Arc::clone(&self.theme)),
+                )
                 .clicked()
             {
-                // Handle connection logic
+                self.request_connect();
             }
 
             if ThemedButton::new("Manage Profiles", Arc::clone(&self.theme))
@@ -259,6 +949,8 @@ impl Component for ConnectionManager {
     }
 
     fn update_theme(&mut self, theme: Arc<Theme>) {
+        self.connection_form.update_theme(Arc::clone(&theme));
+        self.profile_transfer.update_theme(Arc::clone(&theme));
         self.theme = theme;
     }
 }
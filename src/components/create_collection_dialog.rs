@@ -0,0 +1,494 @@
+use crate::components::ThemedButton;
+use crate::theme::Theme;
+use crate::utils::extended_json::{parse_document, parse_pipeline};
+use egui::{Align, ComboBox, Context, Grid, Key, Layout, RichText, TextEdit, Ui, Widget, Window};
+use mongodb::bson::{self, Document};
+use mongodb::options::{
+    ClusteredIndex, Collation, CreateCollectionOptions, TimeseriesGranularity, TimeseriesOptions,
+};
+use std::sync::Arc;
+use std::time::Duration;
+
+// Characters the server rejects in database names
+const INVALID_DATABASE_CHARACTERS: &[char] = &['/', '\\', '.', ' ', '"', '$', '\0'];
+const MAX_DATABASE_NAME_BYTES: usize = 63;
+
+/// Why `name` can't be used for a collection or view, if it can't.
+pub fn collection_name_error(name: &str) -> Option<&'static str> {
+    if name.is_empty() {
+        Some("Enter a name")
+    } else if name.contains('$') || name.contains('\0') {
+        Some("Names can't contain '$'")
+    } else if name.starts_with("system.") {
+        Some("The 'system.' prefix is reserved")
+    } else {
+        None
+    }
+}
+
+fn database_name_error(name: &str) -> Option<&'static str> {
+    if name.is_empty() {
+        Some("Enter a database name")
+    } else if name.contains(INVALID_DATABASE_CHARACTERS) {
+        Some("Database names can't contain /\\. \"$")
+    } else if name.len() > MAX_DATABASE_NAME_BYTES {
+        Some("Database names are limited to 63 bytes")
+    } else {
+        None
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Kind {
+    Collection,
+    View,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+enum Granularity {
+    #[default]
+    ServerDefault,
+    Seconds,
+    Minutes,
+    Hours,
+}
+
+impl Granularity {
+    const ALL: [Granularity; 4] = [
+        Granularity::ServerDefault,
+        Granularity::Seconds,
+        Granularity::Minutes,
+        Granularity::Hours,
+    ];
+
+    fn label(self) -> &'static str {
+        match self {
+            Granularity::ServerDefault => "Server default",
+            Granularity::Seconds => "Seconds",
+            Granularity::Minutes => "Minutes",
+            Granularity::Hours => "Hours",
+        }
+    }
+
+    fn to_driver(self) -> Option<TimeseriesGranularity> {
+        match self {
+            Granularity::ServerDefault => None,
+            Granularity::Seconds => Some(TimeseriesGranularity::Seconds),
+            Granularity::Minutes => Some(TimeseriesGranularity::Minutes),
+            Granularity::Hours => Some(TimeseriesGranularity::Hours),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum NewCollection {
+    Collection {
+        database: String,
+        name: String,
+        options: Box<CreateCollectionOptions>,
+    },
+    View {
+        database: String,
+        name: String,
+        view_on: String,
+        pipeline: Vec<Document>,
+    },
+}
+
+#[derive(Debug)]
+pub enum CreateCollectionOutcome {
+    Pending,
+    Submitted(NewCollection),
+    Cancelled,
+}
+
+/// Form for a new collection with its creation options, or a new view over
+/// an existing collection. Without a database it also names a new database,
+/// which the server creates along with its first collection.
+pub struct CreateCollectionDialog {
+    kind: Kind,
+    database: String,
+    new_database: bool,
+    name: String,
+    capped: bool,
+    capped_size: String,
+    capped_max: String,
+    validator: String,
+    collation: String,
+    clustered: bool,
+    timeseries: bool,
+    time_field: String,
+    meta_field: String,
+    granularity: Granularity,
+    expire_after_seconds: String,
+    view_on: String,
+    pipeline: String,
+    /// Offered as the source of a view.
+    collections: Vec<String>,
+    error: Option<String>,
+    theme: Arc<Theme>,
+}
+
+impl CreateCollectionDialog {
+    /// `database` is `None` when the collection goes into a new database.
+    pub fn collection(database: Option<String>, theme: Arc<Theme>) -> Self {
+        Self {
+            kind: Kind::Collection,
+            new_database: database.is_none(),
+            database: database.unwrap_or_default(),
+            name: String::new(),
+            capped: false,
+            capped_size: String::new(),
+            capped_max: String::new(),
+            validator: String::new(),
+            collation: String::new(),
+            clustered: false,
+            timeseries: false,
+            time_field: String::new(),
+            meta_field: String::new(),
+            granularity: Granularity::default(),
+            expire_after_seconds: String::new(),
+            view_on: String::new(),
+            pipeline: String::new(),
+            collections: Vec::new(),
+            error: None,
+            theme,
+        }
+    }
+
+    pub fn view(database: String, collections: Vec<String>, theme: Arc<Theme>) -> Self {
+        Self {
+            kind: Kind::View,
+            view_on: collections.first().cloned().unwrap_or_default(),
+            pipeline: "[]".to_string(),
+            collections,
+            ..Self::collection(Some(database), theme)
+        }
+    }
+
+    fn build(&self) -> Result<NewCollection, String> {
+        let database = self.database.trim().to_string();
+        let name = self.name.trim().to_string();
+        if let Some(error) = database_name_error(&database) {
+            return Err(error.to_string());
+        }
+        if let Some(error) = collection_name_error(&name) {
+            return Err(error.to_string());
+        }
+
+        if self.kind == Kind::View {
+            let view_on = self.view_on.trim().to_string();
+            if view_on.is_empty() {
+                return Err("Choose the collection the view reads from".to_string());
+            }
+            let pipeline =
+                parse_pipeline(&self.pipeline).map_err(|e| format!("Pipeline: {}", e))?;
+            return Ok(NewCollection::View {
+                database,
+                name,
+                view_on,
+                pipeline,
+            });
+        }
+
+        let mut options = CreateCollectionOptions::default();
+        if self.capped {
+            if self.timeseries {
+                return Err("Time series collections can't be capped".to_string());
+            }
+            options.capped = Some(true);
+            options.size = Some(
+                parse_positive(&self.capped_size)
+                    .ok_or("Capped collections need a maximum size in bytes")?,
+            );
+            if !self.capped_max.trim().is_empty() {
+                options.max = Some(
+                    parse_positive(&self.capped_max)
+                        .ok_or("The maximum document count must be a positive number")?,
+                );
+            }
+        }
+        if !self.validator.trim().is_empty() {
+            options.validator =
+                Some(parse_document(&self.validator).map_err(|e| format!("Validator: {}", e))?);
+        }
+        if !self.collation.trim().is_empty() {
+            let collation =
+                parse_document(&self.collation).map_err(|e| format!("Collation: {}", e))?;
+            options.collation = Some(
+                bson::from_document::<Collation>(collation)
+                    .map_err(|e| format!("Collation: {}", e))?,
+            );
+        }
+        if self.clustered {
+            if self.timeseries {
+                return Err("Time series collections can't be clustered".to_string());
+            }
+            options.clustered_index = Some(ClusteredIndex::default());
+        }
+        if self.timeseries {
+            let time_field = self.time_field.trim();
+            if time_field.is_empty() {
+                return Err("Time series collections need a time field".to_string());
+            }
+            let meta_field = Some(self.meta_field.trim())
+                .filter(|field| !field.is_empty())
+                .map(str::to_string);
+            options.timeseries = Some(
+                TimeseriesOptions::builder()
+                    .time_field(time_field.to_string())
+                    .meta_field(meta_field)
+                    .granularity(self.granularity.to_driver())
+                    .build(),
+            );
+        }
+        if !self.expire_after_seconds.trim().is_empty() {
+            if !self.timeseries && !self.clustered {
+                return Err(
+                    "Only time series and clustered collections expire documents".to_string(),
+                );
+            }
+            options.expire_after_seconds = Some(Duration::from_secs(
+                parse_positive(&self.expire_after_seconds)
+                    .ok_or("Expire after must be a positive number of seconds")?,
+            ));
+        }
+
+        Ok(NewCollection::Collection {
+            database,
+            name,
+            options: Box::new(options),
+        })
+    }
+
+    pub fn show(&mut self, ctx: &Context) -> CreateCollectionOutcome {
+        let mut submitted = false;
+        let mut cancelled = false;
+        let title = match (self.kind, self.new_database) {
+            (Kind::View, _) => "Create View",
+            (Kind::Collection, true) => "Create Database",
+            (Kind::Collection, false) => "Create Collection",
+        };
+        Window::new(title)
+            .collapsible(false)
+            .resizable(false)
+            .default_width(420.0)
+            .show(ctx, |ui| {
+                Grid::new("create_collection_names")
+                    .num_columns(2)
+                    .spacing([10.0, 6.0])
+                    .show(ui, |ui| {
+                        ui.label("Database:");
+                        ui.add_enabled(self.new_database, TextEdit::singleline(&mut self.database));
+                        ui.end_row();
+                        ui.label(match self.kind {
+                            Kind::Collection => "Collection:",
+                            Kind::View => "View:",
+                        });
+                        ui.text_edit_singleline(&mut self.name);
+                        ui.end_row();
+                    });
+                if self.new_database {
+                    ui.label(
+                        RichText::new("A database is created with its first collection.")
+                            .small()
+                            .weak(),
+                    );
+                }
+                ui.add_space(5.0);
+
+                match self.kind {
+                    Kind::Collection => self.render_collection_options(ui),
+                    Kind::View => self.render_view_options(ui),
+                }
+
+                if let Some(error) = &self.error {
+                    ui.add_space(5.0);
+                    ui.label(RichText::new(error).color(self.theme.danger_color));
+                }
+                ui.add_space(5.0);
+                ui.with_layout(Layout::right_to_left(Align::TOP), |ui| {
+                    if ThemedButton::new("Create", Arc::clone(&self.theme))
+                        .ui(ui)
+                        .clicked()
+                    {
+                        submitted = true;
+                    }
+                    if ThemedButton::new("Cancel", Arc::clone(&self.theme))
+                        .ui(ui)
+                        .clicked()
+                    {
+                        cancelled = true;
+                    }
+                });
+            });
+
+        if cancelled || ctx.input(|i| i.key_pressed(Key::Escape)) {
+            return CreateCollectionOutcome::Cancelled;
+        }
+        if submitted {
+            match self.build() {
+                Ok(collection) => return CreateCollectionOutcome::Submitted(collection),
+                Err(error) => self.error = Some(error),
+            }
+        }
+        CreateCollectionOutcome::Pending
+    }
+
+    fn render_collection_options(&mut self, ui: &mut Ui) {
+        ui.checkbox(&mut self.capped, "Capped")
+            .on_hover_text("A fixed-size collection that overwrites its oldest documents");
+        if self.capped {
+            Grid::new("create_collection_capped")
+                .num_columns(2)
+                .show(ui, |ui| {
+                    ui.label("Size (bytes):");
+                    ui.text_edit_singleline(&mut self.capped_size);
+                    ui.end_row();
+                    ui.label("Max documents:");
+                    ui.add(TextEdit::singleline(&mut self.capped_max).hint_text("No limit"));
+                    ui.end_row();
+                });
+        }
+
+        ui.checkbox(&mut self.clustered, "Clustered index on _id")
+            .on_hover_text("Stores documents ordered by _id (MongoDB 5.3+)");
+
+        ui.checkbox(&mut self.timeseries, "Time series");
+        if self.timeseries {
+            Grid::new("create_collection_timeseries")
+                .num_columns(2)
+                .show(ui, |ui| {
+                    ui.label("Time field:");
+                    ui.text_edit_singleline(&mut self.time_field);
+                    ui.end_row();
+                    ui.label("Meta field:");
+                    ui.add(TextEdit::singleline(&mut self.meta_field).hint_text("Optional"));
+                    ui.end_row();
+                    ui.label("Granularity:");
+                    ComboBox::from_id_source("create_collection_granularity")
+                        .selected_text(self.granularity.label())
+                        .show_ui(ui, |ui| {
+                            for granularity in Granularity::ALL {
+                                ui.selectable_value(
+                                    &mut self.granularity,
+                                    granularity,
+                                    granularity.label(),
+                                );
+                            }
+                        });
+                    ui.end_row();
+                });
+        }
+        if self.timeseries || self.clustered {
+            ui.horizontal(|ui| {
+                ui.label("Expire after (seconds):");
+                ui.add(TextEdit::singleline(&mut self.expire_after_seconds).hint_text("Never"));
+            });
+        }
+
+        ui.add_space(5.0);
+        ui.label("Validator:");
+        ui.add(
+            TextEdit::multiline(&mut self.validator)
+                .code_editor()
+                .desired_rows(3)
+                .desired_width(f32::INFINITY)
+                .hint_text(r#"{ "$jsonSchema": { "required": ["name"] } }"#),
+        );
+        ui.label("Collation:");
+        ui.add(
+            TextEdit::singleline(&mut self.collation)
+                .code_editor()
+                .desired_width(f32::INFINITY)
+                .hint_text(r#"{ "locale": "en", "strength": 2 }"#),
+        );
+    }
+
+    fn render_view_options(&mut self, ui: &mut Ui) {
+        ui.horizontal(|ui| {
+            ui.label("Source collection:");
+            if self.collections.is_empty() {
+                ui.text_edit_singleline(&mut self.view_on);
+            } else {
+                ComboBox::from_id_source("create_view_source")
+                    .width(220.0)
+                    .selected_text(&self.view_on)
+                    .show_ui(ui, |ui| {
+                        for collection in &self.collections {
+                            ui.selectable_value(&mut self.view_on, collection.clone(), collection);
+                        }
+                    });
+            }
+        });
+        ui.label("Pipeline:");
+        ui.add(
+            TextEdit::multiline(&mut self.pipeline)
+                .code_editor()
+                .desired_rows(6)
+                .desired_width(f32::INFINITY),
+        );
+    }
+}
+
+fn parse_positive(text: &str) -> Option<u64> {
+    text.trim().parse().ok().filter(|value| *value > 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mongodb::bson::doc;
+
+    fn dialog() -> CreateCollectionDialog {
+        let mut dialog = CreateCollectionDialog::collection(
+            Some("shop".to_string()),
+            Arc::new(Theme::google_theme()),
+        );
+        dialog.name = "orders".to_string();
+        dialog
+    }
+
+    #[test]
+    fn builds_collection_options() {
+        let mut dialog = dialog();
+        dialog.capped = true;
+        dialog.capped_size = "1048576".to_string();
+        dialog.validator = r#"{ "$jsonSchema": { "required": ["total"] } }"#.to_string();
+        dialog.collation = r#"{ "locale": "fr", "strength": 2 }"#.to_string();
+
+        let NewCollection::Collection { options, .. } = dialog.build().unwrap() else {
+            panic!("expected a collection");
+        };
+        assert_eq!(options.capped, Some(true));
+        assert_eq!(options.size, Some(1048576));
+        assert_eq!(options.max, None);
+        assert_eq!(
+            options.validator,
+            Some(doc! { "$jsonSchema": { "required": ["total"] } })
+        );
+        assert_eq!(options.collation.unwrap().locale, "fr");
+    }
+
+    #[test]
+    fn rejects_conflicting_options() {
+        let mut dialog = dialog();
+        dialog.capped = true;
+        assert!(dialog.build().unwrap_err().contains("maximum size"));
+
+        dialog.capped_size = "4096".to_string();
+        dialog.timeseries = true;
+        dialog.time_field = "at".to_string();
+        assert_eq!(
+            dialog.build().unwrap_err(),
+            "Time series collections can't be capped"
+        );
+
+        dialog.capped = false;
+        dialog.name = "system.orders".to_string();
+        assert_eq!(
+            dialog.build().unwrap_err(),
+            "The 'system.' prefix is reserved"
+        );
+    }
+}
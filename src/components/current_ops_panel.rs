@@ -0,0 +1,298 @@
+use crate::components::{ConfirmationDialog, ConfirmationOutcome, ThemedButton};
+use crate::models::{ConnectionEntry, CurrentOpFilter, CurrentOperation};
+use crate::services::DatabaseService;
+use crate::theme::Theme;
+use crate::utils::error::Result;
+use crate::utils::format::format_duration;
+use crate::utils::task::BackgroundTask;
+use egui::{Context, DragValue, Grid, RichText, ScrollArea, TextEdit, Ui};
+use mongodb::bson::Bson;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+const AUTO_REFRESH_INTERVAL: Duration = Duration::from_secs(2);
+
+struct PendingKill {
+    opid: Bson,
+    dialog: ConfirmationDialog,
+}
+
+/// Bottom panel listing the operations running on the active tab's
+/// connection, with a confirmed `killOp` for each.
+pub struct CurrentOpsPanel {
+    visible: bool,
+    connection_id: Option<String>,
+    service: Option<Arc<DatabaseService>>,
+    read_only: bool,
+    filter: CurrentOpFilter,
+    auto_refresh: bool,
+    last_refresh: Option<Instant>,
+    operations: Vec<CurrentOperation>,
+    task: Option<BackgroundTask<Result<Vec<CurrentOperation>>>>,
+    pending_kill: Option<PendingKill>,
+    kill_task: Option<BackgroundTask<Result<()>>>,
+    /// The outcome of the last refresh or kill, and whether it failed.
+    message: Option<(String, bool)>,
+    theme: Arc<Theme>,
+}
+
+impl CurrentOpsPanel {
+    pub fn new(theme: Arc<Theme>) -> Self {
+        Self {
+            visible: false,
+            connection_id: None,
+            service: None,
+            read_only: false,
+            filter: CurrentOpFilter::default(),
+            auto_refresh: false,
+            last_refresh: None,
+            operations: Vec::new(),
+            task: None,
+            pending_kill: None,
+            kill_task: None,
+            message: None,
+            theme,
+        }
+    }
+
+    /// Points the panel at the active tab's connection, if it is open.
+    pub fn set_connection(&mut self, connection: Option<&ConnectionEntry>) {
+        let connection = connection.filter(|entry| entry.is_connected());
+        let id = connection.map(|entry| entry.id.clone());
+        if id != self.connection_id {
+            self.connection_id = id;
+            self.operations.clear();
+            self.task = None;
+            self.pending_kill = None;
+            self.last_refresh = None;
+            self.message = None;
+        }
+        self.service = connection.map(|entry| Arc::clone(&entry.service));
+        self.read_only = connection.is_some_and(|entry| entry.read_only());
+    }
+
+    /// The footer button that shows and hides the panel.
+    pub fn render_toggle(&mut self, ui: &mut Ui) {
+        ui.toggle_value(&mut self.visible, "Current Ops")
+            .on_hover_text("Operations running on this tab's connection");
+    }
+
+    fn refresh(&mut self, ctx: &Context) {
+        let Some(service) = self.service.clone() else {
+            return;
+        };
+        let filter = self.filter.clone();
+        self.last_refresh = Some(Instant::now());
+        self.task = Some(BackgroundTask::spawn(ctx, async move {
+            service.current_operations(&filter).await
+        }));
+    }
+
+    pub fn show(&mut self, ctx: &Context) {
+        if !self.visible {
+            return;
+        }
+        if let Some(result) = self.task.as_mut().and_then(|task| task.poll()) {
+            self.task = None;
+            match result {
+                Ok(operations) => {
+                    self.operations = operations;
+                    if self.message.as_ref().is_some_and(|(_, failed)| *failed) {
+                        self.message = None;
+                    }
+                }
+                Err(e) => self.message = Some((e.to_string(), true)),
+            }
+        }
+        if let Some(result) = self.kill_task.as_mut().and_then(|task| task.poll()) {
+            self.kill_task = None;
+            self.message = Some(match result {
+                Ok(()) => ("Kill requested".to_string(), false),
+                Err(e) => (e.to_string(), true),
+            });
+            self.refresh(ctx);
+        }
+
+        if self.service.is_some() && self.task.is_none() {
+            match self.last_refresh {
+                None => self.refresh(ctx),
+                Some(last) if self.auto_refresh => {
+                    let elapsed = last.elapsed();
+                    if elapsed >= AUTO_REFRESH_INTERVAL {
+                        self.refresh(ctx);
+                    } else {
+                        ctx.request_repaint_after(AUTO_REFRESH_INTERVAL - elapsed);
+                    }
+                }
+                Some(_) => {}
+            }
+        }
+
+        egui::TopBottomPanel::bottom("current_ops")
+            .resizable(true)
+            .default_height(220.0)
+            .show(ctx, |ui| self.render_contents(ui));
+        self.show_kill_dialog(ctx);
+    }
+
+    fn render_contents(&mut self, ui: &mut Ui) {
+        let mut refresh = false;
+        ui.horizontal(|ui| {
+            ui.label(
+                RichText::new("Current operations")
+                    .color(self.theme.text_color)
+                    .strong(),
+            );
+            ui.add_space(10.0);
+            refresh |= ui
+                .checkbox(&mut self.filter.active_only, "Active only")
+                .changed();
+            ui.label("Running ≥");
+            refresh |= ui
+                .add(
+                    DragValue::new(&mut self.filter.min_seconds)
+                        .range(0..=86_400)
+                        .suffix(" s"),
+                )
+                .changed();
+            ui.label("Namespace:");
+            refresh |= ui
+                .add(TextEdit::singleline(&mut self.filter.namespace).desired_width(120.0))
+                .lost_focus();
+            ui.label("Client:");
+            refresh |= ui
+                .add(TextEdit::singleline(&mut self.filter.client).desired_width(120.0))
+                .on_hover_text("Part of the client's address or application name")
+                .lost_focus();
+            ui.checkbox(&mut self.auto_refresh, "Auto-refresh");
+            refresh |= ui
+                .add_enabled(
+                    self.service.is_some() && self.task.is_none(),
+                    ThemedButton::new("Refresh", Arc::clone(&self.theme)),
+                )
+                .clicked();
+            if self.task.is_some() || self.kill_task.is_some() {
+                ui.spinner();
+            }
+        });
+        if refresh && self.task.is_none() {
+            self.refresh(ui.ctx());
+        }
+
+        if self.service.is_none() {
+            ui.label(RichText::new("Connect this tab to see its server's operations.").weak());
+            return;
+        }
+        if let Some((message, failed)) = &self.message {
+            let color = if *failed {
+                self.theme.danger_color
+            } else {
+                self.theme.accent_color
+            };
+            ui.label(RichText::new(message).color(color));
+        }
+
+        let mut kill = None;
+        ScrollArea::both()
+            .auto_shrink([false, true])
+            .show(ui, |ui| {
+                Grid::new("current_ops_grid")
+                    .num_columns(7)
+                    .striped(true)
+                    .show(ui, |ui| {
+                        for header in ["Op id", "Op", "Namespace", "Running", "Plan", "Client", ""]
+                        {
+                            ui.label(RichText::new(header).strong());
+                        }
+                        ui.end_row();
+                        for operation in &self.operations {
+                            ui.label(operation.opid_label());
+                            let op = ui.label(&operation.op);
+                            if let Some(command) = &operation.command {
+                                op.on_hover_text(
+                                    Bson::Document(command.clone())
+                                        .into_relaxed_extjson()
+                                        .to_string(),
+                                );
+                            }
+                            ui.label(&operation.namespace);
+                            ui.label(
+                                operation
+                                    .running_micros
+                                    .map(format_duration)
+                                    .unwrap_or_default(),
+                            );
+                            ui.label(&operation.plan_summary);
+                            let client = ui.label(&operation.client);
+                            if !operation.app_name.is_empty() {
+                                client.on_hover_text(&operation.app_name);
+                            }
+                            if operation.can_kill()
+                                && ui
+                                    .add_enabled(
+                                        !self.read_only && self.kill_task.is_none(),
+                                        egui::Button::new(
+                                            RichText::new("Kill").color(self.theme.danger_color),
+                                        ),
+                                    )
+                                    .on_disabled_hover_text("Not on a read-only connection")
+                                    .clicked()
+                            {
+                                kill = Some(operation.clone());
+                            }
+                            ui.end_row();
+                        }
+                    });
+                if self.operations.is_empty() && self.task.is_none() {
+                    ui.label(RichText::new("No operations match the filter").weak());
+                }
+            });
+
+        if let Some(operation) = kill {
+            let running = operation
+                .running_micros
+                .map(|micros| format!(", running for {}", format_duration(micros)))
+                .unwrap_or_default();
+            let message = format!(
+                "Kill operation {} ({} on {}{}) from {}?",
+                operation.opid_label(),
+                operation.op,
+                if operation.namespace.is_empty() {
+                    "no namespace"
+                } else {
+                    &operation.namespace
+                },
+                running,
+                operation.client,
+            );
+            self.pending_kill = Some(PendingKill {
+                opid: operation.opid,
+                dialog: ConfirmationDialog::new("Kill operation", message, Arc::clone(&self.theme)),
+            });
+        }
+    }
+
+    fn show_kill_dialog(&mut self, ctx: &Context) {
+        let Some(pending) = &mut self.pending_kill else {
+            return;
+        };
+        match pending.dialog.show(ctx) {
+            ConfirmationOutcome::Confirmed => {
+                let opid = pending.opid.clone();
+                self.pending_kill = None;
+                let Some(service) = self.service.clone() else {
+                    return;
+                };
+                self.kill_task = Some(BackgroundTask::spawn(ctx, async move {
+                    service.kill_operation(opid).await
+                }));
+            }
+            ConfirmationOutcome::Cancelled => self.pending_kill = None,
+            ConfirmationOutcome::Pending => {}
+        }
+    }
+
+    pub fn update_theme(&mut self, theme: Arc<Theme>) {
+        self.theme = theme;
+    }
+}
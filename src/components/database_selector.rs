@@ -6,6 +6,7 @@ use std::sync::Arc;
 pub struct DatabaseSelector {
     selected_database: String,
     databases: Vec<String>,
+    refresh_requested: bool,
     theme: Arc<Theme>,
 }
 
@@ -14,9 +15,33 @@ impl DatabaseSelector {
         Self {
             selected_database: String::new(),
             databases: Vec::new(),
+            refresh_requested: false,
             theme,
         }
     }
+
+    pub fn set_databases(&mut self, databases: Vec<String>) {
+        if !databases.contains(&self.selected_database) {
+            self.selected_database.clear();
+        }
+        self.databases = databases;
+    }
+
+    pub fn databases(&self) -> &[String] {
+        &self.databases
+    }
+
+    pub fn selected(&self) -> &str {
+        &self.selected_database
+    }
+
+    pub fn select(&mut self, database: String) {
+        self.selected_database = database;
+    }
+
+    pub fn take_refresh_request(&mut self) -> bool {
+        std::mem::take(&mut self.refresh_requested)
+    }
 }
 
 impl Component for DatabaseSelector {
@@ -40,7 +65,7 @@ impl Component for DatabaseSelector {
                 .ui(ui)
                 .clicked()
             {
-                // Refresh database list
+                self.refresh_requested = true;
             }
         });
     }
@@ -0,0 +1,1165 @@
+use crate::components::{
+    collection_name_error, AdminRequest, ConfirmationDialog, ConfirmationOutcome,
+    CreateCollectionDialog, CreateCollectionOutcome, NewCollection, ProfilerRequest, StatsRequest,
+    ThemedButton, ValidationRequest,
+};
+use crate::models::{ConnectionEntry, ConnectionRegistry, ConnectionStatus};
+use crate::services::{CollectionInfo, CollectionKind, DatabaseService, IndexInfo};
+use crate::theme::Theme;
+use crate::utils::error::Result;
+use crate::utils::format::{format_bytes, format_count};
+use crate::utils::task::BackgroundTask;
+use egui::collapsing_header::CollapsingState;
+use egui::{
+    Align, Context, Frame, Id, Key, Label, Layout, RichText, ScrollArea, Sense, Stroke, Ui, Widget,
+    Window,
+};
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::Arc;
+
+const GRIDFS_FILES_SUFFIX: &str = ".files";
+const GRIDFS_CHUNKS_SUFFIX: &str = ".chunks";
+
+/// A query tab to open from the explorer, on a database or collection.
+pub struct OpenTabRequest {
+    pub connection_id: String,
+    pub database: Option<String>,
+    pub collection: Option<String>,
+}
+
+/// Data that is fetched the first time its node is expanded.
+enum Loadable<T> {
+    Loading(BackgroundTask<Result<T>>),
+    Loaded(T),
+    Failed(String),
+}
+
+impl<T: Send + 'static> Loadable<T> {
+    fn spawn<F>(ctx: &Context, future: F) -> Self
+    where
+        F: Future<Output = Result<T>> + Send + 'static,
+    {
+        Loadable::Loading(BackgroundTask::spawn(ctx, future))
+    }
+
+    fn poll(&mut self) {
+        if let Loadable::Loading(task) = self {
+            if let Some(result) = task.poll() {
+                *self = match result {
+                    Ok(value) => Loadable::Loaded(value),
+                    Err(e) => Loadable::Failed(e.to_string()),
+                };
+            }
+        }
+    }
+}
+
+/// What the explorer has loaded below one connection.
+struct ConnectionTree {
+    /// The service the data came from; a reconnect replaces it, which
+    /// invalidates everything loaded before.
+    service: Arc<DatabaseService>,
+    collections: HashMap<String, Loadable<Vec<CollectionInfo>>>,
+    indexes: HashMap<(String, String), Loadable<Vec<IndexInfo>>>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Target {
+    Database(String),
+    Collection(String, String),
+    View(String, String),
+    GridFsBucket(String, String),
+}
+
+impl Target {
+    fn database(&self) -> &str {
+        match self {
+            Target::Database(database)
+            | Target::Collection(database, _)
+            | Target::View(database, _)
+            | Target::GridFsBucket(database, _) => database,
+        }
+    }
+
+    fn name(&self) -> &str {
+        match self {
+            Target::Database(name)
+            | Target::Collection(_, name)
+            | Target::View(_, name)
+            | Target::GridFsBucket(_, name) => name,
+        }
+    }
+
+    fn kind(&self) -> &'static str {
+        match self {
+            Target::Database(_) => "database",
+            Target::Collection(..) => "collection",
+            Target::View(..) => "view",
+            Target::GridFsBucket(..) => "GridFS bucket",
+        }
+    }
+
+    fn namespace(&self) -> String {
+        match self {
+            Target::Database(database) => database.clone(),
+            _ => format!("{}.{}", self.database(), self.name()),
+        }
+    }
+}
+
+enum Action {
+    OpenTab(OpenTabRequest),
+    Refresh(String),
+    Disconnect(String),
+    Monitor(String),
+    Topology(String),
+    Profiler(String, String),
+    Admin(String, String),
+    Validation(String, String, String),
+    Stats(String, Target),
+    Drop(String, Target),
+    Rename(String, String, String),
+    /// Creates a collection, in a new database when none is given.
+    CreateCollection(String, Option<String>),
+    CreateView(String, String),
+}
+
+struct PendingCreate {
+    service: Arc<DatabaseService>,
+    connection_id: String,
+    dialog: CreateCollectionDialog,
+}
+
+struct PendingDrop {
+    service: Arc<DatabaseService>,
+    connection_id: String,
+    target: Target,
+    dialog: ConfirmationDialog,
+}
+
+struct PendingRename {
+    service: Arc<DatabaseService>,
+    connection_id: String,
+    database: String,
+    collection: String,
+    new_name: String,
+    focus_requested: bool,
+}
+
+struct Operation {
+    connection_id: String,
+    database: String,
+    running_message: String,
+    done_message: String,
+    task: BackgroundTask<Result<()>>,
+}
+
+/// Left sidebar with the open connections and, below each, a tree of its
+/// databases, collections, views, GridFS buckets and indexes. Levels are
+/// loaded the first time they are expanded.
+pub struct ExplorerSidebar {
+    filter: String,
+    trees: HashMap<String, ConnectionTree>,
+    actions: Vec<Action>,
+    pending_drop: Option<PendingDrop>,
+    pending_rename: Option<PendingRename>,
+    pending_create: Option<PendingCreate>,
+    operation: Option<Operation>,
+    /// The outcome of the last drop or rename, and whether it failed.
+    message: Option<(String, bool)>,
+    disconnect_request: Option<String>,
+    new_tab_request: Option<OpenTabRequest>,
+    refresh_request: Option<String>,
+    stats_request: Option<StatsRequest>,
+    monitor_request: Option<String>,
+    topology_request: Option<String>,
+    profiler_request: Option<ProfilerRequest>,
+    validation_request: Option<ValidationRequest>,
+    admin_request: Option<AdminRequest>,
+    theme: Arc<Theme>,
+}
+
+impl ExplorerSidebar {
+    pub fn new(theme: Arc<Theme>) -> Self {
+        Self {
+            filter: String::new(),
+            trees: HashMap::new(),
+            actions: Vec::new(),
+            pending_drop: None,
+            pending_rename: None,
+            pending_create: None,
+            operation: None,
+            message: None,
+            disconnect_request: None,
+            new_tab_request: None,
+            refresh_request: None,
+            stats_request: None,
+            monitor_request: None,
+            topology_request: None,
+            profiler_request: None,
+            validation_request: None,
+            admin_request: None,
+            theme,
+        }
+    }
+
+    /// Returns the id of the connection whose Disconnect button was clicked.
+    pub fn take_disconnect_request(&mut self) -> Option<String> {
+        self.disconnect_request.take()
+    }
+
+    /// Returns the connection, and optionally the database and collection,
+    /// to open a new query tab on.
+    pub fn take_new_tab_request(&mut self) -> Option<OpenTabRequest> {
+        self.new_tab_request.take()
+    }
+
+    /// Returns the id of a connection whose database list is out of date.
+    pub fn take_refresh_request(&mut self) -> Option<String> {
+        self.refresh_request.take()
+    }
+
+    /// Returns the database or collection whose stats were asked for.
+    pub fn take_stats_request(&mut self) -> Option<StatsRequest> {
+        self.stats_request.take()
+    }
+
+    /// Returns the id of a connection whose server status should be charted.
+    pub fn take_monitor_request(&mut self) -> Option<String> {
+        self.monitor_request.take()
+    }
+
+    /// Returns the id of a connection whose topology should be shown.
+    pub fn take_topology_request(&mut self) -> Option<String> {
+        self.topology_request.take()
+    }
+
+    /// Returns the database whose profiler should be shown.
+    pub fn take_profiler_request(&mut self) -> Option<ProfilerRequest> {
+        self.profiler_request.take()
+    }
+
+    /// Returns the collection whose validation rules should be edited.
+    pub fn take_validation_request(&mut self) -> Option<ValidationRequest> {
+        self.validation_request.take()
+    }
+
+    /// Returns the database whose users and roles should be managed.
+    pub fn take_admin_request(&mut self) -> Option<AdminRequest> {
+        self.admin_request.take()
+    }
+
+    /// `active_connection` is highlighted as the one the current tab uses.
+    pub fn render(
+        &mut self,
+        ui: &mut Ui,
+        registry: &ConnectionRegistry,
+        active_connection: Option<&str>,
+    ) {
+        self.sync_trees(registry);
+        self.poll_operation();
+
+        ui.add_space(5.0);
+        ui.label(
+            RichText::new("Connections")
+                .color(self.theme.text_color)
+                .strong(),
+        );
+        ui.add(
+            egui::TextEdit::singleline(&mut self.filter)
+                .hint_text("Filter databases and collections")
+                .desired_width(f32::INFINITY),
+        )
+        .on_hover_text("Collections are matched in databases that have been expanded once");
+        if let Some((message, failed)) = &self.message {
+            let color = if *failed {
+                self.theme.danger_color
+            } else {
+                self.theme.text_color
+            };
+            let mut dismiss = false;
+            ui.horizontal_wrapped(|ui| {
+                ui.label(RichText::new(message).color(color).small());
+                dismiss = ui.small_button("×").clicked();
+            });
+            if dismiss {
+                self.message = None;
+            }
+        }
+        if let Some(operation) = &self.operation {
+            ui.horizontal(|ui| {
+                ui.spinner();
+                ui.label(RichText::new(&operation.running_message).small().weak());
+            });
+        }
+        ui.separator();
+
+        let mut connections = registry.iter().peekable();
+        if connections.peek().is_none() {
+            ui.label(
+                RichText::new("Not connected. Connections opened with Connect are listed here.")
+                    .color(self.theme.text_color)
+                    .small(),
+            );
+        } else {
+            ScrollArea::vertical().show(ui, |ui| {
+                for entry in connections {
+                    let is_active = active_connection == Some(entry.id.as_str());
+                    self.render_connection(ui, entry, is_active);
+                    ui.add_space(4.0);
+                }
+            });
+        }
+
+        let actions = std::mem::take(&mut self.actions);
+        for action in actions {
+            self.apply(registry, action);
+        }
+        self.show_drop_confirmation(ui.ctx());
+        self.show_rename_prompt(ui.ctx());
+        self.show_create_dialog(ui.ctx());
+    }
+
+    /// Drops what was loaded for connections that closed or reconnected.
+    fn sync_trees(&mut self, registry: &ConnectionRegistry) {
+        self.trees.retain(|id, tree| {
+            registry
+                .get(id)
+                .is_some_and(|entry| Arc::ptr_eq(&entry.service, &tree.service))
+        });
+        for entry in registry.iter() {
+            self.trees
+                .entry(entry.id.clone())
+                .or_insert_with(|| ConnectionTree {
+                    service: Arc::clone(&entry.service),
+                    collections: HashMap::new(),
+                    indexes: HashMap::new(),
+                });
+        }
+        for tree in self.trees.values_mut() {
+            tree.collections.values_mut().for_each(Loadable::poll);
+            tree.indexes.values_mut().for_each(Loadable::poll);
+        }
+    }
+
+    fn poll_operation(&mut self) {
+        let Some(operation) = &mut self.operation else {
+            return;
+        };
+        let Some(result) = operation.task.poll() else {
+            return;
+        };
+        let operation = self.operation.take().expect("operation checked above");
+        if let Some(tree) = self.trees.get_mut(&operation.connection_id) {
+            tree.collections.remove(&operation.database);
+            tree.indexes
+                .retain(|(database, _), _| *database != operation.database);
+        }
+        self.message = Some(match result {
+            Ok(()) => (operation.done_message, false),
+            Err(e) => (format!("Error: {}", e), true),
+        });
+        self.refresh_request = Some(operation.connection_id);
+    }
+
+    fn render_connection(&mut self, ui: &mut Ui, entry: &ConnectionEntry, is_active: bool) {
+        let theme = Arc::clone(&self.theme);
+        let mut frame = Frame::none()
+            .inner_margin(4.0)
+            .rounding(theme.frame_rounding);
+        if is_active {
+            frame = frame.stroke(Stroke::new(1.0, theme.accent_color));
+        }
+
+        frame.show(ui, |ui| {
+            ui.set_width(ui.available_width());
+            let id = Id::new(("explorer_connection", &entry.id));
+            CollapsingState::load_with_default_open(ui.ctx(), id, true)
+                .show_header(ui, |ui| {
+                    let status_color = match entry.status {
+                        ConnectionStatus::Connected => theme.accent_color,
+                        ConnectionStatus::Failed(_) => theme.danger_color,
+                        ConnectionStatus::Connecting | ConnectionStatus::Disconnecting => {
+                            theme.warning_color
+                        }
+                    };
+                    ui.label(RichText::new("●").color(status_color));
+                    let response = ui.add(
+                        Label::new(RichText::new(&entry.name).color(theme.text_color).strong())
+                            .sense(Sense::click()),
+                    );
+                    response.context_menu(|ui| {
+                        if ui
+                            .add_enabled(entry.is_connected(), egui::Button::new("New Tab"))
+                            .clicked()
+                        {
+                            self.actions.push(Action::OpenTab(OpenTabRequest {
+                                connection_id: entry.id.clone(),
+                                database: None,
+                                collection: None,
+                            }));
+                            ui.close_menu();
+                        }
+                        if ui
+                            .add_enabled(entry.is_connected(), egui::Button::new("Refresh"))
+                            .clicked()
+                        {
+                            self.actions.push(Action::Refresh(entry.id.clone()));
+                            ui.close_menu();
+                        }
+                        if ui
+                            .add_enabled(entry.is_connected(), egui::Button::new("Monitor"))
+                            .clicked()
+                        {
+                            self.actions.push(Action::Monitor(entry.id.clone()));
+                            ui.close_menu();
+                        }
+                        if ui
+                            .add_enabled(entry.is_connected(), egui::Button::new("Topology"))
+                            .clicked()
+                        {
+                            self.actions.push(Action::Topology(entry.id.clone()));
+                            ui.close_menu();
+                        }
+                        if ui
+                            .add_enabled(
+                                entry.is_connected() && !entry.read_only(),
+                                egui::Button::new("Create Database…"),
+                            )
+                            .on_disabled_hover_text("Needs a writable, open connection")
+                            .clicked()
+                        {
+                            self.actions
+                                .push(Action::CreateCollection(entry.id.clone(), None));
+                            ui.close_menu();
+                        }
+                        if ui.button("Disconnect").clicked() {
+                            self.actions.push(Action::Disconnect(entry.id.clone()));
+                            ui.close_menu();
+                        }
+                    });
+                })
+                .body(|ui| {
+                    self.render_connection_details(ui, entry);
+                    if entry.is_connected() {
+                        for database in &entry.databases {
+                            self.render_database(ui, entry, &database.name, database.size_on_disk);
+                        }
+                    }
+                });
+        });
+    }
+
+    fn render_connection_details(&mut self, ui: &mut Ui, entry: &ConnectionEntry) {
+        ui.horizontal_wrapped(|ui| {
+            if let Some(color) = self.theme.environment_color(entry.environment()) {
+                ui.label(
+                    RichText::new(entry.environment().label().to_uppercase())
+                        .color(color)
+                        .small()
+                        .strong(),
+                );
+            }
+            if entry.read_only() {
+                ui.label(
+                    RichText::new("🔒 Read-only")
+                        .color(self.theme.danger_color)
+                        .small(),
+                );
+            }
+            let status = match &entry.status {
+                ConnectionStatus::Connected => {
+                    format!("{} databases", entry.databases.len())
+                }
+                status => status.label(),
+            };
+            ui.label(RichText::new(status).color(self.theme.text_color).small());
+        });
+        ui.horizontal(|ui| {
+            if ui
+                .add_enabled(entry.is_connected(), egui::Button::new("New Tab"))
+                .clicked()
+            {
+                self.actions.push(Action::OpenTab(OpenTabRequest {
+                    connection_id: entry.id.clone(),
+                    database: None,
+                    collection: None,
+                }));
+            }
+            let label = match entry.status {
+                ConnectionStatus::Connecting => "Cancel",
+                ConnectionStatus::Failed(_) if !entry.service.is_connected() => "Remove",
+                _ => "Disconnect",
+            };
+            let disconnecting = entry.status == ConnectionStatus::Disconnecting;
+            if ui
+                .add_enabled(!disconnecting, egui::Button::new(label))
+                .clicked()
+            {
+                self.actions.push(Action::Disconnect(entry.id.clone()));
+            }
+        });
+    }
+
+    fn render_database(
+        &mut self,
+        ui: &mut Ui,
+        entry: &ConnectionEntry,
+        database: &str,
+        size_on_disk: u64,
+    ) {
+        let filter = self.filter.trim().to_lowercase();
+        let tree = self.trees.get(&entry.id);
+        let loaded = tree
+            .and_then(|tree| tree.collections.get(database))
+            .and_then(|collections| match collections {
+                Loadable::Loaded(collections) => Some(collections),
+                _ => None,
+            });
+        let database_matches = filter.is_empty() || database.to_lowercase().contains(&filter);
+        let collection_matches = !database_matches
+            && loaded.is_some_and(|collections| {
+                collections
+                    .iter()
+                    .any(|collection| collection.name.to_lowercase().contains(&filter))
+            });
+        if !database_matches && !collection_matches {
+            return;
+        }
+
+        let target = Target::Database(database.to_string());
+        let id = Id::new(("explorer_database", &entry.id, database));
+        let mut state = CollapsingState::load_with_default_open(ui.ctx(), id, false);
+        if collection_matches {
+            state.set_open(true);
+        }
+        state
+            .show_header(ui, |ui| {
+                let response = ui.add(
+                    Label::new(RichText::new(database).color(self.theme.text_color))
+                        .sense(Sense::click()),
+                );
+                self.node_menu(&response, entry, &target);
+                badge(ui, &format_bytes(size_on_disk));
+            })
+            .body(|ui| {
+                let filter = if database_matches { "" } else { &filter };
+                self.render_collections(ui, entry, database, filter);
+            });
+    }
+
+    fn render_collections(
+        &mut self,
+        ui: &mut Ui,
+        entry: &ConnectionEntry,
+        database: &str,
+        filter: &str,
+    ) {
+        let Some(tree) = self.trees.get_mut(&entry.id) else {
+            return;
+        };
+        let collections = tree
+            .collections
+            .entry(database.to_string())
+            .or_insert_with(|| {
+                let service = Arc::clone(&entry.service);
+                let database = database.to_string();
+                Loadable::spawn(ui.ctx(), async move {
+                    service.list_collections(&database).await
+                })
+            });
+        let collections = match collections {
+            Loadable::Loading(_) => {
+                ui.spinner();
+                return;
+            }
+            Loadable::Failed(error) => {
+                ui.label(
+                    RichText::new(format!("Error: {}", error))
+                        .color(self.theme.danger_color)
+                        .small(),
+                );
+                return;
+            }
+            Loadable::Loaded(collections) => collections.clone(),
+        };
+        if collections.is_empty() {
+            ui.label(RichText::new("No collections").small().weak());
+            return;
+        }
+
+        let is_bucket_part = |name: &str, suffix: &str, other: &str| {
+            name.strip_suffix(suffix).is_some_and(|bucket| {
+                collections
+                    .iter()
+                    .any(|collection| collection.name == format!("{}{}", bucket, other))
+            })
+        };
+        for collection in &collections {
+            if !filter.is_empty() && !collection.name.to_lowercase().contains(filter) {
+                continue;
+            }
+            if is_bucket_part(&collection.name, GRIDFS_CHUNKS_SUFFIX, GRIDFS_FILES_SUFFIX) {
+                // Shown as part of its bucket
+                continue;
+            }
+            if is_bucket_part(&collection.name, GRIDFS_FILES_SUFFIX, GRIDFS_CHUNKS_SUFFIX) {
+                let bucket = &collection.name[..collection.name.len() - GRIDFS_FILES_SUFFIX.len()];
+                let target = Target::GridFsBucket(database.to_string(), bucket.to_string());
+                ui.horizontal(|ui| {
+                    ui.add_space(18.0);
+                    let response = ui.add(
+                        Label::new(RichText::new(bucket).color(self.theme.text_color))
+                            .sense(Sense::click()),
+                    );
+                    self.node_menu(&response, entry, &target);
+                    badge(ui, "GridFS");
+                    if let Some(count) = collection.document_count {
+                        badge(ui, &format!("{} files", format_count(count)));
+                    }
+                });
+                continue;
+            }
+            match collection.kind {
+                CollectionKind::View => {
+                    let target = Target::View(database.to_string(), collection.name.clone());
+                    ui.horizontal(|ui| {
+                        ui.add_space(18.0);
+                        let response = ui.add(
+                            Label::new(
+                                RichText::new(&collection.name)
+                                    .color(self.theme.text_color)
+                                    .italics(),
+                            )
+                            .sense(Sense::click()),
+                        );
+                        self.node_menu(&response, entry, &target);
+                        badge(ui, "view");
+                    });
+                }
+                CollectionKind::Collection | CollectionKind::Timeseries => {
+                    self.render_collection(ui, entry, database, collection);
+                }
+            }
+        }
+    }
+
+    fn render_collection(
+        &mut self,
+        ui: &mut Ui,
+        entry: &ConnectionEntry,
+        database: &str,
+        collection: &CollectionInfo,
+    ) {
+        let target = Target::Collection(database.to_string(), collection.name.clone());
+        let id = Id::new(("explorer_collection", &entry.id, database, &collection.name));
+        CollapsingState::load_with_default_open(ui.ctx(), id, false)
+            .show_header(ui, |ui| {
+                let response = ui.add(
+                    Label::new(RichText::new(&collection.name).color(self.theme.text_color))
+                        .sense(Sense::click()),
+                );
+                self.node_menu(&response, entry, &target);
+                if collection.kind == CollectionKind::Timeseries {
+                    badge(ui, "time series");
+                }
+                if let Some(count) = collection.document_count {
+                    badge(ui, &format!("{} docs", format_count(count)));
+                }
+            })
+            .body(|ui| {
+                self.render_indexes(ui, entry, database, &collection.name);
+            });
+    }
+
+    fn render_indexes(
+        &mut self,
+        ui: &mut Ui,
+        entry: &ConnectionEntry,
+        database: &str,
+        collection: &str,
+    ) {
+        let Some(tree) = self.trees.get_mut(&entry.id) else {
+            return;
+        };
+        let indexes = tree
+            .indexes
+            .entry((database.to_string(), collection.to_string()))
+            .or_insert_with(|| {
+                let service = Arc::clone(&entry.service);
+                let database = database.to_string();
+                let collection = collection.to_string();
+                Loadable::spawn(ui.ctx(), async move {
+                    service.list_indexes(&database, &collection).await
+                })
+            });
+        match indexes {
+            Loadable::Loading(_) => {
+                ui.spinner();
+            }
+            Loadable::Failed(error) => {
+                ui.label(
+                    RichText::new(format!("Error: {}", error))
+                        .color(self.theme.danger_color)
+                        .small(),
+                );
+            }
+            Loadable::Loaded(indexes) => {
+                for index in indexes.iter() {
+                    ui.horizontal(|ui| {
+                        ui.label(RichText::new(&index.name).color(self.theme.text_color))
+                            .on_hover_text(index.keys.to_string());
+                        if index.unique {
+                            badge(ui, "unique");
+                        }
+                        if let Some(size) = index.size {
+                            badge(ui, &format_bytes(size));
+                        }
+                    });
+                }
+            }
+        }
+    }
+
+    fn node_menu(&mut self, response: &egui::Response, entry: &ConnectionEntry, target: &Target) {
+        response.context_menu(|ui| {
+            let open_tab = match target {
+                Target::Database(database) => Some((database.clone(), None)),
+                Target::Collection(database, name) | Target::View(database, name) => {
+                    Some((database.clone(), Some(name.clone())))
+                }
+                Target::GridFsBucket(database, bucket) => Some((
+                    database.clone(),
+                    Some(format!("{}{}", bucket, GRIDFS_FILES_SUFFIX)),
+                )),
+            };
+            if let Some((database, collection)) = open_tab {
+                if ui.button("Open in New Tab").clicked() {
+                    self.actions.push(Action::OpenTab(OpenTabRequest {
+                        connection_id: entry.id.clone(),
+                        database: Some(database),
+                        collection,
+                    }));
+                    ui.close_menu();
+                }
+            }
+            if matches!(target, Target::Database(_) | Target::Collection(..))
+                && ui.button("Stats").clicked()
+            {
+                self.actions
+                    .push(Action::Stats(entry.id.clone(), target.clone()));
+                ui.close_menu();
+            }
+            if let Target::Database(database) = target {
+                if ui.button("Profiler…").clicked() {
+                    self.actions
+                        .push(Action::Profiler(entry.id.clone(), database.clone()));
+                    ui.close_menu();
+                }
+                if ui.button("Users & Roles…").clicked() {
+                    self.actions
+                        .push(Action::Admin(entry.id.clone(), database.clone()));
+                    ui.close_menu();
+                }
+            }
+            if let Target::Collection(database, collection) = target {
+                if ui.button("Validation…").clicked() {
+                    self.actions.push(Action::Validation(
+                        entry.id.clone(),
+                        database.clone(),
+                        collection.clone(),
+                    ));
+                    ui.close_menu();
+                }
+            }
+            ui.separator();
+
+            let writable = !entry.read_only();
+            let read_only_note = "The connection's profile is read-only";
+            if let Target::Database(database) = target {
+                if ui
+                    .add_enabled(writable, egui::Button::new("Create Collection…"))
+                    .on_disabled_hover_text(read_only_note)
+                    .clicked()
+                {
+                    self.actions.push(Action::CreateCollection(
+                        entry.id.clone(),
+                        Some(database.clone()),
+                    ));
+                    ui.close_menu();
+                }
+                if ui
+                    .add_enabled(writable, egui::Button::new("Create View…"))
+                    .on_disabled_hover_text(read_only_note)
+                    .clicked()
+                {
+                    self.actions
+                        .push(Action::CreateView(entry.id.clone(), database.clone()));
+                    ui.close_menu();
+                }
+            }
+            if let Target::Collection(database, collection) = target {
+                if ui
+                    .add_enabled(writable, egui::Button::new("Rename…"))
+                    .on_disabled_hover_text(read_only_note)
+                    .clicked()
+                {
+                    self.actions.push(Action::Rename(
+                        entry.id.clone(),
+                        database.clone(),
+                        collection.clone(),
+                    ));
+                    ui.close_menu();
+                }
+            }
+            if ui
+                .add_enabled(
+                    writable,
+                    egui::Button::new(format!("Drop {}…", target.kind())),
+                )
+                .on_disabled_hover_text(read_only_note)
+                .clicked()
+            {
+                self.actions
+                    .push(Action::Drop(entry.id.clone(), target.clone()));
+                ui.close_menu();
+            }
+        });
+    }
+
+    fn apply(&mut self, registry: &ConnectionRegistry, action: Action) {
+        let service = |connection_id: &str| {
+            registry
+                .get(connection_id)
+                .map(|entry| Arc::clone(&entry.service))
+        };
+        match action {
+            Action::OpenTab(request) => self.new_tab_request = Some(request),
+            Action::Refresh(connection_id) => {
+                if let Some(tree) = self.trees.get_mut(&connection_id) {
+                    tree.collections.clear();
+                    tree.indexes.clear();
+                }
+                self.refresh_request = Some(connection_id);
+            }
+            Action::Disconnect(connection_id) => self.disconnect_request = Some(connection_id),
+            Action::Monitor(connection_id) => self.monitor_request = Some(connection_id),
+            Action::Topology(connection_id) => self.topology_request = Some(connection_id),
+            Action::Validation(connection_id, database, collection) => {
+                self.validation_request = Some(ValidationRequest {
+                    connection_id,
+                    database,
+                    collection,
+                })
+            }
+            Action::Profiler(connection_id, database) => {
+                self.profiler_request = Some(ProfilerRequest {
+                    connection_id,
+                    database,
+                })
+            }
+            Action::Admin(connection_id, database) => {
+                self.admin_request = Some(AdminRequest {
+                    connection_id,
+                    database,
+                })
+            }
+            Action::Stats(connection_id, target) => {
+                self.stats_request = Some(StatsRequest {
+                    connection_id,
+                    database: target.database().to_string(),
+                    collection: match target {
+                        Target::Database(_) => None,
+                        _ => Some(target.name().to_string()),
+                    },
+                });
+            }
+            Action::Drop(connection_id, target) => {
+                let Some(service) = service(&connection_id) else {
+                    return;
+                };
+                let message = match &target {
+                    Target::Database(database) => format!(
+                        "Drop the database '{}' with all its collections? This can't be undone.",
+                        database
+                    ),
+                    _ => format!(
+                        "Drop the {} '{}'? This can't be undone.",
+                        target.kind(),
+                        target.namespace()
+                    ),
+                };
+                let dialog = ConfirmationDialog::new(
+                    format!("Drop {}", target.kind()),
+                    message,
+                    Arc::clone(&self.theme),
+                )
+                .require_typed_name(target.name());
+                self.pending_drop = Some(PendingDrop {
+                    service,
+                    connection_id,
+                    target,
+                    dialog,
+                });
+            }
+            Action::CreateCollection(connection_id, database) => {
+                let Some(service) = service(&connection_id) else {
+                    return;
+                };
+                self.pending_create = Some(PendingCreate {
+                    service,
+                    connection_id,
+                    dialog: CreateCollectionDialog::collection(database, Arc::clone(&self.theme)),
+                });
+            }
+            Action::CreateView(connection_id, database) => {
+                let Some(service) = service(&connection_id) else {
+                    return;
+                };
+                let collections = match self
+                    .trees
+                    .get(&connection_id)
+                    .and_then(|tree| tree.collections.get(&database))
+                {
+                    Some(Loadable::Loaded(collections)) => collections
+                        .iter()
+                        .filter(|collection| collection.kind != CollectionKind::View)
+                        .map(|collection| collection.name.clone())
+                        .collect(),
+                    _ => Vec::new(),
+                };
+                self.pending_create = Some(PendingCreate {
+                    service,
+                    connection_id,
+                    dialog: CreateCollectionDialog::view(
+                        database,
+                        collections,
+                        Arc::clone(&self.theme),
+                    ),
+                });
+            }
+            Action::Rename(connection_id, database, collection) => {
+                let Some(service) = service(&connection_id) else {
+                    return;
+                };
+                self.pending_rename = Some(PendingRename {
+                    service,
+                    connection_id,
+                    database,
+                    new_name: collection.clone(),
+                    collection,
+                    focus_requested: false,
+                });
+            }
+        }
+    }
+
+    fn start_operation<F>(
+        &mut self,
+        ctx: &Context,
+        connection_id: String,
+        database: String,
+        (running_message, done_message): (String, String),
+        future: F,
+    ) where
+        F: Future<Output = Result<()>> + Send + 'static,
+    {
+        self.message = None;
+        self.operation = Some(Operation {
+            connection_id,
+            database,
+            running_message,
+            done_message,
+            task: BackgroundTask::spawn(ctx, future),
+        });
+    }
+
+    fn show_drop_confirmation(&mut self, ctx: &Context) {
+        let Some(pending) = &mut self.pending_drop else {
+            return;
+        };
+        match pending.dialog.show(ctx) {
+            ConfirmationOutcome::Confirmed => {
+                let pending = self
+                    .pending_drop
+                    .take()
+                    .expect("pending drop checked above");
+                let service = pending.service;
+                let target = pending.target.clone();
+                let messages = (
+                    format!("Dropping {}…", pending.target.namespace()),
+                    format!("Dropped {}", pending.target.namespace()),
+                );
+                self.start_operation(
+                    ctx,
+                    pending.connection_id,
+                    pending.target.database().to_string(),
+                    messages,
+                    async move {
+                        match &target {
+                            Target::Database(database) => service.drop_database(database).await,
+                            Target::Collection(database, name) | Target::View(database, name) => {
+                                service.drop_collection(database, name).await
+                            }
+                            Target::GridFsBucket(database, bucket) => {
+                                service.drop_gridfs_bucket(database, bucket).await
+                            }
+                        }
+                    },
+                );
+            }
+            ConfirmationOutcome::Cancelled => self.pending_drop = None,
+            ConfirmationOutcome::Pending => {}
+        }
+    }
+
+    fn show_rename_prompt(&mut self, ctx: &Context) {
+        let Some(pending) = &mut self.pending_rename else {
+            return;
+        };
+        let new_name = pending.new_name.trim();
+        let valid = new_name != pending.collection && collection_name_error(new_name).is_none();
+
+        let mut submitted = false;
+        let mut cancelled = false;
+        Window::new("Rename Collection")
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.label(
+                    RichText::new(format!(
+                        "New name for '{}.{}':",
+                        pending.database, pending.collection
+                    ))
+                    .color(self.theme.text_color),
+                );
+                let response = ui.text_edit_singleline(&mut pending.new_name);
+                if !pending.focus_requested {
+                    response.request_focus();
+                    pending.focus_requested = true;
+                }
+                if valid && response.lost_focus() && ui.input(|i| i.key_pressed(Key::Enter)) {
+                    submitted = true;
+                }
+                ui.add_space(5.0);
+                ui.with_layout(Layout::right_to_left(Align::TOP), |ui| {
+                    if ui
+                        .add_enabled(valid, ThemedButton::new("Rename", Arc::clone(&self.theme)))
+                        .clicked()
+                    {
+                        submitted = true;
+                    }
+                    if ThemedButton::new("Cancel", Arc::clone(&self.theme))
+                        .ui(ui)
+                        .clicked()
+                    {
+                        cancelled = true;
+                    }
+                });
+            });
+
+        if cancelled || ctx.input(|i| i.key_pressed(Key::Escape)) {
+            self.pending_rename = None;
+        } else if submitted {
+            let pending = self
+                .pending_rename
+                .take()
+                .expect("pending rename checked above");
+            let new_name = pending.new_name.trim().to_string();
+            let messages = (
+                format!("Renaming {}.{}…", pending.database, pending.collection),
+                format!(
+                    "Renamed {}.{} to {}",
+                    pending.database, pending.collection, new_name
+                ),
+            );
+            let service = pending.service;
+            let database = pending.database.clone();
+            let collection = pending.collection;
+            self.start_operation(
+                ctx,
+                pending.connection_id,
+                pending.database,
+                messages,
+                async move {
+                    service
+                        .rename_collection(&database, &collection, &new_name)
+                        .await
+                },
+            );
+        }
+    }
+
+    fn show_create_dialog(&mut self, ctx: &Context) {
+        let Some(pending) = &mut self.pending_create else {
+            return;
+        };
+        match pending.dialog.show(ctx) {
+            CreateCollectionOutcome::Submitted(collection) => {
+                let pending = self
+                    .pending_create
+                    .take()
+                    .expect("pending create checked above");
+                let service = pending.service;
+                match collection {
+                    NewCollection::Collection {
+                        database,
+                        name,
+                        options,
+                    } => {
+                        let messages = (
+                            format!("Creating {}.{}…", database, name),
+                            format!("Created {}.{}", database, name),
+                        );
+                        let task_database = database.clone();
+                        self.start_operation(
+                            ctx,
+                            pending.connection_id,
+                            database,
+                            messages,
+                            async move {
+                                service
+                                    .create_collection(&task_database, &name, *options)
+                                    .await
+                            },
+                        );
+                    }
+                    NewCollection::View {
+                        database,
+                        name,
+                        view_on,
+                        pipeline,
+                    } => {
+                        let messages = (
+                            format!("Creating view {}.{}…", database, name),
+                            format!("Created view {}.{}", database, name),
+                        );
+                        let task_database = database.clone();
+                        self.start_operation(
+                            ctx,
+                            pending.connection_id,
+                            database,
+                            messages,
+                            async move {
+                                service
+                                    .create_view(&task_database, &name, &view_on, pipeline)
+                                    .await
+                            },
+                        );
+                    }
+                }
+            }
+            CreateCollectionOutcome::Cancelled => self.pending_create = None,
+            CreateCollectionOutcome::Pending => {}
+        }
+    }
+
+    pub fn update_theme(&mut self, theme: Arc<Theme>) {
+        self.theme = theme;
+    }
+}
+
+fn badge(ui: &mut Ui, text: &str) {
+    ui.label(RichText::new(text).small().weak());
+}
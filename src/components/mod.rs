@@ -1,5 +1,4 @@
 use crate::theme::Theme;
-use egui::Ui;
 use std::sync::Arc;
 
 pub trait Component {
@@ -7,20 +6,52 @@ pub trait Component {
     fn update_theme(&mut self, theme: Arc<Theme>);
 }
 
+mod admin_window;
 mod collection_selector;
+mod confirmation_dialog;
+mod connection_form;
 mod connection_manager;
+mod create_collection_dialog;
+mod current_ops_panel;
 mod database_selector;
+mod explorer_sidebar;
+mod password_prompt;
+mod profile_transfer;
+mod profiler_window;
 mod query_builder;
+mod query_tab;
 mod results_view;
+mod saved_queries_window;
+mod server_monitor;
+mod stats_dashboard;
 mod status_bar;
 mod tab;
+mod topology_window;
+mod validation_editor;
 mod widgets;
 
+pub use admin_window::{AdminRequest, AdminWindow};
 pub use collection_selector::CollectionSelector;
-pub use connection_manager::ConnectionManager;
+pub use confirmation_dialog::{ConfirmationDialog, ConfirmationOutcome};
+pub use connection_form::ConnectionForm;
+pub use connection_manager::{ConnectRequest, ConnectionManager};
+pub use create_collection_dialog::{
+    collection_name_error, CreateCollectionDialog, CreateCollectionOutcome, NewCollection,
+};
+pub use current_ops_panel::CurrentOpsPanel;
 pub use database_selector::DatabaseSelector;
+pub use explorer_sidebar::ExplorerSidebar;
+pub use password_prompt::{PasswordPrompt, PasswordPromptOutcome};
+pub use profile_transfer::ProfileTransferDialog;
+pub use profiler_window::{ExplainRequest, ProfilerRequest, ProfilerWindow};
 pub use query_builder::QueryBuilder;
+pub use query_tab::QueryTab;
 pub use results_view::ResultsView;
+pub use saved_queries_window::SavedQueriesWindow;
+pub use server_monitor::ServerMonitor;
+pub use stats_dashboard::{StatsDashboard, StatsRequest};
 pub use status_bar::StatusBar;
 pub use tab::Tab;
+pub use topology_window::TopologyWindow;
+pub use validation_editor::{ValidationEditor, ValidationRequest};
 pub use widgets::ThemedButton;
@@ -0,0 +1,83 @@
+use crate::components::ThemedButton;
+use crate::theme::Theme;
+use egui::{Align, Context, Key, Layout, RichText, Widget, Window};
+use std::sync::Arc;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PasswordPromptOutcome {
+    Pending,
+    Submitted { password: String, remember: bool },
+    Cancelled,
+}
+
+/// Modal asking for the password of a profile that doesn't save it.
+pub struct PasswordPrompt {
+    message: String,
+    password: String,
+    remember: bool,
+    focus_requested: bool,
+    theme: Arc<Theme>,
+}
+
+impl PasswordPrompt {
+    pub fn new(message: impl Into<String>, theme: Arc<Theme>) -> Self {
+        Self {
+            message: message.into(),
+            password: String::new(),
+            remember: false,
+            focus_requested: false,
+            theme,
+        }
+    }
+
+    pub fn show(&mut self, ctx: &Context) -> PasswordPromptOutcome {
+        let mut submitted = false;
+        let mut cancelled = false;
+        Window::new("Password Required")
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.label(RichText::new(&self.message).color(self.theme.text_color));
+                ui.add_space(5.0);
+                let response = ui.add(
+                    egui::TextEdit::singleline(&mut self.password)
+                        .password(true)
+                        .hint_text("Password"),
+                );
+                if !self.focus_requested {
+                    response.request_focus();
+                    self.focus_requested = true;
+                }
+                if response.lost_focus() && ui.input(|i| i.key_pressed(Key::Enter)) {
+                    submitted = true;
+                }
+                ui.checkbox(&mut self.remember, "Remember until the app closes");
+                ui.add_space(5.0);
+                ui.with_layout(Layout::right_to_left(Align::TOP), |ui| {
+                    if ThemedButton::new("Connect", Arc::clone(&self.theme))
+                        .ui(ui)
+                        .clicked()
+                    {
+                        submitted = true;
+                    }
+                    if ThemedButton::new("Cancel", Arc::clone(&self.theme))
+                        .ui(ui)
+                        .clicked()
+                    {
+                        cancelled = true;
+                    }
+                });
+            });
+
+        if cancelled || ctx.input(|i| i.key_pressed(Key::Escape)) {
+            PasswordPromptOutcome::Cancelled
+        } else if submitted {
+            PasswordPromptOutcome::Submitted {
+                password: std::mem::take(&mut self.password),
+                remember: self.remember,
+            }
+        } else {
+            PasswordPromptOutcome::Pending
+        }
+    }
+}
@@ -0,0 +1,394 @@
+use crate::components::{ConfirmationDialog, ConfirmationOutcome, ThemedButton};
+use crate::models::{
+    export_profiles, parse_import, ConflictPolicy, ConnectionProfileManager, ExportOptions,
+    ImportBatch, ImportSource, MatchBy,
+};
+use crate::theme::Theme;
+use crate::utils::error::{MongoLiteError, Result};
+use egui::{Align, Context, Layout, RichText, Ui, Window};
+use std::cell::RefCell;
+use std::collections::HashSet;
+use std::fs;
+use std::rc::Rc;
+use std::sync::Arc;
+
+const DEFAULT_EXPORT_PATH: &str = "mongolite_profiles.json";
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum TransferMode {
+    Export,
+    Import,
+}
+
+/// Window for exporting profiles to a file and importing them back, or from
+/// connection string lists and mongosh config files.
+pub struct ProfileTransferDialog {
+    profile_manager: Rc<RefCell<ConnectionProfileManager>>,
+    theme: Arc<Theme>,
+    mode: Option<TransferMode>,
+    path: String,
+    selected: HashSet<String>,
+    strip_credentials: bool,
+    encrypt: bool,
+    passphrase: String,
+    passphrase_confirmation: String,
+    source: ImportSource,
+    match_by: MatchBy,
+    conflict_policy: ConflictPolicy,
+    status: Option<std::result::Result<String, String>>,
+    rejected: Vec<String>,
+    /// An import holding placeholders, waiting for the user to allow them.
+    placeholder_confirmation: Option<(ImportBatch, ConfirmationDialog)>,
+}
+
+impl ProfileTransferDialog {
+    pub fn new(profile_manager: Rc<RefCell<ConnectionProfileManager>>, theme: Arc<Theme>) -> Self {
+        Self {
+            profile_manager,
+            theme,
+            mode: None,
+            path: String::new(),
+            selected: HashSet::new(),
+            strip_credentials: false,
+            encrypt: false,
+            passphrase: String::new(),
+            passphrase_confirmation: String::new(),
+            source: ImportSource::MongoliteExport,
+            match_by: MatchBy::Id,
+            conflict_policy: ConflictPolicy::Skip,
+            status: None,
+            rejected: Vec::new(),
+            placeholder_confirmation: None,
+        }
+    }
+
+    /// Opens the export window with every profile selected.
+    pub fn open_export(&mut self) {
+        self.open(TransferMode::Export);
+        self.path = DEFAULT_EXPORT_PATH.to_string();
+        self.selected = self
+            .profile_manager
+            .borrow()
+            .get_profiles()
+            .iter()
+            .map(|profile| profile.id.clone())
+            .collect();
+    }
+
+    pub fn open_import(&mut self) {
+        self.open(TransferMode::Import);
+        self.path.clear();
+    }
+
+    fn open(&mut self, mode: TransferMode) {
+        self.mode = Some(mode);
+        self.passphrase.clear();
+        self.passphrase_confirmation.clear();
+        self.status = None;
+        self.rejected.clear();
+        self.placeholder_confirmation = None;
+    }
+
+    pub fn update_theme(&mut self, theme: Arc<Theme>) {
+        self.theme = theme;
+    }
+
+    fn export(&self) -> Result<String> {
+        let profiles: Vec<_> = self
+            .profile_manager
+            .borrow()
+            .get_profiles()
+            .iter()
+            .filter(|profile| self.selected.contains(&profile.id))
+            .cloned()
+            .collect();
+        let options = ExportOptions {
+            strip_credentials: self.strip_credentials,
+            passphrase: self.encrypt.then(|| self.passphrase.clone()),
+        };
+        let contents = export_profiles(&profiles, &options)?;
+        fs::write(self.path.trim(), contents).map_err(|e| {
+            MongoLiteError::UnexpectedError(format!("cannot write '{}': {}", self.path, e))
+        })?;
+        Ok(format!(
+            "Exported {} profile(s) to {}",
+            profiles.len(),
+            self.path.trim()
+        ))
+    }
+
+    /// Imports the file, unless its profiles hold `${...}` placeholders: those
+    /// read environment variables or files on this machine when connecting,
+    /// so they are only saved once the user has confirmed them.
+    fn import(&mut self) -> Result<Option<String>> {
+        let contents = fs::read_to_string(self.path.trim()).map_err(|e| {
+            MongoLiteError::ImportError(format!("cannot read '{}': {}", self.path, e))
+        })?;
+        let batch = parse_import(self.source, &contents, Some(&self.passphrase))?;
+        let placeholders = batch.placeholders();
+        if placeholders.is_empty() {
+            return self.save_import(batch).map(Some);
+        }
+
+        let listed: Vec<_> = placeholders
+            .iter()
+            .map(|(name, keys)| format!("{}: ${{{}}}", name, keys.join("}, ${")))
+            .collect();
+        let message = format!(
+            "These profiles read environment variables or files on this computer when \
+             connecting, and send what they read to the server:\n\n{}\n\n\
+             Import them anyway?",
+            listed.join("\n")
+        );
+        self.placeholder_confirmation = Some((
+            batch,
+            ConfirmationDialog::new("Import Placeholders", message, Arc::clone(&self.theme)),
+        ));
+        Ok(None)
+    }
+
+    fn save_import(&mut self, batch: ImportBatch) -> Result<String> {
+        let summary = self.profile_manager.borrow_mut().import_profiles(
+            batch.profiles,
+            self.match_by,
+            self.conflict_policy,
+        )?;
+        self.rejected = batch.rejected;
+        Ok(format!(
+            "Added {}, overwrote {}, skipped {}",
+            summary.added, summary.overwritten, summary.skipped
+        ))
+    }
+
+    fn render_passphrase(&mut self, ui: &mut Ui, confirm: bool) {
+        ui.label(RichText::new("Passphrase:").color(self.theme.text_color));
+        ui.add(egui::TextEdit::singleline(&mut self.passphrase).password(true));
+        ui.end_row();
+        if confirm {
+            ui.label(RichText::new("Confirm:").color(self.theme.text_color));
+            ui.add(egui::TextEdit::singleline(&mut self.passphrase_confirmation).password(true));
+            ui.end_row();
+        }
+    }
+
+    fn render_export(&mut self, ui: &mut Ui) {
+        let profiles = self.profile_manager.borrow().get_profiles().to_vec();
+
+        ui.horizontal(|ui| {
+            ui.label(
+                RichText::new("Profiles")
+                    .color(self.theme.text_color)
+                    .strong(),
+            );
+            if ui.small_button("All").clicked() {
+                self.selected = profiles.iter().map(|p| p.id.clone()).collect();
+            }
+            if ui.small_button("None").clicked() {
+                self.selected.clear();
+            }
+        });
+        egui::ScrollArea::vertical()
+            .id_source("export_profiles")
+            .max_height(200.0)
+            .show(ui, |ui| {
+                for profile in &profiles {
+                    let mut checked = self.selected.contains(&profile.id);
+                    let label = if profile.folder.is_empty() {
+                        profile.name.clone()
+                    } else {
+                        format!("{}/{}", profile.folder, profile.name)
+                    };
+                    if ui.checkbox(&mut checked, label).changed() {
+                        if checked {
+                            self.selected.insert(profile.id.clone());
+                        } else {
+                            self.selected.remove(&profile.id);
+                        }
+                    }
+                }
+            });
+        ui.add_space(5.0);
+
+        egui::Grid::new("export_options")
+            .num_columns(2)
+            .spacing([10.0, 6.0])
+            .show(ui, |ui| {
+                ui.label(RichText::new("File:").color(self.theme.text_color));
+                ui.text_edit_singleline(&mut self.path);
+                ui.end_row();
+
+                ui.label(RichText::new("Credentials:").color(self.theme.text_color));
+                ui.checkbox(&mut self.strip_credentials, "Strip usernames and passwords")
+                    .on_hover_text("SSH tunnel passwords and key passphrases are removed too");
+                ui.end_row();
+
+                ui.label(RichText::new("Encryption:").color(self.theme.text_color));
+                ui.checkbox(&mut self.encrypt, "Encrypt with a passphrase");
+                ui.end_row();
+
+                if self.encrypt {
+                    self.render_passphrase(ui, true);
+                }
+            });
+
+        let passphrase_ok = !self.encrypt
+            || (!self.passphrase.is_empty() && self.passphrase == self.passphrase_confirmation);
+        if self.encrypt && !self.passphrase_confirmation.is_empty() && !passphrase_ok {
+            ui.label(RichText::new("Passphrases do not match").color(self.theme.danger_color));
+        }
+
+        let can_export = !self.selected.is_empty() && !self.path.trim().is_empty() && passphrase_ok;
+        ui.with_layout(Layout::right_to_left(Align::TOP), |ui| {
+            if ui
+                .add_enabled(
+                    can_export,
+                    ThemedButton::new("Export", Arc::clone(&self.theme)),
+                )
+                .clicked()
+            {
+                self.status = Some(self.export().map_err(|e| e.to_string()));
+            }
+        });
+    }
+
+    fn render_import(&mut self, ui: &mut Ui) {
+        egui::Grid::new("import_options")
+            .num_columns(2)
+            .spacing([10.0, 6.0])
+            .show(ui, |ui| {
+                ui.label(RichText::new("Format:").color(self.theme.text_color));
+                egui::ComboBox::from_id_source("import_source")
+                    .selected_text(self.source.label())
+                    .show_ui(ui, |ui| {
+                        for source in ImportSource::ALL {
+                            ui.selectable_value(&mut self.source, source, source.label());
+                        }
+                    });
+                ui.end_row();
+
+                ui.label(RichText::new("File:").color(self.theme.text_color));
+                ui.text_edit_singleline(&mut self.path);
+                ui.end_row();
+
+                if self.source == ImportSource::MongoliteExport {
+                    self.render_passphrase(ui, false);
+                }
+
+                ui.label(RichText::new("Match by:").color(self.theme.text_color));
+                egui::ComboBox::from_id_source("import_match_by")
+                    .selected_text(self.match_by.label())
+                    .show_ui(ui, |ui| {
+                        for match_by in MatchBy::ALL {
+                            ui.selectable_value(&mut self.match_by, match_by, match_by.label());
+                        }
+                    });
+                ui.end_row();
+
+                ui.label(RichText::new("On conflict:").color(self.theme.text_color));
+                egui::ComboBox::from_id_source("import_conflict_policy")
+                    .selected_text(self.conflict_policy.label())
+                    .show_ui(ui, |ui| {
+                        for policy in ConflictPolicy::ALL {
+                            ui.selectable_value(&mut self.conflict_policy, policy, policy.label());
+                        }
+                    });
+                ui.end_row();
+            });
+        if self.source == ImportSource::MongoliteExport {
+            ui.label(
+                RichText::new("Leave the passphrase empty for unencrypted files.")
+                    .color(self.theme.separator_color)
+                    .small(),
+            );
+        }
+
+        let can_import = !self.path.trim().is_empty();
+        ui.with_layout(Layout::right_to_left(Align::TOP), |ui| {
+            if ui
+                .add_enabled(
+                    can_import,
+                    ThemedButton::new("Import", Arc::clone(&self.theme)),
+                )
+                .clicked()
+            {
+                self.rejected.clear();
+                match self.import() {
+                    Ok(Some(message)) => self.status = Some(Ok(message)),
+                    Ok(None) => self.status = None,
+                    Err(e) => self.status = Some(Err(e.to_string())),
+                }
+            }
+        });
+    }
+
+    fn render_status(&self, ui: &mut Ui) {
+        match &self.status {
+            Some(Ok(message)) => {
+                ui.label(RichText::new(message).color(self.theme.accent_color));
+            }
+            Some(Err(message)) => {
+                ui.label(RichText::new(message).color(self.theme.danger_color));
+            }
+            None => {}
+        }
+        if !self.rejected.is_empty() {
+            ui.label(
+                RichText::new(format!(
+                    "{} entries were not imported:",
+                    self.rejected.len()
+                ))
+                .color(self.theme.danger_color),
+            );
+            for entry in &self.rejected {
+                ui.label(RichText::new(entry).color(self.theme.text_color).small());
+            }
+        }
+    }
+
+    pub fn show(&mut self, ctx: &Context) {
+        let Some(mode) = self.mode else {
+            return;
+        };
+        let title = match mode {
+            TransferMode::Export => "Export Profiles",
+            TransferMode::Import => "Import Profiles",
+        };
+        let mut open = true;
+        Window::new(title)
+            .open(&mut open)
+            .collapsible(false)
+            .default_width(420.0)
+            .show(ctx, |ui| {
+                match mode {
+                    TransferMode::Export => self.render_export(ui),
+                    TransferMode::Import => self.render_import(ui),
+                }
+                ui.add_space(5.0);
+                self.render_status(ui);
+            });
+        if !open {
+            self.mode = None;
+            self.placeholder_confirmation = None;
+        }
+        self.show_placeholder_confirmation(ctx);
+    }
+
+    fn show_placeholder_confirmation(&mut self, ctx: &Context) {
+        let Some((_, dialog)) = &mut self.placeholder_confirmation else {
+            return;
+        };
+        match dialog.show(ctx) {
+            ConfirmationOutcome::Confirmed => {
+                if let Some((batch, _)) = self.placeholder_confirmation.take() {
+                    let status = self.save_import(batch).map_err(|e| e.to_string());
+                    self.status = Some(status);
+                }
+            }
+            ConfirmationOutcome::Cancelled => {
+                self.placeholder_confirmation = None;
+                self.status = Some(Err("Import cancelled".to_string()));
+            }
+            ConfirmationOutcome::Pending => {}
+        }
+    }
+}
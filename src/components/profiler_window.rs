@@ -0,0 +1,322 @@
+use crate::components::ThemedButton;
+use crate::models::{
+    group_by_shape, ConnectionEntry, ProfileSort, ProfiledOperation, ProfilerStatus,
+    ProfilingLevel, QueryShapeGroup,
+};
+use crate::services::DatabaseService;
+use crate::theme::Theme;
+use crate::utils::error::Result;
+use crate::utils::format::format_count;
+use crate::utils::task::BackgroundTask;
+use egui::{CollapsingHeader, ComboBox, Context, DragValue, Grid, RichText, Ui, Window};
+use mongodb::bson::Document;
+use std::sync::Arc;
+
+/// Entries read from `system.profile`, newest first.
+const PROFILE_LIMIT: i64 = 1000;
+/// Operations listed under each query shape, slowest first.
+const OPERATIONS_PER_GROUP: usize = 20;
+
+/// The database whose profiler was asked for.
+pub struct ProfilerRequest {
+    pub connection_id: String,
+    pub database: String,
+}
+
+/// A profiled query to load into a query tab and explain.
+pub struct ExplainRequest {
+    pub connection_id: String,
+    pub database: String,
+    pub collection: String,
+    pub filter: Document,
+    pub projection: Option<Document>,
+    pub sort: Option<Document>,
+}
+
+/// Window with a database's profiler settings and its `system.profile`
+/// entries grouped by query shape.
+pub struct ProfilerWindow {
+    service: Option<Arc<DatabaseService>>,
+    connection_id: String,
+    read_only: bool,
+    title: String,
+    database: String,
+    level: ProfilingLevel,
+    slow_ms: i64,
+    status_task: Option<BackgroundTask<Result<ProfilerStatus>>>,
+    apply_task: Option<BackgroundTask<Result<()>>>,
+    operations: Vec<ProfiledOperation>,
+    operations_task: Option<BackgroundTask<Result<Vec<ProfiledOperation>>>>,
+    sort: ProfileSort,
+    groups: Vec<QueryShapeGroup>,
+    /// The outcome of the last change or refresh, and whether it failed.
+    message: Option<(String, bool)>,
+    explain_request: Option<ExplainRequest>,
+    theme: Arc<Theme>,
+}
+
+impl ProfilerWindow {
+    pub fn new(theme: Arc<Theme>) -> Self {
+        Self {
+            service: None,
+            connection_id: String::new(),
+            read_only: false,
+            title: String::new(),
+            database: String::new(),
+            level: ProfilingLevel::Off,
+            slow_ms: 100,
+            status_task: None,
+            apply_task: None,
+            operations: Vec::new(),
+            operations_task: None,
+            sort: ProfileSort::TotalDuration,
+            groups: Vec::new(),
+            message: None,
+            explain_request: None,
+            theme,
+        }
+    }
+
+    pub fn open(&mut self, ctx: &Context, connection: &ConnectionEntry, database: String) {
+        self.title = format!("Profiler · {} · {}", connection.name, database);
+        self.service = Some(Arc::clone(&connection.service));
+        self.connection_id = connection.id.clone();
+        self.read_only = connection.read_only();
+        self.database = database;
+        self.operations.clear();
+        self.groups.clear();
+        self.message = None;
+        self.apply_task = None;
+        self.refresh(ctx);
+    }
+
+    /// Returns the profiled query whose Explain button was clicked.
+    pub fn take_explain_request(&mut self) -> Option<ExplainRequest> {
+        self.explain_request.take()
+    }
+
+    fn refresh(&mut self, ctx: &Context) {
+        let Some(service) = self.service.clone() else {
+            return;
+        };
+        let database = self.database.clone();
+        self.status_task = Some(BackgroundTask::spawn(ctx, {
+            let service = Arc::clone(&service);
+            let database = database.clone();
+            async move { service.profiler_status(&database).await }
+        }));
+        self.operations_task = Some(BackgroundTask::spawn(ctx, async move {
+            service.profiled_operations(&database, PROFILE_LIMIT).await
+        }));
+    }
+
+    fn apply(&mut self, ctx: &Context) {
+        let Some(service) = self.service.clone() else {
+            return;
+        };
+        let database = self.database.clone();
+        let (level, slow_ms) = (self.level, self.slow_ms);
+        self.apply_task = Some(BackgroundTask::spawn(ctx, async move {
+            service.set_profiler(&database, level, slow_ms).await
+        }));
+    }
+
+    fn regroup(&mut self) {
+        self.groups = group_by_shape(self.operations.clone(), self.sort);
+    }
+
+    pub fn show(&mut self, ctx: &Context) {
+        if self.service.is_none() {
+            return;
+        }
+        if let Some(result) = self.status_task.as_mut().and_then(|task| task.poll()) {
+            self.status_task = None;
+            match result {
+                Ok(status) => {
+                    self.level = status.level;
+                    self.slow_ms = status.slow_ms;
+                }
+                Err(e) => self.message = Some((e.to_string(), true)),
+            }
+        }
+        if let Some(result) = self.operations_task.as_mut().and_then(|task| task.poll()) {
+            self.operations_task = None;
+            match result {
+                Ok(operations) => {
+                    self.operations = operations;
+                    self.regroup();
+                }
+                Err(e) => self.message = Some((e.to_string(), true)),
+            }
+        }
+        if let Some(result) = self.apply_task.as_mut().and_then(|task| task.poll()) {
+            self.apply_task = None;
+            self.message = Some(match result {
+                Ok(()) => ("Profiler settings saved".to_string(), false),
+                Err(e) => (e.to_string(), true),
+            });
+        }
+
+        let mut open = true;
+        let mut refresh = false;
+        let mut apply = false;
+        Window::new(&self.title)
+            .id(egui::Id::new("profiler_window"))
+            .open(&mut open)
+            .default_size([640.0, 520.0])
+            .vscroll(true)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("Level:");
+                    ComboBox::from_id_source("profiler_level")
+                        .selected_text(self.level.label())
+                        .show_ui(ui, |ui| {
+                            for level in ProfilingLevel::ALL {
+                                ui.selectable_value(&mut self.level, level, level.label());
+                            }
+                        });
+                    ui.label("Slow over");
+                    ui.add(
+                        DragValue::new(&mut self.slow_ms)
+                            .range(0..=3_600_000)
+                            .suffix(" ms"),
+                    );
+                    apply = ui
+                        .add_enabled(
+                            !self.read_only && self.apply_task.is_none(),
+                            ThemedButton::new("Apply", Arc::clone(&self.theme)),
+                        )
+                        .on_disabled_hover_text("Not on a read-only connection")
+                        .clicked();
+                    refresh = ui
+                        .add_enabled(
+                            self.operations_task.is_none(),
+                            ThemedButton::new("Refresh", Arc::clone(&self.theme)),
+                        )
+                        .clicked();
+                    if self.status_task.is_some()
+                        || self.operations_task.is_some()
+                        || self.apply_task.is_some()
+                    {
+                        ui.spinner();
+                    }
+                });
+                if let Some((message, failed)) = &self.message {
+                    let color = if *failed {
+                        self.theme.danger_color
+                    } else {
+                        self.theme.accent_color
+                    };
+                    ui.label(RichText::new(message).color(color));
+                }
+                ui.separator();
+                self.render_groups(ui);
+            });
+
+        if !open {
+            self.service = None;
+            self.status_task = None;
+            self.operations_task = None;
+        } else {
+            if apply {
+                self.apply(ctx);
+            }
+            if refresh {
+                self.message = None;
+                self.refresh(ctx);
+            }
+        }
+    }
+
+    fn render_groups(&mut self, ui: &mut Ui) {
+        ui.horizontal(|ui| {
+            ui.label(format!(
+                "{} operations in {} query shapes",
+                format_count(self.operations.len() as u64),
+                self.groups.len()
+            ));
+            ui.add_space(10.0);
+            ui.label("Sort by:");
+            let before = self.sort;
+            ui.radio_value(&mut self.sort, ProfileSort::TotalDuration, "Total time");
+            ui.radio_value(&mut self.sort, ProfileSort::AverageDuration, "Average time");
+            if self.sort != before {
+                self.regroup();
+            }
+        });
+        if self.groups.is_empty() && self.operations_task.is_none() {
+            ui.label(
+                RichText::new("No profiled operations. Set the level and run some queries.").weak(),
+            );
+            return;
+        }
+
+        let mut explain = None;
+        for (index, group) in self.groups.iter().enumerate() {
+            let header = format!(
+                "{} · {} · {}× · {} ms total · {:.0} ms avg · {} ms max",
+                group.namespace,
+                group.operation,
+                group.operations.len(),
+                format_count(group.total_millis),
+                group.average_millis(),
+                format_count(group.max_millis())
+            );
+            CollapsingHeader::new(header)
+                .id_source(("profiler_group", index))
+                .show(ui, |ui| {
+                    ui.label(RichText::new(&group.shape).monospace().small());
+                    Grid::new(("profiler_operations", index))
+                        .num_columns(7)
+                        .striped(true)
+                        .show(ui, |ui| {
+                            for header in ["Time", "ms", "Plan", "Keys", "Docs", "Returned", ""] {
+                                ui.label(RichText::new(header).strong());
+                            }
+                            ui.end_row();
+                            for operation in group.operations.iter().take(OPERATIONS_PER_GROUP) {
+                                ui.label(
+                                    operation
+                                        .time
+                                        .and_then(|time| time.try_to_rfc3339_string().ok())
+                                        .unwrap_or_default(),
+                                );
+                                ui.label(format_count(operation.millis));
+                                ui.label(&operation.plan_summary);
+                                ui.label(format_count(operation.keys_examined));
+                                ui.label(format_count(operation.docs_examined));
+                                ui.label(format_count(operation.returned));
+                                let explainable = operation.filter.is_some()
+                                    && !operation.collection().is_empty();
+                                if ui
+                                    .add_enabled(explainable, egui::Button::new("Explain"))
+                                    .on_hover_text(
+                                        "Load the filter into a query tab and explain it",
+                                    )
+                                    .on_disabled_hover_text("This operation has no filter")
+                                    .clicked()
+                                {
+                                    explain = Some(operation.clone());
+                                }
+                                ui.end_row();
+                            }
+                        });
+                });
+        }
+
+        if let Some(operation) = explain {
+            self.explain_request = Some(ExplainRequest {
+                connection_id: self.connection_id.clone(),
+                database: self.database.clone(),
+                collection: operation.collection().to_string(),
+                filter: operation.filter.unwrap_or_default(),
+                projection: operation.projection,
+                sort: operation.sort,
+            });
+        }
+    }
+
+    pub fn update_theme(&mut self, theme: Arc<Theme>) {
+        self.theme = theme;
+    }
+}
@@ -1,6 +1,9 @@
 use crate::components::Component;
+use crate::models::{QueryOptions, ReadConcernMode, ReadPreferenceMode};
 use crate::theme::Theme;
-use egui::{RichText, ScrollArea, Ui, Vec2, Widget};
+use crate::utils::extended_json::parse_document;
+use egui::{ComboBox, DragValue, Grid, RichText, ScrollArea, TextEdit, Ui, Vec2, Widget};
+use mongodb::bson::Document;
 use std::sync::Arc;
 
 use super::ThemedButton;
@@ -9,6 +12,9 @@ pub struct QueryBuilder {
     query: String,
     projection: String,
     sort: String,
+    options: QueryOptions,
+    execute_requested: bool,
+    explain_requested: bool,
     theme: Arc<Theme>,
 }
 
@@ -18,9 +24,201 @@ impl QueryBuilder {
             query: String::new(),
             projection: String::new(),
             sort: String::new(),
+            options: QueryOptions::default(),
+            execute_requested: false,
+            explain_requested: false,
             theme,
         }
     }
+
+    /// Replaces the query, projection and sort, e.g. with a query taken from
+    /// the profiler.
+    pub fn set_query(&mut self, query: String, projection: String, sort: String) {
+        self.query = query;
+        self.projection = projection;
+        self.sort = sort;
+    }
+
+    /// The query, projection and sort as typed.
+    pub fn text(&self) -> (&str, &str, &str) {
+        (&self.query, &self.projection, &self.sort)
+    }
+
+    pub fn options(&self) -> &QueryOptions {
+        &self.options
+    }
+
+    pub fn set_options(&mut self, options: QueryOptions) {
+        self.options = options;
+    }
+
+    /// Parses the filter, projection and sort. An empty filter matches every
+    /// document; an empty projection or sort is left out.
+    pub fn parse(&self) -> Result<(Document, Option<Document>, Option<Document>), String> {
+        let optional = |label: &str, text: &str| {
+            if text.trim().is_empty() {
+                Ok(None)
+            } else {
+                parse_document(text)
+                    .map(Some)
+                    .map_err(|e| format!("{}: {}", label, e))
+            }
+        };
+        let filter = optional("Query", &self.query)?.unwrap_or_default();
+        let projection = optional("Projection", &self.projection)?;
+        let sort = optional("Sort", &self.sort)?;
+        Ok((filter, projection, sort))
+    }
+
+    /// Runs the query as if Execute Query was clicked, e.g. on F5.
+    pub fn request_execute(&mut self) {
+        self.execute_requested = true;
+    }
+
+    pub fn take_execute_request(&mut self) -> bool {
+        std::mem::take(&mut self.execute_requested)
+    }
+
+    pub fn take_explain_request(&mut self) -> bool {
+        std::mem::take(&mut self.explain_requested)
+    }
+
+    fn render_options(&mut self, ui: &mut Ui, id_prefix: &str) {
+        let header = if self.options.is_default() {
+            "Options".to_string()
+        } else {
+            "Options (changed)".to_string()
+        };
+        egui::CollapsingHeader::new(header)
+            .id_source(format!("{}_options", id_prefix))
+            .show(ui, |ui| {
+                let options = &mut self.options;
+                Grid::new(format!("{}_options_grid", id_prefix))
+                    .num_columns(2)
+                    .show(ui, |ui| {
+                        ui.label("Read preference:");
+                        ComboBox::from_id_source(format!("{}_read_preference", id_prefix))
+                            .selected_text(
+                                options
+                                    .read_preference
+                                    .map_or("Connection default", |mode| mode.uri_value()),
+                            )
+                            .show_ui(ui, |ui| {
+                                ui.selectable_value(
+                                    &mut options.read_preference,
+                                    None,
+                                    "Connection default",
+                                );
+                                for mode in ReadPreferenceMode::ALL {
+                                    ui.selectable_value(
+                                        &mut options.read_preference,
+                                        Some(mode),
+                                        mode.uri_value(),
+                                    );
+                                }
+                            });
+                        ui.end_row();
+
+                        ui.label("Tag sets:");
+                        ui.add(
+                            TextEdit::singleline(&mut options.read_preference_tags)
+                                .hint_text("dc:east,rack:1; dc:west"),
+                        );
+                        ui.end_row();
+
+                        ui.label("Max staleness:");
+                        optional_number(ui, &mut options.max_staleness_seconds, 90, " s");
+                        ui.end_row();
+
+                        ui.label("Read concern:");
+                        ComboBox::from_id_source(format!("{}_read_concern", id_prefix))
+                            .selected_text(
+                                options.read_concern.map_or("Default", |mode| mode.label()),
+                            )
+                            .show_ui(ui, |ui| {
+                                ui.selectable_value(&mut options.read_concern, None, "Default");
+                                for mode in ReadConcernMode::ALL {
+                                    ui.selectable_value(
+                                        &mut options.read_concern,
+                                        Some(mode),
+                                        mode.label(),
+                                    );
+                                }
+                            });
+                        ui.end_row();
+
+                        ui.label("Write concern:");
+                        ui.horizontal(|ui| {
+                            ui.add(
+                                TextEdit::singleline(&mut options.write_concern_w)
+                                    .hint_text("w: majority or a number")
+                                    .desired_width(140.0),
+                            );
+                            ui.checkbox(&mut options.write_concern_journal, "Journal");
+                        });
+                        ui.end_row();
+
+                        ui.label("Write timeout:");
+                        optional_number(ui, &mut options.write_concern_timeout_ms, 1000, " ms");
+                        ui.end_row();
+
+                        ui.label("Max time:");
+                        optional_number(ui, &mut options.max_time_ms, 1000, " ms");
+                        ui.end_row();
+
+                        ui.label("Hint:");
+                        ui.add(
+                            TextEdit::singleline(&mut options.hint)
+                                .hint_text("index name or { \"field\": 1 }"),
+                        );
+                        ui.end_row();
+
+                        ui.label("Collation:");
+                        ui.add(
+                            TextEdit::singleline(&mut options.collation)
+                                .hint_text("{ \"locale\": \"en\", \"strength\": 2 }"),
+                        );
+                        ui.end_row();
+
+                        ui.label("Comment:");
+                        ui.text_edit_singleline(&mut options.comment);
+                        ui.end_row();
+
+                        ui.label("Batch size:");
+                        optional_number(ui, &mut options.batch_size, 101, "");
+                        ui.end_row();
+
+                        ui.label("");
+                        ui.horizontal(|ui| {
+                            ui.checkbox(&mut options.allow_disk_use, "Allow disk use");
+                            ui.checkbox(&mut options.no_cursor_timeout, "No cursor timeout");
+                        });
+                        ui.end_row();
+                    });
+                if ui
+                    .add_enabled(!options.is_default(), egui::Button::new("Reset Options"))
+                    .clicked()
+                {
+                    *options = QueryOptions::default();
+                }
+            });
+    }
+}
+
+/// A checkbox that turns a number on, with the number next to it.
+fn optional_number<T>(ui: &mut Ui, value: &mut Option<T>, default: T, suffix: &str)
+where
+    T: egui::emath::Numeric,
+{
+    ui.horizontal(|ui| {
+        let mut enabled = value.is_some();
+        if ui.checkbox(&mut enabled, "").changed() {
+            *value = enabled.then_some(default);
+        }
+        if let Some(number) = value {
+            ui.add(DragValue::new(number).suffix(suffix));
+        }
+    });
 }
 
 impl Component for QueryBuilder {
@@ -60,15 +258,25 @@ impl Component for QueryBuilder {
                 .id_source(format!("{}_sort", id_prefix));
             ui.add(sort_edit);
 
+            ui.add_space(10.0);
+            self.render_options(ui, id_prefix);
+
             ui.add_space(20.0);
 
-            // Execute button
+            // Execute and explain buttons
             ui.with_layout(egui::Layout::right_to_left(egui::Align::TOP), |ui| {
                 if ThemedButton::new("Execute Query", Arc::clone(&self.theme))
                     .ui(ui)
                     .clicked()
                 {
-                    // Execute query logic here
+                    self.execute_requested = true;
+                }
+                if ThemedButton::new("Explain", Arc::clone(&self.theme))
+                    .ui(ui)
+                    .on_hover_text("Show the query plan with execution stats")
+                    .clicked()
+                {
+                    self.explain_requested = true;
                 }
             });
         });
@@ -0,0 +1,418 @@
+use crate::components::{
+    CollectionSelector, Component, DatabaseSelector, QueryBuilder, ResultsView, StatsRequest, Tab,
+    ThemedButton,
+};
+use crate::models::{ConnectionEntry, ExplainSummary, SavedQuery};
+use crate::services::{CancellationHandle, DatabaseService, QueryService};
+use crate::theme::Theme;
+use crate::utils::error::Result;
+use crate::utils::task::BackgroundTask;
+use egui::Ui;
+use mongodb::bson::{Bson, Document};
+use std::sync::Arc;
+use std::time::Instant;
+
+const TABLE_RESULTS_TAB: usize = 0;
+const EXPLAIN_RESULTS_TAB: usize = 2;
+/// Documents a query returns at most.
+const RESULT_LIMIT: i64 = 1000;
+
+/// A query workspace bound to one connection, with its own database and
+/// collection selection, query and results.
+pub struct QueryTab {
+    pub title: String,
+    /// Id of the connection in the registry. It is kept after the connection
+    /// closes, so reconnecting the same profile brings the tab back.
+    pub connection_id: Option<String>,
+    pub database_selector: DatabaseSelector,
+    pub collection_selector: CollectionSelector,
+    query_builder: QueryBuilder,
+    results_view: ResultsView,
+    query_tab: Tab<QueryBuilder>,
+    results_tab: Tab<ResultsView>,
+    /// The database the collection selector lists, once requested.
+    collections_database: Option<String>,
+    collections_task: Option<BackgroundTask<Result<Vec<String>>>>,
+    query_task: Option<BackgroundTask<Result<Vec<Document>>>>,
+    /// Stops the running query, and when it was started.
+    running_query: Option<(CancellationHandle, Instant)>,
+    cancel_task: Option<BackgroundTask<Result<()>>>,
+    explain_task: Option<BackgroundTask<Result<ExplainSummary>>>,
+    stats_requested: bool,
+    theme: Arc<Theme>,
+}
+
+impl QueryTab {
+    pub fn new(title: String, connection_id: Option<String>, theme: Arc<Theme>) -> Self {
+        let mut query_tab = Tab::new(format!("{}_query_tab", title), Arc::clone(&theme));
+        query_tab.add_tab(
+            "Query".to_string(),
+            Box::new(
+                |ui: &mut Ui, query_builder: &mut QueryBuilder, _: &Theme, id_prefix: &str| {
+                    query_builder.render(ui, id_prefix);
+                },
+            ),
+        );
+        query_tab.add_tab(
+            "Aggregation".to_string(),
+            Box::new(|ui: &mut Ui, _: &mut QueryBuilder, _: &Theme, _: &str| {
+                ui.label("Aggregation tab content (to be implemented)");
+            }),
+        );
+
+        let mut results_tab = Tab::new(format!("{}_results_tab", title), Arc::clone(&theme));
+        results_tab.add_tab(
+            "Table View".to_string(),
+            Box::new(
+                |ui: &mut Ui, results_view: &mut ResultsView, _: &Theme, id_prefix: &str| {
+                    results_view.render_table(ui, id_prefix);
+                },
+            ),
+        );
+        results_tab.add_tab(
+            "JSON View".to_string(),
+            Box::new(
+                |ui: &mut Ui, results_view: &mut ResultsView, _: &Theme, id_prefix: &str| {
+                    results_view.render_json(ui, id_prefix);
+                },
+            ),
+        );
+        results_tab.add_tab(
+            "Explain".to_string(),
+            Box::new(
+                |ui: &mut Ui, results_view: &mut ResultsView, _: &Theme, id_prefix: &str| {
+                    results_view.render_explain(ui, id_prefix);
+                },
+            ),
+        );
+
+        Self {
+            title,
+            connection_id,
+            database_selector: DatabaseSelector::new(Arc::clone(&theme)),
+            collection_selector: CollectionSelector::new(Arc::clone(&theme)),
+            query_builder: QueryBuilder::new(Arc::clone(&theme)),
+            results_view: ResultsView::new(Arc::clone(&theme)),
+            query_tab,
+            results_tab,
+            collections_database: None,
+            collections_task: None,
+            query_task: None,
+            running_query: None,
+            cancel_task: None,
+            explain_task: None,
+            stats_requested: false,
+            theme,
+        }
+    }
+
+    /// Keeps the collection selector listing the selected database's
+    /// collections. `service` is the tab's connection while it's connected.
+    pub fn sync_collections(
+        &mut self,
+        ctx: &egui::Context,
+        service: Option<&Arc<DatabaseService>>,
+    ) {
+        let refresh = self.collection_selector.take_refresh_request();
+        let database = self.database_selector.selected().to_string();
+        let Some(service) = service.filter(|_| !database.is_empty()) else {
+            self.collections_database = None;
+            self.collections_task = None;
+            self.collection_selector.set_collections(Vec::new());
+            return;
+        };
+
+        if refresh || self.collections_database.as_deref() != Some(database.as_str()) {
+            let service = Arc::clone(service);
+            self.collections_database = Some(database.clone());
+            self.collections_task = Some(BackgroundTask::spawn(ctx, async move {
+                service.list_collection_names(&database).await
+            }));
+        }
+        if let Some(result) = self.collections_task.as_mut().and_then(|task| task.poll()) {
+            self.collections_task = None;
+            // A failed listing leaves the selector empty; Refresh retries
+            self.collection_selector
+                .set_collections(result.unwrap_or_default());
+        }
+    }
+
+    /// Runs the query builder's execute and explain requests and the
+    /// results view's cancel request, and shows their outcome. `connection`
+    /// is the tab's connection while it's connected.
+    pub fn sync_queries(
+        &mut self,
+        ctx: &egui::Context,
+        connection: Option<&ConnectionEntry>,
+        query_service: &Arc<QueryService>,
+    ) {
+        let service = connection.map(|entry| &entry.service);
+        if self.query_builder.take_execute_request() {
+            let default_max_time_ms = connection.and_then(ConnectionEntry::default_max_time_ms);
+            self.execute(ctx, service, default_max_time_ms, query_service);
+        }
+        if self.results_view.take_cancel_request() {
+            self.cancel(ctx, service);
+        }
+        if let Some(result) = self.query_task.as_mut().and_then(|task| task.poll()) {
+            self.query_task = None;
+            self.running_query = None;
+            self.results_view
+                .set_results(result.map_err(|e| e.to_string()));
+        }
+        if let Some(result) = self.cancel_task.as_mut().and_then(|task| task.poll()) {
+            self.cancel_task = None;
+            if let Err(e) = result {
+                if self.query_task.is_none() {
+                    self.results_view.set_results(Err(format!(
+                        "Query cancelled, but it may still be running on the server: {}",
+                        e
+                    )));
+                }
+            }
+        }
+        if self.query_builder.take_explain_request() {
+            self.explain(ctx, service);
+        }
+        if let Some(result) = self.explain_task.as_mut().and_then(|task| task.poll()) {
+            self.explain_task = None;
+            self.results_view
+                .set_explain(result.map_err(|e| e.to_string()));
+        }
+    }
+
+    /// Runs the query in the builder, with its options, on the selected
+    /// collection, cancelling the one still running. `default_max_time_ms`
+    /// applies when the query doesn't set its own.
+    pub fn execute(
+        &mut self,
+        ctx: &egui::Context,
+        service: Option<&Arc<DatabaseService>>,
+        default_max_time_ms: Option<u64>,
+        query_service: &Arc<QueryService>,
+    ) {
+        self.cancel(ctx, service);
+        if self.results_tab.selected() == EXPLAIN_RESULTS_TAB {
+            self.results_tab.select(TABLE_RESULTS_TAB);
+        }
+        let database = self.database_selector.selected().to_string();
+        let collection = self.collection_selector.selected().to_string();
+        let Some(service) = service else {
+            self.results_view
+                .set_results(Err("This tab isn't connected".to_string()));
+            return;
+        };
+        if database.is_empty() || collection.is_empty() {
+            self.results_view
+                .set_results(Err("Select a database and collection first".to_string()));
+            return;
+        }
+        let (filter, projection, sort) = match self.query_builder.parse() {
+            Ok(query) => query,
+            Err(e) => {
+                self.results_view.set_results(Err(e));
+                return;
+            }
+        };
+        let mut options = self.query_builder.options().clone();
+        if options.max_time_ms.is_none() {
+            options.max_time_ms = default_max_time_ms;
+        }
+        let mut find_options = match options.find_options(projection, sort) {
+            Ok(find_options) => find_options,
+            Err(e) => {
+                self.results_view.set_results(Err(e));
+                return;
+            }
+        };
+        find_options.limit = Some(RESULT_LIMIT);
+        let (service, query_service) = (Arc::clone(service), Arc::clone(query_service));
+        let cancellation = CancellationHandle::new();
+        self.running_query = Some((cancellation.clone(), Instant::now()));
+        self.results_view.set_running();
+        self.query_task = Some(BackgroundTask::spawn(ctx, async move {
+            let collection = service.collection(&database, &collection, &options)?;
+            query_service
+                .execute_query(&collection, filter, find_options, &cancellation)
+                .await
+        }));
+    }
+
+    /// Stops the running query. It returns at once; its operation and
+    /// cursor are killed on the server in the background.
+    pub fn cancel(&mut self, ctx: &egui::Context, service: Option<&Arc<DatabaseService>>) {
+        let Some((cancellation, _)) = self.running_query.take() else {
+            return;
+        };
+        cancellation.cancel();
+        if let Some(service) = service {
+            let service = Arc::clone(service);
+            self.cancel_task = Some(BackgroundTask::spawn(ctx, async move {
+                service.cancel_query(cancellation.tag()).await
+            }));
+        }
+    }
+
+    /// When the running query was started.
+    pub fn query_started(&self) -> Option<Instant> {
+        self.running_query.as_ref().map(|(_, started)| *started)
+    }
+
+    /// Runs the query as if Execute Query was clicked.
+    pub fn request_execute(&mut self) {
+        self.query_builder.request_execute();
+    }
+
+    /// Explains the query in the builder on the selected collection.
+    pub fn explain(&mut self, ctx: &egui::Context, service: Option<&Arc<DatabaseService>>) {
+        self.results_tab.select(EXPLAIN_RESULTS_TAB);
+        let database = self.database_selector.selected().to_string();
+        let collection = self.collection_selector.selected().to_string();
+        let Some(service) = service else {
+            self.results_view
+                .set_explain(Err("This tab isn't connected".to_string()));
+            return;
+        };
+        if database.is_empty() || collection.is_empty() {
+            self.results_view
+                .set_explain(Err("Select a database and collection first".to_string()));
+            return;
+        }
+        let (filter, projection, sort) = match self.query_builder.parse() {
+            Ok(query) => query,
+            Err(e) => {
+                self.results_view.set_explain(Err(e));
+                return;
+            }
+        };
+        let service = Arc::clone(service);
+        let options = self.query_builder.options().clone();
+        self.results_view.set_explaining();
+        self.explain_task = Some(BackgroundTask::spawn(ctx, async move {
+            service
+                .explain_find(&database, &collection, filter, sort, projection, &options)
+                .await
+        }));
+    }
+
+    /// Puts a query in the builder, e.g. one found by the profiler.
+    pub fn load_query(
+        &mut self,
+        filter: &Document,
+        projection: Option<&Document>,
+        sort: Option<&Document>,
+    ) {
+        let json = |document: Option<&Document>| {
+            document
+                .map(|document| {
+                    serde_json::to_string_pretty(
+                        &Bson::Document(document.clone()).into_relaxed_extjson(),
+                    )
+                    .unwrap_or_default()
+                })
+                .unwrap_or_default()
+        };
+        self.query_builder
+            .set_query(json(Some(filter)), json(projection), json(sort));
+    }
+
+    /// The tab's query, where it runs and its options, to be saved as `name`.
+    pub fn saved_query(&self, name: String) -> SavedQuery {
+        let (filter, projection, sort) = self.query_builder.text();
+        SavedQuery {
+            name,
+            database: self.database_selector.selected().to_string(),
+            collection: self.collection_selector.selected().to_string(),
+            filter: filter.to_string(),
+            projection: projection.to_string(),
+            sort: sort.to_string(),
+            options: self.query_builder.options().clone(),
+        }
+    }
+
+    /// Puts a saved query in the builder and selects its database and
+    /// collection.
+    pub fn load_saved_query(&mut self, query: &SavedQuery) {
+        if !query.database.is_empty() {
+            self.database_selector.select(query.database.clone());
+        }
+        if !query.collection.is_empty() {
+            self.collection_selector.select(query.collection.clone());
+        }
+        self.query_builder.set_query(
+            query.filter.clone(),
+            query.projection.clone(),
+            query.sort.clone(),
+        );
+        self.query_builder.set_options(query.options.clone());
+    }
+
+    /// Lists the collections again, e.g. after one was created or dropped.
+    pub fn refresh_collections(&mut self) {
+        self.collections_database = None;
+    }
+
+    /// Returns the selected database or collection once Stats was clicked.
+    pub fn take_stats_request(&mut self) -> Option<StatsRequest> {
+        if !std::mem::take(&mut self.stats_requested) {
+            return None;
+        }
+        let connection_id = self.connection_id.clone()?;
+        let database = self.database_selector.selected().to_string();
+        if database.is_empty() {
+            return None;
+        }
+        let collection = Some(self.collection_selector.selected().to_string())
+            .filter(|collection| !collection.is_empty());
+        Some(StatsRequest {
+            connection_id,
+            database,
+            collection,
+        })
+    }
+
+    pub fn render_selectors(&mut self, ui: &mut Ui) {
+        ui.vertical(|ui| {
+            self.database_selector.render(ui, "database_selector");
+            ui.add_space(10.0);
+            self.collection_selector.render(ui, "collection_selector");
+            ui.add_space(10.0);
+            let has_database = !self.database_selector.selected().is_empty();
+            if ui
+                .add_enabled(
+                    has_database,
+                    ThemedButton::new("Stats", Arc::clone(&self.theme)),
+                )
+                .on_hover_text("Storage stats of the selected collection, or of the database")
+                .clicked()
+            {
+                self.stats_requested = true;
+            }
+        });
+    }
+
+    pub fn render_workspace(&mut self, ui: &mut Ui) {
+        ui.horizontal(|ui| {
+            ui.vertical(|ui| {
+                let max_width = ui.available_width().min(600.0); // Set a maximum width of 600 pixels
+                ui.set_max_width(max_width);
+                self.query_tab.render(ui, &mut self.query_builder);
+            });
+            ui.add_space(10.0);
+            ui.vertical(|ui| {
+                ui.set_min_width(ui.available_width());
+                self.results_tab.render(ui, &mut self.results_view);
+            });
+        });
+    }
+
+    pub fn update_theme(&mut self, theme: Arc<Theme>) {
+        self.theme = Arc::clone(&theme);
+        self.database_selector.update_theme(Arc::clone(&theme));
+        self.collection_selector.update_theme(Arc::clone(&theme));
+        self.query_builder.update_theme(Arc::clone(&theme));
+        self.results_view.update_theme(Arc::clone(&theme));
+        self.query_tab.update_theme(Arc::clone(&theme));
+        self.results_tab.update_theme(theme);
+    }
+}
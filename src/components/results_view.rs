@@ -1,20 +1,103 @@
-use crate::components::Component;
+use crate::components::{Component, ThemedButton};
+use crate::models::ExplainSummary;
 use crate::theme::Theme;
+use crate::utils::format::format_count;
 use egui::{Grid, RichText, ScrollArea, Ui};
+use mongodb::bson::{Bson, Document};
 use std::sync::Arc;
 
+/// Columns shown in the table view; further fields are in the JSON view.
+const MAX_COLUMNS: usize = 50;
+
 pub struct ResultsView {
-    results: Vec<Vec<String>>,
+    documents: Vec<Document>,
+    /// Top-level fields of the documents, in the order first seen.
+    columns: Vec<String>,
+    /// Why the last query failed.
+    error: Option<String>,
+    running: bool,
+    cancel_requested: bool,
+    /// The last explain, or why it failed.
+    explain: Option<Result<ExplainSummary, String>>,
+    explaining: bool,
     theme: Arc<Theme>,
 }
 
 impl ResultsView {
     pub fn new(theme: Arc<Theme>) -> Self {
         Self {
-            results: Vec::new(),
+            documents: Vec::new(),
+            columns: Vec::new(),
+            error: None,
+            running: false,
+            cancel_requested: false,
+            explain: None,
+            explaining: false,
             theme,
         }
     }
+
+    pub fn set_running(&mut self) {
+        self.running = true;
+    }
+
+    /// Returns true once Cancel was clicked on the running query.
+    pub fn take_cancel_request(&mut self) -> bool {
+        std::mem::take(&mut self.cancel_requested)
+    }
+
+    pub fn set_results(&mut self, results: Result<Vec<Document>, String>) {
+        self.running = false;
+        self.columns.clear();
+        match results {
+            Ok(documents) => {
+                for document in &documents {
+                    for key in document.keys() {
+                        if self.columns.len() < MAX_COLUMNS && !self.columns.contains(key) {
+                            self.columns.push(key.clone());
+                        }
+                    }
+                }
+                self.documents = documents;
+                self.error = None;
+            }
+            Err(error) => {
+                self.documents.clear();
+                self.error = Some(error);
+            }
+        }
+    }
+
+    /// Shows the running query or its error in place of results. Returns
+    /// whether there was anything to show.
+    fn render_status(&mut self, ui: &mut Ui) -> bool {
+        if self.running {
+            ui.horizontal(|ui| {
+                ui.spinner();
+                if ui
+                    .add(ThemedButton::new("Cancel", Arc::clone(&self.theme)))
+                    .on_hover_text("Stop the query and kill it on the server")
+                    .clicked()
+                {
+                    self.cancel_requested = true;
+                }
+            });
+        } else if let Some(error) = &self.error {
+            ui.label(RichText::new(error).color(self.theme.danger_color));
+        } else {
+            return false;
+        }
+        true
+    }
+
+    pub fn set_explaining(&mut self) {
+        self.explaining = true;
+    }
+
+    pub fn set_explain(&mut self, explain: Result<ExplainSummary, String>) {
+        self.explain = Some(explain);
+        self.explaining = false;
+    }
 }
 
 impl Component for ResultsView {
@@ -33,13 +116,28 @@ impl Component for ResultsView {
 }
 
 impl ResultsView {
-    pub fn render_table(&self, ui: &mut Ui, id_prefix: &str) {
+    pub fn render_table(&mut self, ui: &mut Ui, id_prefix: &str) {
+        if self.render_status(ui) {
+            return;
+        }
+        ui.label(format!(
+            "{} documents",
+            format_count(self.documents.len() as u64)
+        ));
         ScrollArea::both()
             .id_source(format!("{}_table", id_prefix))
             .show(ui, |ui| {
                 Grid::new("results_grid").striped(true).show(ui, |ui| {
-                    for row in &self.results {
-                        for cell in row {
+                    for column in &self.columns {
+                        ui.label(RichText::new(column).strong());
+                    }
+                    ui.end_row();
+                    for document in &self.documents {
+                        for column in &self.columns {
+                            let cell = document
+                                .get(column)
+                                .map(|value| value.clone().into_relaxed_extjson().to_string())
+                                .unwrap_or_default();
                             ui.label(cell);
                         }
                         ui.end_row();
@@ -48,19 +146,70 @@ impl ResultsView {
             });
     }
 
-    pub fn render_json(&self, ui: &mut Ui, id_prefix: &str) {
+    pub fn render_explain(&self, ui: &mut Ui, id_prefix: &str) {
+        if self.explaining {
+            ui.spinner();
+            return;
+        }
+        let summary = match &self.explain {
+            None => {
+                ui.label("Click Explain to see how the query runs.");
+                return;
+            }
+            Some(Err(error)) => {
+                ui.label(RichText::new(error).color(self.theme.danger_color));
+                return;
+            }
+            Some(Ok(summary)) => summary,
+        };
+        ScrollArea::both()
+            .id_source(format!("{}_explain", id_prefix))
+            .show(ui, |ui| {
+                Grid::new(format!("{}_explain_grid", id_prefix))
+                    .num_columns(2)
+                    .striped(true)
+                    .show(ui, |ui| {
+                        let mut row = |label: &str, value: String| {
+                            ui.label(RichText::new(label).strong());
+                            ui.label(value);
+                            ui.end_row();
+                        };
+                        row("Plan", summary.stages.join(" ← "));
+                        row("Returned", format_count(summary.returned));
+                        row("Keys examined", format_count(summary.keys_examined));
+                        row("Documents examined", format_count(summary.docs_examined));
+                        row("Time", format!("{} ms", summary.millis));
+                    });
+                if summary.scans_collection() {
+                    ui.label(
+                        RichText::new("The plan scans the whole collection; an index on the filtered fields would help.")
+                            .color(self.theme.warning_color),
+                    );
+                }
+                ui.add_space(10.0);
+                ui.collapsing("Full explain output", |ui| {
+                    let json = serde_json::to_string_pretty(
+                        &Bson::Document(summary.raw.clone()).into_relaxed_extjson(),
+                    )
+                    .unwrap_or_default();
+                    ui.code(json);
+                });
+            });
+    }
+
+    pub fn render_json(&mut self, ui: &mut Ui, id_prefix: &str) {
+        if self.render_status(ui) {
+            return;
+        }
         ScrollArea::both()
             .id_source(format!("{}_json", id_prefix))
             .show(ui, |ui| {
-                // This is a simplistic JSON representation. In a real application,
-                // you'd want to use a proper JSON serialization library.
-                let json = self
-                    .results
+                let documents = self
+                    .documents
                     .iter()
-                    .map(|row| format!("  {}", row.join(", ")))
-                    .collect::<Vec<_>>()
-                    .join(",\n");
-                let json = format!("[\n{}\n]", json);
+                    .map(|document| Bson::Document(document.clone()).into_relaxed_extjson())
+                    .collect::<Vec<_>>();
+                let json = serde_json::to_string_pretty(&documents).unwrap_or_default();
                 ui.code(json);
             });
     }
@@ -0,0 +1,191 @@
+use crate::components::{ConfirmationDialog, ConfirmationOutcome, ThemedButton};
+use crate::models::{SavedQuery, SavedQueryStore};
+use crate::theme::Theme;
+use egui::{Context, Grid, RichText, TextEdit, Window};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// Window for saving the current tab's query by name and opening or
+/// deleting saved ones.
+pub struct SavedQueriesWindow {
+    data_dir: PathBuf,
+    store: Option<SavedQueryStore>,
+    open: bool,
+    name: String,
+    save_requested: Option<String>,
+    open_requested: Option<SavedQuery>,
+    pending_delete: Option<(String, ConfirmationDialog)>,
+    message: Option<(String, bool)>,
+    theme: Arc<Theme>,
+}
+
+impl SavedQueriesWindow {
+    pub fn new(theme: Arc<Theme>, data_dir: &Path) -> Self {
+        Self {
+            data_dir: data_dir.to_path_buf(),
+            store: None,
+            open: false,
+            name: String::new(),
+            save_requested: None,
+            open_requested: None,
+            pending_delete: None,
+            message: None,
+            theme,
+        }
+    }
+
+    /// Opens the window with `name` suggested for saving the current query.
+    pub fn open(&mut self, name: &str) {
+        self.open = true;
+        self.name = name.to_string();
+        self.message = None;
+        match SavedQueryStore::open(&self.data_dir) {
+            Ok(store) => self.store = Some(store),
+            Err(e) => {
+                self.store = None;
+                self.message = Some((e.to_string(), true));
+            }
+        }
+    }
+
+    /// The name to save the current query as, once Save was clicked.
+    pub fn take_save_request(&mut self) -> Option<String> {
+        self.save_requested.take()
+    }
+
+    pub fn take_open_request(&mut self) -> Option<SavedQuery> {
+        self.open_requested.take()
+    }
+
+    pub fn store(&mut self, query: SavedQuery) {
+        let Some(store) = &mut self.store else {
+            return;
+        };
+        let name = query.name.clone();
+        self.message = Some(match store.save(query) {
+            Ok(()) => (format!("Saved '{}'", name), false),
+            Err(e) => (e.to_string(), true),
+        });
+    }
+
+    pub fn show(&mut self, ctx: &Context) {
+        if !self.open {
+            return;
+        }
+        self.show_confirmation(ctx);
+
+        let mut open = true;
+        let mut save = false;
+        let mut open_query = None;
+        let mut delete = None;
+        Window::new("Saved Queries")
+            .id(egui::Id::new("saved_queries_window"))
+            .open(&mut open)
+            .default_size([480.0, 360.0])
+            .vscroll(true)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("Name");
+                    ui.add(TextEdit::singleline(&mut self.name).desired_width(240.0));
+                    save = ui
+                        .add_enabled(
+                            self.store.is_some() && !self.name.trim().is_empty(),
+                            ThemedButton::new("Save Current Query", Arc::clone(&self.theme)),
+                        )
+                        .clicked();
+                });
+                if let Some((message, is_error)) = &self.message {
+                    let text = RichText::new(message);
+                    ui.label(if *is_error {
+                        text.color(self.theme.danger_color)
+                    } else {
+                        text.weak()
+                    });
+                }
+                ui.separator();
+
+                let Some(store) = &self.store else {
+                    return;
+                };
+                if store.queries().is_empty() {
+                    ui.label(RichText::new("No saved queries yet.").weak());
+                    return;
+                }
+                Grid::new("saved_queries")
+                    .num_columns(3)
+                    .striped(true)
+                    .show(ui, |ui| {
+                        for query in store.queries() {
+                            ui.label(RichText::new(&query.name).strong());
+                            ui.label(
+                                RichText::new(format!("{}.{}", query.database, query.collection))
+                                    .monospace(),
+                            );
+                            ui.horizontal(|ui| {
+                                if ui
+                                    .add(ThemedButton::new("Open", Arc::clone(&self.theme)))
+                                    .clicked()
+                                {
+                                    open_query = Some(query.clone());
+                                }
+                                if ui
+                                    .add_enabled(
+                                        self.pending_delete.is_none(),
+                                        ThemedButton::new("Delete", Arc::clone(&self.theme)),
+                                    )
+                                    .clicked()
+                                {
+                                    delete = Some(query.name.clone());
+                                }
+                            });
+                            ui.end_row();
+                        }
+                    });
+            });
+
+        if save {
+            self.save_requested = Some(self.name.trim().to_string());
+        }
+        if let Some(query) = open_query {
+            self.open_requested = Some(query);
+            open = false;
+        }
+        if let Some(name) = delete {
+            let dialog = ConfirmationDialog::new(
+                "Delete saved query",
+                format!("Delete the saved query '{}'?", name),
+                Arc::clone(&self.theme),
+            );
+            self.pending_delete = Some((name, dialog));
+        }
+        if !open {
+            self.open = false;
+            self.store = None;
+            self.pending_delete = None;
+        }
+    }
+
+    fn show_confirmation(&mut self, ctx: &Context) {
+        let Some((_, dialog)) = &mut self.pending_delete else {
+            return;
+        };
+        match dialog.show(ctx) {
+            ConfirmationOutcome::Confirmed => {
+                if let (Some((name, _)), Some(store)) =
+                    (self.pending_delete.take(), &mut self.store)
+                {
+                    self.message = Some(match store.delete(&name) {
+                        Ok(()) => (format!("Deleted '{}'", name), false),
+                        Err(e) => (e.to_string(), true),
+                    });
+                }
+            }
+            ConfirmationOutcome::Cancelled => self.pending_delete = None,
+            ConfirmationOutcome::Pending => {}
+        }
+    }
+
+    pub fn update_theme(&mut self, theme: Arc<Theme>) {
+        self.theme = theme;
+    }
+}
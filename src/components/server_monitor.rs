@@ -0,0 +1,361 @@
+use crate::components::ThemedButton;
+use crate::models::{MonitorPoint, Opcounters, ServerStatusHistory, ServerStatusSample};
+use crate::services::DatabaseService;
+use crate::theme::Theme;
+use crate::utils::error::{MongoLiteError, Result};
+use crate::utils::format::{format_bytes, format_count};
+use crate::utils::task::BackgroundTask;
+use chrono::Local;
+use egui::{Color32, Context, DragValue, RichText, Ui, Window};
+use egui_plot::{Line, Plot, PlotPoints};
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Samples kept in memory; an hour at the default interval.
+const HISTORY_CAPACITY: usize = 1800;
+const DEFAULT_INTERVAL_SECONDS: u64 = 2;
+const DEFAULT_EXPORT_FILE_NAME: &str = "server_status.csv";
+const CHART_HEIGHT: f32 = 90.0;
+
+const SERIES_COLORS: [Color32; 6] = [
+    Color32::from_rgb(15, 157, 88),  // Green
+    Color32::from_rgb(66, 133, 244), // Blue
+    Color32::from_rgb(251, 188, 4),  // Yellow
+    Color32::from_rgb(234, 67, 53),  // Red
+    Color32::from_rgb(171, 71, 188), // Purple
+    Color32::from_rgb(0, 172, 193),  // Cyan
+];
+
+struct Series {
+    name: &'static str,
+    color: Color32,
+    values: Vec<f64>,
+}
+
+/// Window charting `serverStatus` over time for one connection. It polls
+/// only while it is open and not collapsed.
+pub struct ServerMonitor {
+    service: Option<Arc<DatabaseService>>,
+    connection_id: String,
+    title: String,
+    interval_seconds: u64,
+    paused: bool,
+    /// Whether the window's contents were drawn last frame.
+    visible: bool,
+    history: ServerStatusHistory,
+    last_poll: Option<Instant>,
+    task: Option<BackgroundTask<Result<ServerStatusSample>>>,
+    error: Option<String>,
+    export_path: String,
+    export_status: Option<std::result::Result<String, String>>,
+    theme: Arc<Theme>,
+}
+
+impl ServerMonitor {
+    /// Exports go to the home directory by default, or to `data_dir` if there
+    /// is none.
+    pub fn new(theme: Arc<Theme>, data_dir: &Path) -> Self {
+        let export_path = dirs::home_dir()
+            .unwrap_or_else(|| data_dir.to_path_buf())
+            .join(DEFAULT_EXPORT_FILE_NAME);
+        Self {
+            service: None,
+            connection_id: String::new(),
+            title: String::new(),
+            interval_seconds: DEFAULT_INTERVAL_SECONDS,
+            paused: false,
+            visible: true,
+            history: ServerStatusHistory::new(HISTORY_CAPACITY),
+            last_poll: None,
+            task: None,
+            error: None,
+            export_path: export_path.display().to_string(),
+            export_status: None,
+            theme,
+        }
+    }
+
+    /// Starts monitoring the connection. The history is kept when the same
+    /// connection is opened again.
+    pub fn open(&mut self, service: Arc<DatabaseService>, connection_id: &str, name: &str) {
+        if self.connection_id != connection_id {
+            self.connection_id = connection_id.to_string();
+            self.history.clear();
+            self.task = None;
+        }
+        self.title = format!("Server Monitor · {}", name);
+        self.service = Some(service);
+        self.visible = true;
+        self.last_poll = None;
+        self.error = None;
+        self.export_status = None;
+    }
+
+    pub fn show(&mut self, ctx: &Context) {
+        let Some(service) = self.service.clone() else {
+            return;
+        };
+        if let Some(result) = self.task.as_mut().and_then(|task| task.poll()) {
+            self.task = None;
+            match result {
+                Ok(sample) => {
+                    self.history.push(sample);
+                    self.error = None;
+                }
+                Err(e) => self.error = Some(e.to_string()),
+            }
+        }
+
+        if self.visible && !self.paused && self.task.is_none() {
+            let interval = Duration::from_secs(self.interval_seconds);
+            let due = self.last_poll.map_or(Duration::ZERO, |last| {
+                interval.saturating_sub(last.elapsed())
+            });
+            if due.is_zero() {
+                self.last_poll = Some(Instant::now());
+                self.task = Some(BackgroundTask::spawn(ctx, async move {
+                    service.server_status().await
+                }));
+            } else {
+                ctx.request_repaint_after(due);
+            }
+        }
+
+        let mut open = true;
+        let shown = Window::new(&self.title)
+            .id(egui::Id::new("server_monitor"))
+            .open(&mut open)
+            .default_size([520.0, 640.0])
+            .vscroll(true)
+            .show(ctx, |ui| self.render_contents(ui));
+        self.visible = shown.is_some_and(|response| response.inner.is_some());
+
+        if !open {
+            self.service = None;
+            self.task = None;
+        }
+    }
+
+    fn render_contents(&mut self, ui: &mut Ui) {
+        ui.horizontal(|ui| {
+            ui.label("Every");
+            ui.add(
+                DragValue::new(&mut self.interval_seconds)
+                    .range(1..=60)
+                    .suffix(" s"),
+            );
+            let label = if self.paused { "Resume" } else { "Pause" };
+            if ui
+                .add(ThemedButton::new(label, Arc::clone(&self.theme)))
+                .clicked()
+            {
+                self.paused = !self.paused;
+            }
+            if ui
+                .add(ThemedButton::new("Clear", Arc::clone(&self.theme)))
+                .clicked()
+            {
+                self.history.clear();
+            }
+            if self.task.is_some() {
+                ui.spinner();
+            }
+        });
+        ui.horizontal(|ui| {
+            ui.label("CSV file:");
+            ui.text_edit_singleline(&mut self.export_path);
+            if ui
+                .add_enabled(
+                    self.history.len() > 1 && !self.export_path.trim().is_empty(),
+                    ThemedButton::new("Export", Arc::clone(&self.theme)),
+                )
+                .clicked()
+            {
+                self.export_status = Some(self.export().map_err(|e| e.to_string()));
+            }
+        });
+        match &self.export_status {
+            Some(Ok(message)) => {
+                ui.label(RichText::new(message).color(self.theme.accent_color));
+            }
+            Some(Err(message)) => {
+                ui.label(RichText::new(message).color(self.theme.danger_color));
+            }
+            None => {}
+        }
+        if let Some(error) = &self.error {
+            ui.label(RichText::new(format!("Error: {}", error)).color(self.theme.danger_color));
+        }
+        ui.label(
+            RichText::new(format!(
+                "{} of {} samples",
+                self.history.len(),
+                HISTORY_CAPACITY
+            ))
+            .small()
+            .weak(),
+        );
+        ui.separator();
+
+        let points = self.history.points();
+        if points.is_empty() {
+            ui.label(RichText::new("Waiting for two samples…").weak());
+            return;
+        }
+        let times: Vec<f64> = points
+            .iter()
+            .map(|point| point.time.timestamp_millis() as f64)
+            .collect();
+        let rate = |value: f64| format!("{:.1}/s", value);
+        let bytes_rate = |value: f64| format!("{}/s", format_bytes(value as u64));
+        let bytes = |value: f64| format_bytes(value as u64);
+        let count = |value: f64| format_count(value as u64);
+
+        let opcounters = Opcounters::NAMES
+            .into_iter()
+            .enumerate()
+            .map(|(i, name)| Series {
+                name,
+                color: SERIES_COLORS[i],
+                values: points.iter().map(|point| point.opcounters[i]).collect(),
+            })
+            .collect::<Vec<_>>();
+        self.chart(ui, "Operations", &times, &opcounters, &rate);
+
+        let connections = [
+            series(0, "current", &points, |point| {
+                point.connections_current as f64
+            }),
+            series(1, "available", &points, |point| {
+                point.connections_available as f64
+            }),
+        ];
+        self.chart(ui, "Connections", &times, &connections, &count);
+
+        let network = [
+            series(0, "in", &points, |point| point.network_in_per_second),
+            series(1, "out", &points, |point| point.network_out_per_second),
+        ];
+        self.chart(ui, "Network", &times, &network, &bytes_rate);
+
+        if points.iter().any(|point| point.cache_bytes.is_some()) {
+            let cache = [
+                series(0, "used", &points, |point| {
+                    point.cache_bytes.unwrap_or(0) as f64
+                }),
+                series(3, "maximum", &points, |point| {
+                    point.cache_max_bytes.unwrap_or(0) as f64
+                }),
+            ];
+            self.chart(ui, "WiredTiger cache", &times, &cache, &bytes);
+        }
+
+        let queues = [
+            series(0, "readers", &points, |point| point.queued_readers as f64),
+            series(2, "writers", &points, |point| point.queued_writers as f64),
+        ];
+        self.chart(ui, "Queued operations", &times, &queues, &count);
+    }
+
+    /// Draws the series as lines over time, with the latest values in the
+    /// legend and the value under the pointer on hover.
+    fn chart(
+        &self,
+        ui: &mut Ui,
+        title: &str,
+        times: &[f64],
+        series: &[Series],
+        format: &dyn Fn(f64) -> String,
+    ) {
+        ui.horizontal_wrapped(|ui| {
+            ui.label(RichText::new(title).color(self.theme.text_color).strong());
+            for series in series {
+                let latest = series.values.last().copied().unwrap_or(0.0);
+                ui.label(
+                    RichText::new(format!("■ {} {}", series.name, format(latest)))
+                        .color(series.color)
+                        .small(),
+                );
+            }
+        });
+
+        // Fixed axes, so that scrolling over a chart scrolls the window
+        Plot::new(title)
+            .height(CHART_HEIGHT)
+            .allow_drag(false)
+            .allow_zoom(false)
+            .allow_scroll(false)
+            .allow_boxed_zoom(false)
+            .allow_double_click_reset(false)
+            .include_y(0.0)
+            .x_axis_formatter(|mark, _| clock_time(mark.value))
+            .y_axis_formatter(|mark, _| {
+                if mark.value < 0.0 {
+                    String::new()
+                } else {
+                    format(mark.value)
+                }
+            })
+            .label_formatter(|name, point| {
+                if name.is_empty() {
+                    clock_time(point.x)
+                } else {
+                    format!("{}\n{}: {}", clock_time(point.x), name, format(point.y))
+                }
+            })
+            .show(ui, |plot| {
+                for series in series {
+                    let line: PlotPoints = times
+                        .iter()
+                        .zip(&series.values)
+                        .map(|(time, value)| [*time, *value])
+                        .collect();
+                    plot.line(
+                        Line::new(line)
+                            .name(series.name)
+                            .color(series.color)
+                            .width(1.5),
+                    );
+                }
+            });
+        ui.add_space(8.0);
+    }
+
+    fn export(&self) -> Result<String> {
+        let path = self.export_path.trim();
+        fs::write(path, self.history.to_csv()).map_err(|e| {
+            MongoLiteError::UnexpectedError(format!("cannot write '{}': {}", path, e))
+        })?;
+        Ok(format!(
+            "Exported {} samples to {}",
+            self.history.len(),
+            path
+        ))
+    }
+
+    pub fn update_theme(&mut self, theme: Arc<Theme>) {
+        self.theme = theme;
+    }
+}
+
+/// Formats a chart position, in milliseconds since the epoch, as local time.
+fn clock_time(millis: f64) -> String {
+    chrono::DateTime::from_timestamp_millis(millis as i64)
+        .map(|time| time.with_timezone(&Local).format("%H:%M:%S").to_string())
+        .unwrap_or_default()
+}
+
+fn series(
+    color: usize,
+    name: &'static str,
+    points: &[MonitorPoint],
+    value: impl Fn(&MonitorPoint) -> f64,
+) -> Series {
+    Series {
+        name,
+        color: SERIES_COLORS[color],
+        values: points.iter().map(value).collect(),
+    }
+}
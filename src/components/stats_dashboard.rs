@@ -0,0 +1,282 @@
+use crate::components::ThemedButton;
+use crate::models::{CollectionStats, DatabaseStats};
+use crate::services::DatabaseService;
+use crate::theme::Theme;
+use crate::utils::error::Result;
+use crate::utils::format::{format_bytes, format_count};
+use crate::utils::task::BackgroundTask;
+use egui::{Context, Grid, ProgressBar, RichText, Ui, Widget, Window};
+use std::sync::Arc;
+
+/// Stats to show for a database, or one of its collections.
+pub struct StatsRequest {
+    pub connection_id: String,
+    pub database: String,
+    pub collection: Option<String>,
+}
+
+enum Stats {
+    Database(DatabaseStats),
+    Collection(CollectionStats),
+}
+
+/// Window with the storage figures of a database or collection: document
+/// counts, data and storage sizes, compression, reusable space and indexes.
+pub struct StatsDashboard {
+    service: Option<Arc<DatabaseService>>,
+    title: String,
+    database: String,
+    collection: Option<String>,
+    stats: Option<Stats>,
+    error: Option<String>,
+    task: Option<BackgroundTask<Result<Stats>>>,
+    theme: Arc<Theme>,
+}
+
+impl StatsDashboard {
+    pub fn new(theme: Arc<Theme>) -> Self {
+        Self {
+            service: None,
+            title: String::new(),
+            database: String::new(),
+            collection: None,
+            stats: None,
+            error: None,
+            task: None,
+            theme,
+        }
+    }
+
+    /// Shows the stats of `database`, or of `collection` in it, read through
+    /// `service`. `connection_name` is only used in the title.
+    pub fn open(
+        &mut self,
+        ctx: &Context,
+        service: Arc<DatabaseService>,
+        connection_name: &str,
+        database: String,
+        collection: Option<String>,
+    ) {
+        let namespace = match &collection {
+            Some(collection) => format!("{}.{}", database, collection),
+            None => database.clone(),
+        };
+        self.title = format!("Stats · {} · {}", connection_name, namespace);
+        self.service = Some(service);
+        self.database = database;
+        self.collection = collection;
+        self.stats = None;
+        self.refresh(ctx);
+    }
+
+    fn refresh(&mut self, ctx: &Context) {
+        let Some(service) = self.service.clone() else {
+            return;
+        };
+        let database = self.database.clone();
+        let collection = self.collection.clone();
+        self.error = None;
+        self.task = Some(BackgroundTask::spawn(ctx, async move {
+            match collection {
+                Some(collection) => service
+                    .collection_stats(&database, &collection)
+                    .await
+                    .map(Stats::Collection),
+                None => service.database_stats(&database).await.map(Stats::Database),
+            }
+        }));
+    }
+
+    pub fn show(&mut self, ctx: &Context) {
+        if self.service.is_none() {
+            return;
+        }
+        if let Some(result) = self.task.as_mut().and_then(|task| task.poll()) {
+            self.task = None;
+            match result {
+                Ok(stats) => self.stats = Some(stats),
+                Err(e) => self.error = Some(e.to_string()),
+            }
+        }
+
+        let mut open = true;
+        let mut refresh = false;
+        Window::new(&self.title)
+            .id(egui::Id::new("stats_dashboard"))
+            .open(&mut open)
+            .default_size([440.0, 480.0])
+            .vscroll(true)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    refresh = ui
+                        .add_enabled(
+                            self.task.is_none(),
+                            ThemedButton::new("Refresh", Arc::clone(&self.theme)),
+                        )
+                        .clicked();
+                    if self.task.is_some() {
+                        ui.spinner();
+                    }
+                });
+                if let Some(error) = &self.error {
+                    ui.label(
+                        RichText::new(format!("Error: {}", error)).color(self.theme.danger_color),
+                    );
+                }
+                ui.add_space(5.0);
+                match &self.stats {
+                    Some(Stats::Database(stats)) => self.render_database(ui, stats),
+                    Some(Stats::Collection(stats)) => self.render_collection(ui, stats),
+                    None => {}
+                }
+            });
+
+        if !open {
+            self.service = None;
+            self.task = None;
+        } else if refresh {
+            self.refresh(ctx);
+        }
+    }
+
+    fn render_database(&self, ui: &mut Ui, stats: &DatabaseStats) {
+        Grid::new("stats_dashboard_database")
+            .num_columns(2)
+            .striped(true)
+            .show(ui, |ui| {
+                row(ui, "Collections", format_count(stats.collections));
+                row(ui, "Views", format_count(stats.views));
+                row(ui, "Documents", format_count(stats.objects));
+                row(
+                    ui,
+                    "Average document size",
+                    format_bytes(stats.avg_obj_size.round() as u64),
+                );
+                row(ui, "Data size", format_bytes(stats.data_size));
+                row(ui, "Storage size", format_bytes(stats.storage_size));
+                row(
+                    ui,
+                    "Compression ratio",
+                    format_ratio(stats.compression_ratio()),
+                );
+                row(ui, "Indexes", format_count(stats.indexes));
+                row(ui, "Index size", format_bytes(stats.index_size));
+            });
+        ui.add_space(10.0);
+        self.render_fragmentation(ui, stats.free_storage_size, stats.fragmentation());
+    }
+
+    fn render_collection(&self, ui: &mut Ui, stats: &CollectionStats) {
+        Grid::new("stats_dashboard_collection")
+            .num_columns(2)
+            .striped(true)
+            .show(ui, |ui| {
+                row(ui, "Documents", format_count(stats.count));
+                row(
+                    ui,
+                    "Average document size",
+                    format_bytes(stats.avg_obj_size.round() as u64),
+                );
+                row(ui, "Data size", format_bytes(stats.data_size));
+                row(ui, "Storage size", format_bytes(stats.storage_size));
+                row(
+                    ui,
+                    "Compression ratio",
+                    format_ratio(stats.compression_ratio()),
+                );
+                row(ui, "Total index size", format_bytes(stats.total_index_size));
+                if stats.capped {
+                    row(ui, "Capped", "yes".to_string());
+                }
+            });
+        ui.add_space(10.0);
+        self.render_fragmentation(ui, stats.free_storage_size, stats.fragmentation());
+
+        ui.add_space(10.0);
+        ui.label(
+            RichText::new("Indexes")
+                .color(self.theme.text_color)
+                .strong(),
+        );
+        Grid::new("stats_dashboard_indexes")
+            .num_columns(3)
+            .striped(true)
+            .show(ui, |ui| {
+                ui.label(RichText::new("Name").strong());
+                ui.label(RichText::new("Size").strong());
+                ui.label(RichText::new("Accesses").strong());
+                ui.end_row();
+                for index in &stats.indexes {
+                    ui.label(&index.name);
+                    let share = if stats.total_index_size > 0 {
+                        index.size as f32 / stats.total_index_size as f32
+                    } else {
+                        0.0
+                    };
+                    ProgressBar::new(share)
+                        .desired_width(140.0)
+                        .text(format_bytes(index.size))
+                        .ui(ui);
+                    let accesses = match (index.accesses, index.since) {
+                        (Some(accesses), Some(since)) => format!(
+                            "{} since {}",
+                            format_count(accesses),
+                            since.try_to_rfc3339_string().unwrap_or_default()
+                        ),
+                        (Some(accesses), None) => format_count(accesses),
+                        (None, _) => "–".to_string(),
+                    };
+                    ui.label(accesses);
+                    ui.end_row();
+                }
+            });
+        if stats.indexes.iter().all(|index| index.accesses.is_none()) {
+            ui.label(
+                RichText::new("Index usage needs the indexStats privilege.")
+                    .small()
+                    .weak(),
+            );
+        }
+    }
+
+    fn render_fragmentation(&self, ui: &mut Ui, free: Option<u64>, fragmentation: Option<f64>) {
+        ui.label(
+            RichText::new("Reusable space")
+                .color(self.theme.text_color)
+                .strong(),
+        )
+        .on_hover_text(
+            "Space on disk that is allocated but free; compacting the collection returns it",
+        );
+        match (free, fragmentation) {
+            (Some(free), Some(fragmentation)) => {
+                ProgressBar::new(fragmentation as f32)
+                    .text(format!(
+                        "{} ({:.1}% of storage)",
+                        format_bytes(free),
+                        fragmentation * 100.0
+                    ))
+                    .ui(ui);
+            }
+            _ => {
+                ui.label(RichText::new("Not reported by this server").small().weak());
+            }
+        }
+    }
+
+    pub fn update_theme(&mut self, theme: Arc<Theme>) {
+        self.theme = theme;
+    }
+}
+
+fn row(ui: &mut Ui, label: &str, value: String) {
+    ui.label(RichText::new(label).strong());
+    ui.label(value);
+    ui.end_row();
+}
+
+fn format_ratio(ratio: Option<f64>) -> String {
+    ratio
+        .map(|ratio| format!("{:.2}×", ratio))
+        .unwrap_or_else(|| "–".to_string())
+}
@@ -2,9 +2,18 @@ use crate::components::Component;
 use crate::theme::Theme;
 use egui::{RichText, Ui};
 use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// How often the elapsed time of a running query is redrawn.
+const ELAPSED_REFRESH: Duration = Duration::from_millis(100);
 
 pub struct StatusBar {
     status: String,
+    read_only: bool,
+    /// The connected deployment and its primary, e.g. from `Topology::summary`.
+    topology: Option<String>,
+    /// When the active tab's running query was started.
+    query_started: Option<Instant>,
     theme: Arc<Theme>,
 }
 
@@ -12,6 +21,9 @@ impl StatusBar {
     pub fn new(theme: Arc<Theme>) -> Self {
         Self {
             status: String::new(),
+            read_only: false,
+            topology: None,
+            query_started: None,
             theme,
         }
     }
@@ -19,6 +31,18 @@ impl StatusBar {
     pub fn set_status(&mut self, status: String) {
         self.status = status;
     }
+
+    pub fn set_read_only(&mut self, read_only: bool) {
+        self.read_only = read_only;
+    }
+
+    pub fn set_topology(&mut self, topology: Option<String>) {
+        self.topology = topology;
+    }
+
+    pub fn set_query_started(&mut self, query_started: Option<Instant>) {
+        self.query_started = query_started;
+    }
 }
 
 impl Component for StatusBar {
@@ -30,6 +54,28 @@ impl Component for StatusBar {
                     .strong(),
             );
             ui.label(RichText::new(&self.status).color(self.theme.text_color));
+            if let Some(topology) = &self.topology {
+                ui.separator();
+                ui.label(RichText::new(topology).color(self.theme.text_color));
+            }
+            if let Some(started) = self.query_started {
+                ui.separator();
+                ui.label(
+                    RichText::new(format!(
+                        "Query running for {:.1} s",
+                        started.elapsed().as_secs_f32()
+                    ))
+                    .color(self.theme.text_color),
+                );
+                ui.ctx().request_repaint_after(ELAPSED_REFRESH);
+            }
+            if self.read_only {
+                ui.label(
+                    RichText::new("🔒 Read-only")
+                        .color(self.theme.danger_color)
+                        .strong(),
+                );
+            }
         });
     }
 
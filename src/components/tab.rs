@@ -30,6 +30,16 @@ impl<T> Tab<T> {
         self.contents.push(Box::new(content));
     }
 
+    pub fn selected(&self) -> usize {
+        self.active_tab
+    }
+
+    pub fn select(&mut self, index: usize) {
+        if index < self.titles.len() {
+            self.active_tab = index;
+        }
+    }
+
     pub fn render(&mut self, ui: &mut Ui, data: &mut T) {
         let theme = &self.theme;
 
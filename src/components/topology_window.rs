@@ -0,0 +1,281 @@
+use crate::components::ThemedButton;
+use crate::models::{
+    ConnectionEntry, ReplicaSetStatus, ShardedClusterStatus, Topology, TopologyKind,
+};
+use crate::services::DatabaseService;
+use crate::theme::Theme;
+use crate::utils::error::Result;
+use crate::utils::format::format_count;
+use crate::utils::task::BackgroundTask;
+use egui::{CollapsingHeader, Context, Grid, ProgressBar, RichText, Ui, Window};
+use std::sync::Arc;
+
+/// Window showing the deployment a connection is to: the members of a
+/// replica set, or the shards, balancer and chunk distribution of a sharded
+/// cluster.
+pub struct TopologyWindow {
+    service: Option<Arc<DatabaseService>>,
+    title: String,
+    topology: Option<Topology>,
+    topology_task: Option<BackgroundTask<Result<Topology>>>,
+    replica_set: Option<ReplicaSetStatus>,
+    replica_set_task: Option<BackgroundTask<Result<ReplicaSetStatus>>>,
+    cluster: Option<ShardedClusterStatus>,
+    cluster_task: Option<BackgroundTask<Result<ShardedClusterStatus>>>,
+    error: Option<String>,
+    theme: Arc<Theme>,
+}
+
+impl TopologyWindow {
+    pub fn new(theme: Arc<Theme>) -> Self {
+        Self {
+            service: None,
+            title: String::new(),
+            topology: None,
+            topology_task: None,
+            replica_set: None,
+            replica_set_task: None,
+            cluster: None,
+            cluster_task: None,
+            error: None,
+            theme,
+        }
+    }
+
+    pub fn open(&mut self, ctx: &Context, connection: &ConnectionEntry) {
+        self.title = format!("Topology · {}", connection.name);
+        self.service = Some(Arc::clone(&connection.service));
+        self.topology = connection.topology.clone();
+        self.replica_set = None;
+        self.cluster = None;
+        self.refresh(ctx);
+    }
+
+    /// Reads the topology again, then the details for its kind.
+    fn refresh(&mut self, ctx: &Context) {
+        let Some(service) = self.service.clone() else {
+            return;
+        };
+        self.error = None;
+        self.replica_set_task = None;
+        self.cluster_task = None;
+        self.topology_task = Some(BackgroundTask::spawn(ctx, async move {
+            service.topology().await
+        }));
+    }
+
+    fn load_details(&mut self, ctx: &Context) {
+        let (Some(service), Some(topology)) = (self.service.clone(), &self.topology) else {
+            return;
+        };
+        match topology.kind {
+            TopologyKind::ReplicaSet => {
+                self.replica_set_task = Some(BackgroundTask::spawn(ctx, async move {
+                    service.replica_set_status().await
+                }))
+            }
+            TopologyKind::Sharded => {
+                self.cluster_task = Some(BackgroundTask::spawn(ctx, async move {
+                    service.sharded_cluster_status().await
+                }))
+            }
+            TopologyKind::Standalone => {}
+        }
+    }
+
+    fn loading(&self) -> bool {
+        self.topology_task.is_some()
+            || self.replica_set_task.is_some()
+            || self.cluster_task.is_some()
+    }
+
+    pub fn show(&mut self, ctx: &Context) {
+        if self.service.is_none() {
+            return;
+        }
+        if let Some(result) = self.topology_task.as_mut().and_then(|task| task.poll()) {
+            self.topology_task = None;
+            match result {
+                Ok(topology) => {
+                    self.topology = Some(topology);
+                    self.load_details(ctx);
+                }
+                Err(e) => self.error = Some(e.to_string()),
+            }
+        }
+        if let Some(result) = self.replica_set_task.as_mut().and_then(|task| task.poll()) {
+            self.replica_set_task = None;
+            match result {
+                Ok(replica_set) => self.replica_set = Some(replica_set),
+                Err(e) => self.error = Some(e.to_string()),
+            }
+        }
+        if let Some(result) = self.cluster_task.as_mut().and_then(|task| task.poll()) {
+            self.cluster_task = None;
+            match result {
+                Ok(cluster) => self.cluster = Some(cluster),
+                Err(e) => self.error = Some(e.to_string()),
+            }
+        }
+
+        let mut open = true;
+        let mut refresh = false;
+        Window::new(&self.title)
+            .id(egui::Id::new("topology_window"))
+            .open(&mut open)
+            .default_size([560.0, 420.0])
+            .vscroll(true)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    match &self.topology {
+                        Some(topology) => ui.label(RichText::new(topology.summary()).strong()),
+                        None => ui.label(RichText::new("Reading topology…").weak()),
+                    };
+                    ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                        refresh = ui
+                            .add_enabled(
+                                !self.loading(),
+                                ThemedButton::new("Refresh", Arc::clone(&self.theme)),
+                            )
+                            .clicked();
+                        if self.loading() {
+                            ui.spinner();
+                        }
+                    });
+                });
+                if let Some(error) = &self.error {
+                    ui.label(RichText::new(error).color(self.theme.danger_color));
+                }
+                ui.separator();
+                match self.topology.as_ref().map(|topology| topology.kind) {
+                    Some(TopologyKind::ReplicaSet) => self.render_replica_set(ui),
+                    Some(TopologyKind::Sharded) => self.render_cluster(ui),
+                    Some(TopologyKind::Standalone) => {
+                        ui.label(
+                            RichText::new("A standalone server has no members or shards.").weak(),
+                        );
+                    }
+                    None => {}
+                }
+            });
+
+        if !open {
+            self.service = None;
+            self.topology_task = None;
+            self.replica_set_task = None;
+            self.cluster_task = None;
+        } else if refresh {
+            self.refresh(ctx);
+        }
+    }
+
+    fn render_replica_set(&self, ui: &mut Ui) {
+        let Some(replica_set) = &self.replica_set else {
+            return;
+        };
+        Grid::new("topology_members")
+            .num_columns(6)
+            .striped(true)
+            .show(ui, |ui| {
+                for header in ["Member", "State", "Health", "Lag", "Priority", "Votes"] {
+                    ui.label(RichText::new(header).strong());
+                }
+                ui.end_row();
+                for member in &replica_set.members {
+                    let mut name = RichText::new(&member.name);
+                    if member.is_self {
+                        name = name.strong();
+                    }
+                    let response = ui.label(name);
+                    if member.is_self {
+                        response.on_hover_text("The member this connection talks to");
+                    }
+
+                    let state = RichText::new(&member.state);
+                    ui.label(if member.is_primary() {
+                        state.color(self.theme.accent_color).strong()
+                    } else {
+                        state
+                    });
+                    if member.healthy {
+                        ui.label("Healthy");
+                    } else {
+                        ui.label(RichText::new("Down").color(self.theme.danger_color));
+                    }
+                    ui.label(match member.lag_seconds {
+                        Some(lag) => format!("{} s", format_count(lag as u64)),
+                        None => "–".to_string(),
+                    });
+                    let priority = if member.hidden {
+                        format!("{} (hidden)", member.priority)
+                    } else {
+                        member.priority.to_string()
+                    };
+                    ui.label(priority);
+                    ui.label(member.votes.to_string());
+                    ui.end_row();
+                }
+            });
+    }
+
+    fn render_cluster(&self, ui: &mut Ui) {
+        let Some(cluster) = &self.cluster else {
+            return;
+        };
+        ui.label(format!("Connected through mongos {}", cluster.mongos));
+        let balancer = match (cluster.balancer.enabled(), cluster.balancer.in_round) {
+            (true, true) => "Balancer on, balancing now",
+            (true, false) => "Balancer on",
+            (false, _) => "Balancer off",
+        };
+        ui.label(balancer);
+        ui.add_space(5.0);
+
+        ui.label(RichText::new("Shards").strong());
+        Grid::new("topology_shards")
+            .num_columns(2)
+            .striped(true)
+            .show(ui, |ui| {
+                for shard in &cluster.shards {
+                    let id = if shard.draining {
+                        format!("{} (draining)", shard.id)
+                    } else {
+                        shard.id.clone()
+                    };
+                    ui.label(id);
+                    ui.label(RichText::new(&shard.host).monospace());
+                    ui.end_row();
+                }
+            });
+        ui.add_space(5.0);
+
+        ui.label(RichText::new("Chunks per collection").strong());
+        if cluster.chunks.is_empty() {
+            ui.label(RichText::new("No sharded collections.").weak());
+        }
+        for distribution in &cluster.chunks {
+            let total = distribution.total();
+            CollapsingHeader::new(format!(
+                "{} · {} chunks",
+                distribution.namespace,
+                format_count(total)
+            ))
+            .id_source(("topology_chunks", &distribution.namespace))
+            .show(ui, |ui| {
+                for (shard, chunks) in &distribution.shards {
+                    ui.add(
+                        ProgressBar::new(*chunks as f32 / total.max(1) as f32).text(format!(
+                            "{} · {}",
+                            shard,
+                            format_count(*chunks)
+                        )),
+                    );
+                }
+            });
+        }
+    }
+
+    pub fn update_theme(&mut self, theme: Arc<Theme>) {
+        self.theme = theme;
+    }
+}
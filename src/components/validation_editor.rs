@@ -0,0 +1,438 @@
+use crate::components::ThemedButton;
+use crate::models::{
+    generate_json_schema, ConnectionEntry, ValidationAction, ValidationCheck, ValidationLevel,
+    ValidationRules,
+};
+use crate::services::DatabaseService;
+use crate::theme::Theme;
+use crate::utils::error::Result;
+use crate::utils::extended_json::parse_document;
+use crate::utils::format::format_count;
+use crate::utils::task::BackgroundTask;
+use egui::{CollapsingHeader, ComboBox, Context, DragValue, RichText, TextEdit, Ui, Window};
+use mongodb::bson::{Bson, Document};
+use std::sync::Arc;
+
+const DEFAULT_SAMPLE_SIZE: i64 = 100;
+/// Failing documents shown after a check.
+const FAILING_EXAMPLES: i64 = 50;
+
+/// The collection whose validation rules were asked for.
+pub struct ValidationRequest {
+    pub connection_id: String,
+    pub database: String,
+    pub collection: String,
+}
+
+/// Window for reading and changing a collection's validator, level and
+/// action, with a `$jsonSchema` generated from sampled documents and a check
+/// of which documents a validator would reject.
+pub struct ValidationEditor {
+    service: Option<Arc<DatabaseService>>,
+    read_only: bool,
+    title: String,
+    database: String,
+    collection: String,
+    validator: String,
+    level: ValidationLevel,
+    action: ValidationAction,
+    sample_size: i64,
+    load_task: Option<BackgroundTask<Result<ValidationRules>>>,
+    apply_task: Option<BackgroundTask<Result<()>>>,
+    sample_task: Option<BackgroundTask<Result<Vec<Document>>>>,
+    check_task: Option<BackgroundTask<Result<ValidationCheck>>>,
+    check: Option<ValidationCheck>,
+    /// The outcome of the last action, and whether it failed.
+    message: Option<(String, bool)>,
+    theme: Arc<Theme>,
+}
+
+impl ValidationEditor {
+    pub fn new(theme: Arc<Theme>) -> Self {
+        Self {
+            service: None,
+            read_only: false,
+            title: String::new(),
+            database: String::new(),
+            collection: String::new(),
+            validator: String::new(),
+            level: ValidationLevel::Strict,
+            action: ValidationAction::Error,
+            sample_size: DEFAULT_SAMPLE_SIZE,
+            load_task: None,
+            apply_task: None,
+            sample_task: None,
+            check_task: None,
+            check: None,
+            message: None,
+            theme,
+        }
+    }
+
+    pub fn open(
+        &mut self,
+        ctx: &Context,
+        connection: &ConnectionEntry,
+        database: String,
+        collection: String,
+    ) {
+        self.title = format!(
+            "Validation · {} · {}.{}",
+            connection.name, database, collection
+        );
+        self.service = Some(Arc::clone(&connection.service));
+        self.read_only = connection.read_only();
+        self.database = database;
+        self.collection = collection;
+        self.validator.clear();
+        self.check = None;
+        self.message = None;
+        self.apply_task = None;
+        self.sample_task = None;
+        self.check_task = None;
+        self.load(ctx);
+    }
+
+    fn load(&mut self, ctx: &Context) {
+        let Some(service) = self.service.clone() else {
+            return;
+        };
+        let (database, collection) = (self.database.clone(), self.collection.clone());
+        self.load_task = Some(BackgroundTask::spawn(ctx, async move {
+            service.validation_rules(&database, &collection).await
+        }));
+    }
+
+    /// The rules as edited. An empty validator removes validation.
+    fn rules(&self) -> std::result::Result<ValidationRules, String> {
+        let validator = if self.validator.trim().is_empty() {
+            Document::new()
+        } else {
+            parse_document(&self.validator).map_err(|e| format!("Validator: {}", e))?
+        };
+        Ok(ValidationRules {
+            validator,
+            level: self.level,
+            action: self.action,
+        })
+    }
+
+    fn apply(&mut self, ctx: &Context) {
+        let Some(service) = self.service.clone() else {
+            return;
+        };
+        let rules = match self.rules() {
+            Ok(rules) => rules,
+            Err(e) => {
+                self.message = Some((e, true));
+                return;
+            }
+        };
+        let (database, collection) = (self.database.clone(), self.collection.clone());
+        self.apply_task = Some(BackgroundTask::spawn(ctx, async move {
+            service
+                .set_validation_rules(&database, &collection, &rules)
+                .await
+        }));
+    }
+
+    fn generate(&mut self, ctx: &Context) {
+        let Some(service) = self.service.clone() else {
+            return;
+        };
+        let (database, collection) = (self.database.clone(), self.collection.clone());
+        let size = self.sample_size;
+        self.sample_task = Some(BackgroundTask::spawn(ctx, async move {
+            service.sample_documents(&database, &collection, size).await
+        }));
+    }
+
+    /// Looks for documents the edited validator rejects, in a sample or the
+    /// whole collection.
+    fn check(&mut self, ctx: &Context, sample: bool) {
+        let Some(service) = self.service.clone() else {
+            return;
+        };
+        let validator = match self.rules() {
+            Ok(rules) if rules.validator.is_empty() => {
+                self.message = Some(("There is no validator to test".to_string(), true));
+                return;
+            }
+            Ok(rules) => rules.validator,
+            Err(e) => {
+                self.message = Some((e, true));
+                return;
+            }
+        };
+        let (database, collection) = (self.database.clone(), self.collection.clone());
+        let sample = sample.then_some(self.sample_size);
+        self.check = None;
+        self.check_task = Some(BackgroundTask::spawn(ctx, async move {
+            service
+                .check_validator(&database, &collection, validator, sample, FAILING_EXAMPLES)
+                .await
+        }));
+    }
+
+    fn busy(&self) -> bool {
+        self.load_task.is_some()
+            || self.apply_task.is_some()
+            || self.sample_task.is_some()
+            || self.check_task.is_some()
+    }
+
+    fn poll_tasks(&mut self) {
+        if let Some(result) = self.load_task.as_mut().and_then(|task| task.poll()) {
+            self.load_task = None;
+            match result {
+                Ok(rules) => {
+                    self.validator = if rules.validator.is_empty() {
+                        String::new()
+                    } else {
+                        to_json(&rules.validator)
+                    };
+                    self.level = rules.level;
+                    self.action = rules.action;
+                }
+                Err(e) => self.message = Some((e.to_string(), true)),
+            }
+        }
+        if let Some(result) = self.apply_task.as_mut().and_then(|task| task.poll()) {
+            self.apply_task = None;
+            self.message = Some(match result {
+                Ok(()) => ("Validation rules saved".to_string(), false),
+                Err(e) => (e.to_string(), true),
+            });
+        }
+        if let Some(result) = self.sample_task.as_mut().and_then(|task| task.poll()) {
+            self.sample_task = None;
+            self.message = Some(match result {
+                Ok(samples) if samples.is_empty() => (
+                    "The collection has no documents to sample".to_string(),
+                    true,
+                ),
+                Ok(samples) => {
+                    self.validator = to_json(&generate_json_schema(&samples));
+                    (
+                        format!(
+                            "Generated from {} sampled documents; review it before applying",
+                            samples.len()
+                        ),
+                        false,
+                    )
+                }
+                Err(e) => (e.to_string(), true),
+            });
+        }
+        if let Some(result) = self.check_task.as_mut().and_then(|task| task.poll()) {
+            self.check_task = None;
+            match result {
+                Ok(check) => {
+                    self.message = None;
+                    self.check = Some(check);
+                }
+                Err(e) => self.message = Some((e.to_string(), true)),
+            }
+        }
+    }
+
+    pub fn show(&mut self, ctx: &Context) {
+        if self.service.is_none() {
+            return;
+        }
+        self.poll_tasks();
+
+        let mut open = true;
+        let mut action = None;
+        Window::new(&self.title)
+            .id(egui::Id::new("validation_editor"))
+            .open(&mut open)
+            .default_size([560.0, 620.0])
+            .vscroll(true)
+            .show(ctx, |ui| action = self.render_contents(ui));
+
+        if !open {
+            self.service = None;
+            return;
+        }
+        match action {
+            Some(EditorAction::Reload) => {
+                self.message = None;
+                self.load(ctx);
+            }
+            Some(EditorAction::Apply) => self.apply(ctx),
+            Some(EditorAction::Generate) => self.generate(ctx),
+            Some(EditorAction::Check { sample }) => self.check(ctx, sample),
+            None => {}
+        }
+    }
+
+    fn render_contents(&mut self, ui: &mut Ui) -> Option<EditorAction> {
+        let mut action = None;
+        let idle = !self.busy();
+        ui.horizontal(|ui| {
+            ui.label("Level:");
+            ComboBox::from_id_source("validation_level")
+                .selected_text(self.level.as_str())
+                .show_ui(ui, |ui| {
+                    for level in ValidationLevel::ALL {
+                        ui.selectable_value(&mut self.level, level, level.as_str())
+                            .on_hover_text(level.description());
+                    }
+                });
+            ui.add_space(10.0);
+            ui.label("Action:");
+            ComboBox::from_id_source("validation_action")
+                .selected_text(self.action.as_str())
+                .show_ui(ui, |ui| {
+                    for validation_action in ValidationAction::ALL {
+                        ui.selectable_value(
+                            &mut self.action,
+                            validation_action,
+                            validation_action.as_str(),
+                        )
+                        .on_hover_text(validation_action.description());
+                    }
+                });
+            if self.busy() {
+                ui.spinner();
+            }
+        });
+
+        ui.label(
+            RichText::new("Validator:")
+                .color(self.theme.text_color)
+                .strong(),
+        )
+        .on_hover_text("A query filter or { \"$jsonSchema\": … } as extended JSON. Leave it empty to turn validation off.");
+        ui.add(
+            TextEdit::multiline(&mut self.validator)
+                .code_editor()
+                .desired_rows(14)
+                .desired_width(f32::INFINITY),
+        );
+
+        ui.horizontal(|ui| {
+            ui.label("Sample size:");
+            ui.add(DragValue::new(&mut self.sample_size).range(1..=10_000));
+            if ui
+                .add_enabled(
+                    idle,
+                    ThemedButton::new("Generate Schema", Arc::clone(&self.theme)),
+                )
+                .on_hover_text(
+                    "Replace the validator with a $jsonSchema describing sampled documents",
+                )
+                .clicked()
+            {
+                action = Some(EditorAction::Generate);
+            }
+            if ui
+                .add_enabled(
+                    idle,
+                    ThemedButton::new("Test on Sample", Arc::clone(&self.theme)),
+                )
+                .clicked()
+            {
+                action = Some(EditorAction::Check { sample: true });
+            }
+            if ui
+                .add_enabled(
+                    idle,
+                    ThemedButton::new("Find Failing Documents", Arc::clone(&self.theme)),
+                )
+                .on_hover_text("Check every document in the collection")
+                .clicked()
+            {
+                action = Some(EditorAction::Check { sample: false });
+            }
+        });
+        ui.horizontal(|ui| {
+            if ui
+                .add_enabled(idle, ThemedButton::new("Reload", Arc::clone(&self.theme)))
+                .on_hover_text("Discard the edits and read the collection's rules again")
+                .clicked()
+            {
+                action = Some(EditorAction::Reload);
+            }
+            if ui
+                .add_enabled(
+                    idle && !self.read_only,
+                    ThemedButton::new("Apply", Arc::clone(&self.theme)),
+                )
+                .on_disabled_hover_text("Not on a read-only connection")
+                .clicked()
+            {
+                action = Some(EditorAction::Apply);
+            }
+        });
+
+        if let Some((message, failed)) = &self.message {
+            let color = if *failed {
+                self.theme.danger_color
+            } else {
+                self.theme.accent_color
+            };
+            ui.label(RichText::new(message).color(color));
+        }
+        if let Some(check) = &self.check {
+            self.render_check(ui, check);
+        }
+        action
+    }
+
+    fn render_check(&self, ui: &mut Ui, check: &ValidationCheck) {
+        ui.separator();
+        let summary = match check.sampled {
+            Some(sampled) => format!(
+                "{} of {} sampled documents fail validation",
+                format_count(check.failing),
+                format_count(sampled)
+            ),
+            None => format!(
+                "{} documents in the collection fail validation",
+                format_count(check.failing)
+            ),
+        };
+        let color = if check.failing == 0 {
+            self.theme.accent_color
+        } else {
+            self.theme.warning_color
+        };
+        ui.label(RichText::new(summary).color(color).strong());
+        if check.failing > check.examples.len() as u64 {
+            ui.label(
+                RichText::new(format!("Showing the first {}", check.examples.len()))
+                    .small()
+                    .weak(),
+            );
+        }
+        for (index, document) in check.examples.iter().enumerate() {
+            let label = match document.get("_id") {
+                Some(id) => format!("_id: {}", id.clone().into_relaxed_extjson()),
+                None => format!("Document {}", index + 1),
+            };
+            CollapsingHeader::new(label)
+                .id_source(("validation_failing", index))
+                .show(ui, |ui| {
+                    ui.code(to_json(document));
+                });
+        }
+    }
+
+    pub fn update_theme(&mut self, theme: Arc<Theme>) {
+        self.theme = theme;
+    }
+}
+
+enum EditorAction {
+    Reload,
+    Apply,
+    Generate,
+    Check { sample: bool },
+}
+
+fn to_json(document: &Document) -> String {
+    serde_json::to_string_pretty(&Bson::Document(document.clone()).into_relaxed_extjson())
+        .unwrap_or_default()
+}
@@ -1,4 +1,6 @@
 use crate::models::MongoDBClient;
+use crate::services::askpass_reply;
+use crate::utils::data_dir;
 
 mod components;
 mod models;
@@ -7,10 +9,29 @@ mod theme;
 mod utils;
 
 fn main() -> Result<(), eframe::Error> {
+    // Started by ssh to answer a password or passphrase prompt for a tunnel
+    if let Some(reply) = askpass_reply() {
+        println!("{}", reply);
+        return Ok(());
+    }
+
+    let data_dir = match data_dir::resolve(std::env::args().skip(1)) {
+        Ok(dir) => dir,
+        Err(message) => {
+            eprintln!("{}", message);
+            std::process::exit(2);
+        }
+    };
+
+    // Database work runs on this runtime; entering it lets the UI thread
+    // spawn tasks with `tokio::spawn`.
+    let runtime = tokio::runtime::Runtime::new().expect("Failed to start tokio runtime");
+    let _guard = runtime.enter();
+
     let options = eframe::NativeOptions::default();
     eframe::run_native(
         "Mongolite",
         options,
-        Box::new(|cc| Ok(Box::new(MongoDBClient::new(cc)))),
+        Box::new(move |cc| Ok(Box::new(MongoDBClient::new(cc, &data_dir)))),
     )
 }
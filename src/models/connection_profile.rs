@@ -1,100 +1,469 @@
-use redb::{Database, Error as RedbError, ReadableTable, TableDefinition, TableError};
+use crate::models::profile_schema;
+use crate::models::{ConflictPolicy, ImportSummary, MatchBy};
+use crate::utils::data_dir;
+use crate::utils::error::{MongoLiteError, Result};
+use chrono::Local;
+use redb::{Database, Error as RedbError, ReadableTable, TableDefinition};
 use serde::{Deserialize, Serialize};
 use std::cell::RefCell;
-use std::path::PathBuf;
+use std::fs::{self, File};
+use std::io::{ErrorKind, Read};
+use std::path::{Path, PathBuf};
 use std::rc::Rc;
+use uuid::Uuid;
 
+pub const PROFILES_FILE_NAME: &str = "mongolite_profiles.redb";
 const PROFILES_TABLE: TableDefinition<&str, &[u8]> = TableDefinition::new("profiles");
+// redb silently re-initializes any file that doesn't start with its magic
+// number, so this is checked before opening to avoid wiping a damaged store.
+const REDB_MAGIC: [u8; 9] = [b'r', b'e', b'd', b'b', 0x1A, 0x0A, 0xA9, 0x0D, 0x0A];
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Environment {
+    #[default]
+    None,
+    Development,
+    Staging,
+    Production,
+}
+
+impl Environment {
+    pub const ALL: [Environment; 4] = [
+        Environment::None,
+        Environment::Development,
+        Environment::Staging,
+        Environment::Production,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Environment::None => "None",
+            Environment::Development => "Development",
+            Environment::Staging => "Staging",
+            Environment::Production => "Production",
+        }
+    }
+}
+
+pub const DEFAULT_SSH_PORT: u16 = 22;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SshAuthMethod {
+    #[default]
+    Password,
+    /// A private key file, or the SSH agent and default keys when no file is
+    /// given.
+    PrivateKey,
+}
+
+impl SshAuthMethod {
+    pub const ALL: [SshAuthMethod; 2] = [SshAuthMethod::Password, SshAuthMethod::PrivateKey];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            SshAuthMethod::Password => "Password",
+            SshAuthMethod::PrivateKey => "Private key",
+        }
+    }
+}
+
+/// Bastion host the connection is forwarded through. The password and key
+/// passphrase accept the same `${VAR}` and `${file:PATH}` placeholders as
+/// connection strings.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SshTunnelSettings {
+    pub host: String,
+    pub port: u16,
+    pub user: String,
+    pub auth_method: SshAuthMethod,
+    pub password: String,
+    pub key_file: String,
+    pub key_passphrase: String,
+}
+
+impl Default for SshTunnelSettings {
+    fn default() -> Self {
+        Self {
+            host: String::new(),
+            port: DEFAULT_SSH_PORT,
+            user: String::new(),
+            auth_method: SshAuthMethod::default(),
+            password: String::new(),
+            key_file: String::new(),
+            key_passphrase: String::new(),
+        }
+    }
+}
+
+// `default` lets JSON exports from older versions be imported; stored records
+// are versioned separately in `profile_schema`.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
 pub struct ConnectionProfile {
     pub id: String,
     pub name: String,
     pub connection_string: String,
+    /// Slash-separated folder path, e.g. "Clients/Acme". Empty for the root.
+    pub folder: String,
+    pub tags: Vec<String>,
+    pub color: Option<[u8; 3]>,
+    pub environment: Environment,
+    /// Blocks every write path, both in the UI and in `DatabaseService`.
+    pub read_only: bool,
+    /// The password is left out of the saved connection string and asked
+    /// for when connecting.
+    pub ask_password: bool,
+    pub ssh_tunnel: Option<SshTunnelSettings>,
+    /// `maxTimeMS` for queries that don't set their own.
+    pub default_max_time_ms: Option<u64>,
+}
+
+impl ConnectionProfile {
+    /// Production profiles make the user type the target's name before any
+    /// destructive operation.
+    pub fn requires_typed_confirmation(&self) -> bool {
+        self.environment == Environment::Production
+    }
+
+    pub fn matches_filter(&self, filter: &str) -> bool {
+        let filter = filter.trim().to_lowercase();
+        filter.is_empty()
+            || self.name.to_lowercase().contains(&filter)
+            || self.folder.to_lowercase().contains(&filter)
+            || self.environment.label().to_lowercase().contains(&filter)
+            || self
+                .tags
+                .iter()
+                .any(|tag| tag.to_lowercase().contains(&filter))
+    }
 }
 
 pub struct ConnectionProfileManager {
-    db: Database,
+    data_dir: PathBuf,
+    path: PathBuf,
+    /// `None` until the store has been opened and read successfully.
+    db: Option<Database>,
     profiles: Vec<ConnectionProfile>,
 }
 
+fn storage_error(error: impl Into<RedbError>) -> MongoLiteError {
+    match error.into() {
+        RedbError::DatabaseAlreadyOpen => MongoLiteError::StorageLocked(
+            "it is already open in another Mongolite window".to_string(),
+        ),
+        RedbError::Corrupted(message) => MongoLiteError::StorageCorrupted(message),
+        other => MongoLiteError::StorageError(other.to_string()),
+    }
+}
+
 impl ConnectionProfileManager {
-    pub fn new() -> Rc<RefCell<Self>> {
-        let db_path = PathBuf::from("mongolite_profiles.redb");
-        let db = Database::create(db_path).expect("Failed to create or open database");
+    /// Creates a manager for the profile store in `data_dir` without opening
+    /// it; call `open` to read the saved profiles.
+    pub fn new(data_dir: &Path) -> Rc<RefCell<Self>> {
+        Rc::new(RefCell::new(Self {
+            data_dir: data_dir.to_path_buf(),
+            path: data_dir.join(PROFILES_FILE_NAME),
+            db: None,
+            profiles: Vec::new(),
+        }))
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.db.is_some()
+    }
 
+    /// Opens (or creates) the profile store and loads its profiles. On
+    /// failure the manager stays usable but empty, and every write fails.
+    pub fn open(&mut self) -> Result<()> {
+        self.db = None;
+        self.profiles.clear();
+
+        fs::create_dir_all(&self.data_dir).map_err(|e| {
+            MongoLiteError::StorageError(format!(
+                "cannot create '{}': {}",
+                self.data_dir.display(),
+                e
+            ))
+        })?;
+
+        self.check_header()?;
+        let db = Database::create(&self.path).map_err(storage_error)?;
         // Ensure the table exists
-        let write_txn = db.begin_write().expect("Failed to begin write transaction");
+        let write_txn = db.begin_write().map_err(storage_error)?;
+        write_txn
+            .open_table(PROFILES_TABLE)
+            .map_err(storage_error)?;
+        write_txn.commit().map_err(storage_error)?;
+
+        self.db = Some(db);
+        if let Err(e) = self.upgrade_records().and_then(|_| self.load_profiles()) {
+            self.db = None;
+            return Err(e);
+        }
+        Ok(())
+    }
+
+    /// Moves a store left in the working directory by older versions into
+    /// the data directory. Call before `open`.
+    pub fn migrate_from_working_dir(&self) -> Result<()> {
+        let moved = data_dir::migrate_from_working_dir(PROFILES_FILE_NAME, &self.data_dir)
+            .map_err(|e| {
+                MongoLiteError::StorageError(format!(
+                    "cannot move {} into '{}': {}",
+                    PROFILES_FILE_NAME,
+                    self.data_dir.display(),
+                    e
+                ))
+            })?;
+        if let Some(path) = moved {
+            log::info!("Moved profile store to {}", path.display());
+        }
+        Ok(())
+    }
+
+    /// Rewrites records stored by older versions in the current format, so
+    /// each migration only ever runs once per record.
+    fn upgrade_records(&self) -> Result<()> {
+        let db = self.db()?;
+        let mut upgraded = Vec::new();
         {
-            write_txn
+            let read_txn = db.begin_read().map_err(storage_error)?;
+            let table = read_txn.open_table(PROFILES_TABLE).map_err(storage_error)?;
+            for result in table.iter().map_err(storage_error)? {
+                let (key, value_bytes) = result.map_err(storage_error)?;
+                let (profile, version) = profile_schema::decode(key.value(), value_bytes.value())?;
+                if version < profile_schema::CURRENT_VERSION {
+                    upgraded.push((key.value().to_string(), profile_schema::encode(&profile)?));
+                }
+            }
+        }
+        if upgraded.is_empty() {
+            return Ok(());
+        }
+
+        let write_txn = db.begin_write().map_err(storage_error)?;
+        {
+            let mut table = write_txn
                 .open_table(PROFILES_TABLE)
-                .expect("Failed to open or create profiles table");
+                .map_err(storage_error)?;
+            for (key, record) in &upgraded {
+                table
+                    .insert(key.as_str(), record.as_slice())
+                    .map_err(storage_error)?;
+            }
         }
-        write_txn.commit().expect("Failed to commit transaction");
+        write_txn.commit().map_err(storage_error)?;
+        log::info!(
+            "Upgraded {} stored profile(s) to version {}",
+            upgraded.len(),
+            profile_schema::CURRENT_VERSION
+        );
+        Ok(())
+    }
 
-        let mut manager = Self {
-            db,
-            profiles: Vec::new(),
+    fn check_header(&self) -> Result<()> {
+        let mut file = match File::open(&self.path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(()),
+            Err(e) => return Err(MongoLiteError::StorageError(e.to_string())),
         };
-        manager.load_profiles();
-        Rc::new(RefCell::new(manager))
+        let mut header = Vec::with_capacity(REDB_MAGIC.len());
+        file.by_ref()
+            .take(REDB_MAGIC.len() as u64)
+            .read_to_end(&mut header)
+            .map_err(|e| MongoLiteError::StorageError(e.to_string()))?;
+        if !header.is_empty() && header != REDB_MAGIC {
+            return Err(MongoLiteError::StorageCorrupted(
+                "the file is not a valid profile database".to_string(),
+            ));
+        }
+        Ok(())
     }
 
-    pub fn load_profiles(&mut self) {
-        let read_txn = self
-            .db
-            .begin_read()
-            .expect("Failed to begin read transaction");
-        let table = read_txn
-            .open_table(PROFILES_TABLE)
-            .expect("Failed to open table");
-
+    /// Moves the current store file aside and starts with an empty one.
+    /// Returns where the old file was moved to.
+    pub fn back_up_and_reset(&mut self) -> Result<PathBuf> {
+        // Close the database first so its file lock is released
+        self.db = None;
         self.profiles.clear();
-        for result in table.iter().expect("Failed to iterate over table") {
-            let (_, value_bytes) = result.expect("Failed to read table entry");
-            let profile: ConnectionProfile =
-                bincode::deserialize(value_bytes.value()).expect("Failed to deserialize profile");
-            self.profiles.push(profile);
+
+        let file_name = self
+            .path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let backup = self.path.with_file_name(format!(
+            "{}.{}.bak",
+            file_name,
+            Local::now().format("%Y%m%d-%H%M%S")
+        ));
+        fs::rename(&self.path, &backup).map_err(|e| {
+            MongoLiteError::StorageError(format!(
+                "cannot move '{}' to '{}': {}",
+                self.path.display(),
+                backup.display(),
+                e
+            ))
+        })?;
+        self.open()?;
+        Ok(backup)
+    }
+
+    fn db(&self) -> Result<&Database> {
+        self.db
+            .as_ref()
+            .ok_or_else(|| MongoLiteError::StorageError("the profile store is not open".into()))
+    }
+
+    pub fn load_profiles(&mut self) -> Result<()> {
+        let read_txn = self.db()?.begin_read().map_err(storage_error)?;
+        let table = read_txn.open_table(PROFILES_TABLE).map_err(storage_error)?;
+
+        let mut profiles = Vec::new();
+        for result in table.iter().map_err(storage_error)? {
+            let (key, value_bytes) = result.map_err(storage_error)?;
+            let (profile, _) = profile_schema::decode(key.value(), value_bytes.value())?;
+            profiles.push(profile);
         }
+        self.profiles = profiles;
+        Ok(())
     }
 
-    pub fn save_profile(&mut self, profile: &ConnectionProfile) {
-        let serialized = bincode::serialize(profile).expect("Failed to serialize profile");
+    pub fn save_profile(&mut self, profile: &ConnectionProfile) -> Result<()> {
+        let serialized = profile_schema::encode(profile)?;
 
-        let write_txn = self
-            .db
-            .begin_write()
-            .expect("Failed to begin write transaction");
+        let write_txn = self.db()?.begin_write().map_err(storage_error)?;
         {
             let mut table = write_txn
                 .open_table(PROFILES_TABLE)
-                .expect("Failed to open table");
+                .map_err(storage_error)?;
             table
                 .insert(&profile.id as &str, serialized.as_slice())
-                .expect("Failed to insert profile");
+                .map_err(storage_error)?;
         }
-        write_txn.commit().expect("Failed to commit transaction");
+        write_txn.commit().map_err(storage_error)?;
 
-        self.load_profiles();
+        self.load_profiles()
     }
 
-    pub fn delete_profile(&mut self, profile_id: &str) {
-        let write_txn = self
-            .db
-            .begin_write()
-            .expect("Failed to begin write transaction");
+    pub fn delete_profile(&mut self, profile_id: &str) -> Result<()> {
+        let write_txn = self.db()?.begin_write().map_err(storage_error)?;
         {
             let mut table = write_txn
                 .open_table(PROFILES_TABLE)
-                .expect("Failed to open table");
-            table.remove(profile_id).expect("Failed to remove profile");
+                .map_err(storage_error)?;
+            table.remove(profile_id).map_err(storage_error)?;
+        }
+        write_txn.commit().map_err(storage_error)?;
+
+        self.load_profiles()
+    }
+
+    /// Saves imported profiles, resolving clashes with existing profiles
+    /// according to `policy`.
+    pub fn import_profiles(
+        &mut self,
+        profiles: Vec<ConnectionProfile>,
+        match_by: MatchBy,
+        policy: ConflictPolicy,
+    ) -> Result<ImportSummary> {
+        let mut summary = ImportSummary::default();
+        for mut profile in profiles {
+            let existing = self
+                .profiles
+                .iter()
+                .find(|existing| match match_by {
+                    MatchBy::Id => existing.id == profile.id,
+                    MatchBy::Name => existing.name == profile.name,
+                })
+                .map(|existing| existing.id.clone());
+            match (existing, policy) {
+                (Some(_), ConflictPolicy::Skip) => {
+                    summary.skipped += 1;
+                    continue;
+                }
+                (Some(existing_id), ConflictPolicy::Overwrite) => {
+                    profile.id = existing_id;
+                    summary.overwritten += 1;
+                }
+                (Some(_), ConflictPolicy::Duplicate) => {
+                    profile.id = Uuid::new_v4().to_string();
+                    profile.name = self.unique_name(&profile.name);
+                    summary.added += 1;
+                }
+                (None, _) => {
+                    // When matching by name an id clash still has to be
+                    // avoided, or the import would replace another profile.
+                    if profile.id.is_empty() || self.profiles.iter().any(|p| p.id == profile.id) {
+                        profile.id = Uuid::new_v4().to_string();
+                    }
+                    summary.added += 1;
+                }
+            }
+            self.save_profile(&profile)?;
         }
-        write_txn.commit().expect("Failed to commit transaction");
+        Ok(summary)
+    }
 
-        self.load_profiles();
+    fn unique_name(&self, name: &str) -> String {
+        let taken = |candidate: &str| self.profiles.iter().any(|p| p.name == candidate);
+        if !taken(name) {
+            return name.to_string();
+        }
+        (2..)
+            .map(|n| format!("{} ({})", name, n))
+            .find(|candidate| !taken(candidate))
+            .expect("an unused name always exists")
     }
 
     pub fn get_profiles(&self) -> &[ConnectionProfile] {
         &self.profiles
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn open_upgrades_records_from_older_versions() {
+        let dir = tempfile::tempdir().unwrap();
+        {
+            let db = Database::create(dir.path().join(PROFILES_FILE_NAME)).unwrap();
+            let write_txn = db.begin_write().unwrap();
+            {
+                let mut table = write_txn.open_table(PROFILES_TABLE).unwrap();
+                let v1: &[u8] = include_bytes!("../../tests/fixtures/profiles/v1.bin");
+                let v2: &[u8] = include_bytes!("../../tests/fixtures/profiles/v2.bin");
+                table.insert("v1", v1).unwrap();
+                table.insert("v2", v2).unwrap();
+            }
+            write_txn.commit().unwrap();
+        }
+
+        let manager = ConnectionProfileManager::new(dir.path());
+        manager.borrow_mut().open().unwrap();
+        let mut names: Vec<_> = manager
+            .borrow()
+            .get_profiles()
+            .iter()
+            .map(|profile| profile.name.clone())
+            .collect();
+        names.sort();
+        assert_eq!(names, ["Local", "Production"]);
+
+        let manager = manager.borrow();
+        let read_txn = manager.db().unwrap().begin_read().unwrap();
+        let table = read_txn.open_table(PROFILES_TABLE).unwrap();
+        for result in table.iter().unwrap() {
+            let (key, value) = result.unwrap();
+            let (_, version) = profile_schema::decode(key.value(), value.value()).unwrap();
+            assert_eq!(version, profile_schema::CURRENT_VERSION);
+        }
+    }
+}
@@ -0,0 +1,259 @@
+use crate::components::ConnectRequest;
+use crate::models::{ConnectionProfile, Environment, Topology};
+use crate::services::{DatabaseInfo, DatabaseService};
+use crate::utils::connection_string::mask_password;
+use crate::utils::error::{MongoLiteError, Result};
+use crate::utils::task::BackgroundTask;
+use mongodb::error::ErrorKind;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// How often a connection's topology is read again, so the status bar
+/// follows a change of primary.
+const TOPOLOGY_REFRESH: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConnectionStatus {
+    Connecting,
+    Connected,
+    Failed(String),
+    Disconnecting,
+}
+
+impl ConnectionStatus {
+    pub fn label(&self) -> String {
+        match self {
+            ConnectionStatus::Connecting => "Connecting...".to_string(),
+            ConnectionStatus::Connected => "Connected".to_string(),
+            ConnectionStatus::Failed(error) => format!("Error: {}", error),
+            ConnectionStatus::Disconnecting => "Disconnecting...".to_string(),
+        }
+    }
+}
+
+/// One open (or opening) connection and the databases it last listed.
+pub struct ConnectionEntry {
+    pub id: String,
+    pub name: String,
+    pub profile: Option<ConnectionProfile>,
+    pub service: Arc<DatabaseService>,
+    pub status: ConnectionStatus,
+    pub databases: Vec<DatabaseInfo>,
+    /// Known once connected.
+    pub topology: Option<Topology>,
+    databases_task: Option<BackgroundTask<Result<Vec<DatabaseInfo>>>>,
+    topology_task: Option<BackgroundTask<Result<Topology>>>,
+    topology_checked: Option<Instant>,
+    disconnect_task: Option<BackgroundTask<()>>,
+}
+
+impl ConnectionEntry {
+    pub fn environment(&self) -> Environment {
+        self.profile
+            .as_ref()
+            .map(|profile| profile.environment)
+            .unwrap_or_default()
+    }
+
+    pub fn read_only(&self) -> bool {
+        self.profile
+            .as_ref()
+            .is_some_and(|profile| profile.read_only)
+    }
+
+    /// `maxTimeMS` for queries on this connection that don't set their own.
+    pub fn default_max_time_ms(&self) -> Option<u64> {
+        self.profile
+            .as_ref()
+            .and_then(|profile| profile.default_max_time_ms)
+    }
+
+    pub fn is_connected(&self) -> bool {
+        self.status == ConnectionStatus::Connected
+    }
+
+    pub fn database_names(&self) -> Vec<String> {
+        self.databases
+            .iter()
+            .map(|database| database.name.clone())
+            .collect()
+    }
+}
+
+/// The connections open at the same time, keyed by profile id. Connections
+/// made from a typed connection string are keyed by that string instead.
+pub struct ConnectionRegistry {
+    // Kept in the order they were opened, which is how the sidebar lists them
+    connections: Vec<ConnectionEntry>,
+}
+
+impl ConnectionRegistry {
+    pub fn new() -> Self {
+        Self {
+            connections: Vec::new(),
+        }
+    }
+
+    pub fn connection_id(request: &ConnectRequest) -> String {
+        match &request.profile {
+            Some(profile) => profile.id.clone(),
+            None => format!("uri:{}", mask_password(&request.connection_string)),
+        }
+    }
+
+    /// Opens the requested connection in the background and returns its id.
+    /// An existing connection with the same id is replaced, which closes it.
+    pub fn connect(&mut self, ctx: &egui::Context, request: ConnectRequest) -> String {
+        let id = Self::connection_id(&request);
+        let name = request
+            .profile
+            .as_ref()
+            .map(|profile| profile.name.clone())
+            .unwrap_or_else(|| mask_password(&request.connection_string));
+        let read_only = request
+            .profile
+            .as_ref()
+            .is_some_and(|profile| profile.read_only);
+        let ssh_tunnel = request
+            .profile
+            .as_ref()
+            .and_then(|profile| profile.ssh_tunnel.clone());
+
+        let service = Arc::new(DatabaseService::new());
+        let task_service = Arc::clone(&service);
+        let databases_task = BackgroundTask::spawn(ctx, async move {
+            task_service
+                .connect(
+                    &request.connection_string,
+                    request.password,
+                    ssh_tunnel.as_ref(),
+                    read_only,
+                )
+                .await?;
+            task_service.list_databases().await
+        });
+
+        let entry = ConnectionEntry {
+            id: id.clone(),
+            name,
+            profile: request.profile,
+            service,
+            status: ConnectionStatus::Connecting,
+            databases: Vec::new(),
+            topology: None,
+            databases_task: Some(databases_task),
+            topology_task: None,
+            topology_checked: None,
+            disconnect_task: None,
+        };
+        match self.connections.iter_mut().find(|entry| entry.id == id) {
+            Some(existing) => *existing = entry,
+            None => self.connections.push(entry),
+        }
+        id
+    }
+
+    /// Lists the databases again, e.g. after one was created elsewhere.
+    pub fn refresh_databases(&mut self, ctx: &egui::Context, id: &str) {
+        let Some(entry) = self
+            .get_mut(id)
+            .filter(|entry| entry.service.is_connected())
+        else {
+            return;
+        };
+        let service = Arc::clone(&entry.service);
+        entry.databases_task = Some(BackgroundTask::spawn(ctx, async move {
+            service.list_databases().await
+        }));
+    }
+
+    /// Closes a connection. It stays listed as disconnecting until the client
+    /// and its SSH tunnel have shut down.
+    pub fn disconnect(&mut self, ctx: &egui::Context, id: &str) {
+        let Some(entry) = self.get_mut(id) else {
+            return;
+        };
+        if !entry.service.is_connected() {
+            // Nothing to shut down yet; dropping the entry abandons the attempt
+            self.connections.retain(|entry| entry.id != id);
+            return;
+        }
+        entry.status = ConnectionStatus::Disconnecting;
+        entry.databases_task = None;
+        entry.topology_task = None;
+        let service = Arc::clone(&entry.service);
+        entry.disconnect_task = Some(BackgroundTask::spawn(ctx, async move {
+            service.disconnect().await
+        }));
+    }
+
+    /// Picks up finished background work and reads the topology of open
+    /// connections when it's due. Returns the ids of profiles whose password
+    /// was rejected, so a remembered one can be forgotten.
+    pub fn poll(&mut self, ctx: &egui::Context) -> Vec<String> {
+        let mut rejected_profiles = Vec::new();
+        for entry in &mut self.connections {
+            if let Some(result) = entry.topology_task.as_mut().and_then(|task| task.poll()) {
+                entry.topology_task = None;
+                // A failed read keeps the last known topology
+                if let Ok(topology) = result {
+                    entry.topology = Some(topology);
+                }
+            }
+            let topology_due = entry
+                .topology_checked
+                .is_none_or(|checked| checked.elapsed() >= TOPOLOGY_REFRESH);
+            if entry.is_connected() && entry.topology_task.is_none() && topology_due {
+                let service = Arc::clone(&entry.service);
+                entry.topology_checked = Some(Instant::now());
+                entry.topology_task = Some(BackgroundTask::spawn(ctx, async move {
+                    service.topology().await
+                }));
+                ctx.request_repaint_after(TOPOLOGY_REFRESH);
+            }
+
+            let Some(result) = entry.databases_task.as_mut().and_then(|task| task.poll()) else {
+                continue;
+            };
+            entry.databases_task = None;
+            match result {
+                Ok(databases) => {
+                    entry.status = ConnectionStatus::Connected;
+                    entry.databases = databases;
+                }
+                Err(e) => {
+                    // The driver only authenticates once it's used, so a
+                    // wrong password shows up here rather than in `connect`
+                    if let (MongoLiteError::MongoDBError(error), Some(profile)) =
+                        (&e, &entry.profile)
+                    {
+                        if matches!(*error.kind, ErrorKind::Authentication { .. }) {
+                            rejected_profiles.push(profile.id.clone());
+                        }
+                    }
+                    entry.status = ConnectionStatus::Failed(e.to_string());
+                }
+            }
+        }
+
+        self.connections.retain_mut(|entry| {
+            entry
+                .disconnect_task
+                .as_mut()
+                .is_none_or(|task| task.poll().is_none())
+        });
+        rejected_profiles
+    }
+
+    pub fn get(&self, id: &str) -> Option<&ConnectionEntry> {
+        self.connections.iter().find(|entry| entry.id == id)
+    }
+
+    fn get_mut(&mut self, id: &str) -> Option<&mut ConnectionEntry> {
+        self.connections.iter_mut().find(|entry| entry.id == id)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &ConnectionEntry> {
+        self.connections.iter()
+    }
+}
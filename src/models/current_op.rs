@@ -0,0 +1,188 @@
+use super::database_stats::to_u64;
+use mongodb::bson::{doc, Bson, Document};
+
+/// Which operations to list from `$currentOp`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CurrentOpFilter {
+    /// Leaves out idle connections and sessions.
+    pub active_only: bool,
+    /// Only operations that have been running at least this long; 0 for all.
+    pub min_seconds: u64,
+    /// Part of the namespace, e.g. `shop` or `shop.orders`.
+    pub namespace: String,
+    /// Part of the client's address or application name.
+    pub client: String,
+}
+
+impl Default for CurrentOpFilter {
+    fn default() -> Self {
+        Self {
+            active_only: true,
+            min_seconds: 0,
+            namespace: String::new(),
+            client: String::new(),
+        }
+    }
+}
+
+impl CurrentOpFilter {
+    /// The aggregation to run on the `admin` database, longest running
+    /// first.
+    pub fn pipeline(&self) -> Vec<Document> {
+        let mut conditions = Vec::new();
+        if self.active_only {
+            conditions.push(doc! { "active": true });
+        }
+        if self.min_seconds > 0 {
+            conditions.push(doc! { "secs_running": { "$gte": self.min_seconds as i64 } });
+        }
+        let namespace = self.namespace.trim();
+        if !namespace.is_empty() {
+            conditions.push(doc! { "ns": contains(namespace) });
+        }
+        let client = self.client.trim();
+        if !client.is_empty() {
+            // mongos reports the client's address as client_s
+            conditions.push(doc! {
+                "$or": [
+                    { "client": contains(client) },
+                    { "client_s": contains(client) },
+                    { "appName": contains(client) },
+                ]
+            });
+        }
+
+        let mut pipeline = vec![doc! {
+            "$currentOp": { "allUsers": true, "idleConnections": !self.active_only }
+        }];
+        if !conditions.is_empty() {
+            pipeline.push(doc! { "$match": { "$and": conditions } });
+        }
+        pipeline.push(doc! { "$sort": { "microsecs_running": -1 } });
+        pipeline
+    }
+}
+
+fn contains(text: &str) -> Document {
+    let mut pattern = String::with_capacity(text.len());
+    for c in text.chars() {
+        if "\\.^$|?*+()[]{}".contains(c) {
+            pattern.push('\\');
+        }
+        pattern.push(c);
+    }
+    doc! { "$regex": pattern, "$options": "i" }
+}
+
+/// One entry of the `$currentOp` output.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CurrentOperation {
+    /// A number, or `shard:number` on a sharded cluster; `killOp` takes it
+    /// as is.
+    pub opid: Bson,
+    pub op: String,
+    pub namespace: String,
+    pub active: bool,
+    pub running_micros: Option<u64>,
+    pub plan_summary: String,
+    pub client: String,
+    pub app_name: String,
+    pub description: String,
+    pub command: Option<Document>,
+}
+
+impl CurrentOperation {
+    pub fn from_document(operation: &Document) -> Self {
+        let text = |key: &str| operation.get_str(key).unwrap_or_default().to_string();
+        let running_micros = operation
+            .get("microsecs_running")
+            .and_then(to_u64)
+            .or_else(|| {
+                operation
+                    .get("secs_running")
+                    .and_then(to_u64)
+                    .map(|seconds| seconds * 1_000_000)
+            });
+        Self {
+            opid: operation.get("opid").cloned().unwrap_or(Bson::Null),
+            op: text("op"),
+            namespace: text("ns"),
+            active: operation.get_bool("active").unwrap_or(false),
+            running_micros,
+            plan_summary: text("planSummary"),
+            client: operation
+                .get_str("client")
+                .or_else(|_| operation.get_str("client_s"))
+                .unwrap_or_default()
+                .to_string(),
+            app_name: text("appName"),
+            description: text("desc"),
+            command: operation.get_document("command").ok().cloned(),
+        }
+    }
+
+    /// Idle connections have no operation id and can't be killed.
+    pub fn can_kill(&self) -> bool {
+        !matches!(self.opid, Bson::Null)
+    }
+
+    pub fn opid_label(&self) -> String {
+        match &self.opid {
+            Bson::String(opid) => opid.clone(),
+            Bson::Null => String::new(),
+            opid => opid.to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builds_the_pipeline_from_the_filter() {
+        let filter = CurrentOpFilter {
+            min_seconds: 5,
+            namespace: "shop.orders".to_string(),
+            ..CurrentOpFilter::default()
+        };
+        let pipeline = filter.pipeline();
+        assert_eq!(
+            pipeline[0],
+            doc! { "$currentOp": { "allUsers": true, "idleConnections": false } }
+        );
+        assert_eq!(
+            pipeline[1],
+            doc! { "$match": { "$and": [
+                { "active": true },
+                { "secs_running": { "$gte": 5_i64 } },
+                { "ns": { "$regex": "shop\\.orders", "$options": "i" } },
+            ] } }
+        );
+
+        let all = CurrentOpFilter {
+            active_only: false,
+            ..CurrentOpFilter::default()
+        };
+        assert_eq!(all.pipeline().len(), 2);
+    }
+
+    #[test]
+    fn reads_mongos_operations() {
+        let operation = CurrentOperation::from_document(&doc! {
+            "shard": "rs0",
+            "opid": "rs0:4521",
+            "active": true,
+            "op": "query",
+            "ns": "shop.orders",
+            "secs_running": 42_i64,
+            "client_s": "10.0.0.7:51234",
+            "planSummary": "COLLSCAN",
+            "command": { "find": "orders", "filter": { "total": { "$gt": 100 } } },
+        });
+        assert_eq!(operation.opid_label(), "rs0:4521");
+        assert_eq!(operation.running_micros, Some(42_000_000));
+        assert_eq!(operation.client, "10.0.0.7:51234");
+        assert!(operation.can_kill());
+    }
+}
@@ -0,0 +1,242 @@
+use mongodb::bson::{Bson, DateTime, Document};
+use std::collections::BTreeMap;
+
+/// Storage figures for a database, from `dbStats`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DatabaseStats {
+    pub collections: u64,
+    pub views: u64,
+    pub objects: u64,
+    pub avg_obj_size: f64,
+    /// Uncompressed size of the documents.
+    pub data_size: u64,
+    /// Space the documents take on disk, including reusable space.
+    pub storage_size: u64,
+    pub indexes: u64,
+    pub index_size: u64,
+    /// Space on disk that is allocated but free for reuse. Only reported
+    /// when asked for with `freeStorage`, which older servers ignore.
+    pub free_storage_size: Option<u64>,
+}
+
+impl DatabaseStats {
+    pub fn from_document(stats: &Document) -> Self {
+        Self {
+            collections: number(stats, "collections"),
+            views: number(stats, "views"),
+            objects: number(stats, "objects"),
+            avg_obj_size: float(stats, "avgObjSize"),
+            data_size: number(stats, "dataSize"),
+            storage_size: number(stats, "storageSize"),
+            indexes: number(stats, "indexes"),
+            index_size: number(stats, "indexSize"),
+            free_storage_size: optional_number(stats, "freeStorageSize"),
+        }
+    }
+
+    pub fn compression_ratio(&self) -> Option<f64> {
+        ratio(self.data_size, self.storage_size)
+    }
+
+    pub fn fragmentation(&self) -> Option<f64> {
+        self.free_storage_size
+            .and_then(|free| ratio(free, self.storage_size))
+    }
+}
+
+/// Size and usage of one index.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct IndexStats {
+    pub name: String,
+    pub size: u64,
+    /// Operations that used the index since `since`, from `$indexStats`.
+    pub accesses: Option<u64>,
+    pub since: Option<DateTime>,
+}
+
+/// Storage figures for a collection, from `$collStats` storage stats and
+/// `$indexStats`. On a sharded cluster the shards' figures are added up.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CollectionStats {
+    pub count: u64,
+    pub avg_obj_size: f64,
+    pub data_size: u64,
+    pub storage_size: u64,
+    pub free_storage_size: Option<u64>,
+    pub total_index_size: u64,
+    pub capped: bool,
+    /// Sorted by name.
+    pub indexes: Vec<IndexStats>,
+}
+
+impl CollectionStats {
+    /// Builds the stats from the `$collStats: { storageStats: {} }` output,
+    /// one document per shard.
+    pub fn from_coll_stats(shards: &[Document]) -> Self {
+        let mut stats = CollectionStats::default();
+        let mut index_sizes = BTreeMap::<String, u64>::new();
+        for shard in shards {
+            let Ok(storage) = shard.get_document("storageStats") else {
+                continue;
+            };
+            stats.count += number(storage, "count");
+            stats.data_size += number(storage, "size");
+            stats.storage_size += number(storage, "storageSize");
+            if let Some(free) = optional_number(storage, "freeStorageSize") {
+                *stats.free_storage_size.get_or_insert(0) += free;
+            }
+            stats.total_index_size += number(storage, "totalIndexSize");
+            stats.capped |= storage.get_bool("capped").unwrap_or(false);
+            if let Ok(sizes) = storage.get_document("indexSizes") {
+                for (name, size) in sizes {
+                    *index_sizes.entry(name.clone()).or_default() += to_u64(size).unwrap_or(0);
+                }
+            }
+        }
+        // Averaged over all shards rather than taken from any one of them
+        if stats.count > 0 {
+            stats.avg_obj_size = stats.data_size as f64 / stats.count as f64;
+        }
+        stats.indexes = index_sizes
+            .into_iter()
+            .map(|(name, size)| IndexStats {
+                name,
+                size,
+                ..IndexStats::default()
+            })
+            .collect();
+        stats
+    }
+
+    /// Adds the access counts from `$indexStats`, one document per index and
+    /// shard.
+    pub fn add_index_usage(&mut self, usage: &[Document]) {
+        for entry in usage {
+            let Ok(name) = entry.get_str("name") else {
+                continue;
+            };
+            let Ok(accesses) = entry.get_document("accesses") else {
+                continue;
+            };
+            let position = match self.indexes.iter().position(|index| index.name == name) {
+                Some(position) => position,
+                None => {
+                    self.indexes.push(IndexStats {
+                        name: name.to_string(),
+                        ..IndexStats::default()
+                    });
+                    self.indexes.len() - 1
+                }
+            };
+            let index = &mut self.indexes[position];
+            *index.accesses.get_or_insert(0) += number(accesses, "ops");
+            if let Ok(since) = accesses.get_datetime("since") {
+                index.since = Some(index.since.map_or(*since, |earliest| earliest.min(*since)));
+            }
+        }
+        self.indexes.sort_by(|a, b| a.name.cmp(&b.name));
+    }
+
+    pub fn compression_ratio(&self) -> Option<f64> {
+        ratio(self.data_size, self.storage_size)
+    }
+
+    pub fn fragmentation(&self) -> Option<f64> {
+        self.free_storage_size
+            .and_then(|free| ratio(free, self.storage_size))
+    }
+}
+
+fn ratio(numerator: u64, denominator: u64) -> Option<f64> {
+    (denominator > 0).then(|| numerator as f64 / denominator as f64)
+}
+
+// The server reports sizes as Int32, Int64 or Double depending on their size
+// and version
+pub(super) fn to_u64(value: &Bson) -> Option<u64> {
+    match value {
+        Bson::Int32(value) => u64::try_from(*value).ok(),
+        Bson::Int64(value) => u64::try_from(*value).ok(),
+        Bson::Double(value) if *value >= 0.0 => Some(*value as u64),
+        _ => None,
+    }
+}
+
+fn optional_number(document: &Document, key: &str) -> Option<u64> {
+    document.get(key).and_then(to_u64)
+}
+
+fn number(document: &Document, key: &str) -> u64 {
+    optional_number(document, key).unwrap_or(0)
+}
+
+fn float(document: &Document, key: &str) -> f64 {
+    match document.get(key) {
+        Some(Bson::Double(value)) => *value,
+        Some(value) => to_u64(value).unwrap_or(0) as f64,
+        None => 0.0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mongodb::bson::doc;
+
+    #[test]
+    fn reads_database_stats() {
+        let stats = DatabaseStats::from_document(&doc! {
+            "db": "shop",
+            "collections": 4,
+            "views": 1,
+            "objects": 1200_i64,
+            "avgObjSize": 512.5,
+            "dataSize": 615000.0,
+            "storageSize": 204800,
+            "indexes": 6,
+            "indexSize": 81920,
+            "freeStorageSize": 20480,
+            "ok": 1.0,
+        });
+        assert_eq!(stats.objects, 1200);
+        assert_eq!(stats.data_size, 615000);
+        assert_eq!(stats.compression_ratio(), Some(615000.0 / 204800.0));
+        assert_eq!(stats.fragmentation(), Some(0.1));
+    }
+
+    #[test]
+    fn adds_up_shards_and_index_usage() {
+        let shard = |count: i32, size: i32, id_size: i32| {
+            doc! {
+                "ns": "shop.orders",
+                "storageStats": {
+                    "count": count,
+                    "size": size,
+                    "avgObjSize": 100,
+                    "storageSize": size / 2,
+                    "freeStorageSize": 1024,
+                    "totalIndexSize": id_size + 4096,
+                    "indexSizes": { "_id_": id_size, "customer_1": 4096 },
+                },
+            }
+        };
+        let mut stats =
+            CollectionStats::from_coll_stats(&[shard(100, 20000, 8192), shard(300, 40000, 16384)]);
+        assert_eq!(stats.count, 400);
+        assert_eq!(stats.data_size, 60000);
+        assert_eq!(stats.avg_obj_size, 150.0);
+        assert_eq!(stats.free_storage_size, Some(2048));
+        assert_eq!(stats.indexes[0].name, "_id_");
+        assert_eq!(stats.indexes[0].size, 24576);
+
+        let early = DateTime::from_millis(1_000);
+        let late = DateTime::from_millis(2_000);
+        stats.add_index_usage(&[
+            doc! { "name": "customer_1", "accesses": { "ops": 5_i64, "since": late } },
+            doc! { "name": "customer_1", "accesses": { "ops": 7_i64, "since": early } },
+        ]);
+        assert_eq!(stats.indexes[1].accesses, Some(12));
+        assert_eq!(stats.indexes[1].since, Some(early));
+        assert_eq!(stats.indexes[0].accesses, None);
+    }
+}
@@ -0,0 +1,103 @@
+use super::database_stats::to_u64;
+use mongodb::bson::{Bson, Document};
+
+/// The parts of an `explain` reply worth reading at a glance.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExplainSummary {
+    /// The winning plan's stages from the top down, e.g. `FETCH`,
+    /// `IXSCAN status_1`. On a sharded cluster each shard's plan follows the
+    /// merge stage.
+    pub stages: Vec<String>,
+    pub returned: u64,
+    pub keys_examined: u64,
+    pub docs_examined: u64,
+    pub millis: u64,
+    pub raw: Document,
+}
+
+impl ExplainSummary {
+    pub fn from_document(explain: &Document) -> Self {
+        let mut stages = Vec::new();
+        if let Ok(plan) = explain
+            .get_document("queryPlanner")
+            .and_then(|planner| planner.get_document("winningPlan"))
+        {
+            collect_stages(plan, &mut stages);
+        }
+        let stats = explain.get_document("executionStats").ok();
+        let number = |key| {
+            stats
+                .and_then(|stats| stats.get(key))
+                .and_then(to_u64)
+                .unwrap_or(0)
+        };
+        Self {
+            stages,
+            returned: number("nReturned"),
+            keys_examined: number("totalKeysExamined"),
+            docs_examined: number("totalDocsExamined"),
+            millis: number("executionTimeMillis"),
+            raw: explain.clone(),
+        }
+    }
+
+    /// Whether the winning plan reads the whole collection.
+    pub fn scans_collection(&self) -> bool {
+        self.stages.iter().any(|stage| stage == "COLLSCAN")
+    }
+}
+
+fn collect_stages(plan: &Document, stages: &mut Vec<String>) {
+    // Slot-based plans on 7.0+ nest the classic plan under queryPlan
+    if let Ok(plan) = plan.get_document("queryPlan") {
+        return collect_stages(plan, stages);
+    }
+    if let Ok(stage) = plan.get_str("stage") {
+        stages.push(match plan.get_str("indexName") {
+            Ok(index) => format!("{} {}", stage, index),
+            Err(_) => stage.to_string(),
+        });
+    }
+    if let Ok(input) = plan.get_document("inputStage") {
+        collect_stages(input, stages);
+    }
+    for key in ["inputStages", "shards"] {
+        if let Ok(inputs) = plan.get_array(key) {
+            for input in inputs.iter().filter_map(Bson::as_document) {
+                match input.get_document("winningPlan") {
+                    Ok(plan) => collect_stages(plan, stages),
+                    Err(_) => collect_stages(input, stages),
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mongodb::bson::doc;
+
+    #[test]
+    fn summarizes_the_winning_plan() {
+        let summary = ExplainSummary::from_document(&doc! {
+            "queryPlanner": {
+                "winningPlan": {
+                    "queryPlan": {
+                        "stage": "FETCH",
+                        "inputStage": { "stage": "IXSCAN", "indexName": "status_1" },
+                    },
+                },
+            },
+            "executionStats": {
+                "nReturned": 12,
+                "executionTimeMillis": 3,
+                "totalKeysExamined": 12,
+                "totalDocsExamined": 12,
+            },
+        });
+        assert_eq!(summary.stages, ["FETCH", "IXSCAN status_1"]);
+        assert_eq!(summary.docs_examined, 12);
+        assert!(!summary.scans_collection());
+    }
+}
@@ -1,5 +1,47 @@
 mod connection_profile;
+mod connection_registry;
+mod connection_settings;
+mod current_op;
+mod database_stats;
+mod explain;
 mod mongodb_client;
+mod profile_schema;
+mod profile_transfer;
+mod profiler;
+mod query_options;
+mod saved_query;
+mod server_status;
+mod topology;
+mod users;
+mod validation;
 
-pub use connection_profile::{ConnectionProfile, ConnectionProfileManager};
+pub use connection_profile::{
+    ConnectionProfile, ConnectionProfileManager, Environment, SshAuthMethod, SshTunnelSettings,
+};
+pub use connection_registry::{ConnectionEntry, ConnectionRegistry, ConnectionStatus};
+pub use connection_settings::{
+    AuthMechanism, ConnectionSettings, HostEntry, ReadPreferenceMode, TlsSettings, DEFAULT_PORT,
+    OPTION_ENCODE_SET, USERINFO_ENCODE_SET,
+};
+pub use current_op::{CurrentOpFilter, CurrentOperation};
+pub use database_stats::{CollectionStats, DatabaseStats};
+pub use explain::ExplainSummary;
 pub use mongodb_client::MongoDBClient;
+pub use profile_transfer::{
+    export_profiles, parse_import, ConflictPolicy, ExportOptions, ImportBatch, ImportSource,
+    ImportSummary, MatchBy,
+};
+pub use profiler::{
+    group_by_shape, ProfileSort, ProfiledOperation, ProfilerStatus, ProfilingLevel, QueryShapeGroup,
+};
+pub use query_options::{QueryOptions, ReadConcernMode};
+pub use saved_query::{SavedQuery, SavedQueryStore};
+pub use server_status::{MonitorPoint, Opcounters, ServerStatusHistory, ServerStatusSample};
+pub use topology::{
+    chunk_distribution, BalancerStatus, ReplicaSetStatus, ShardInfo, ShardedClusterStatus,
+    Topology, TopologyKind,
+};
+pub use users::{parse_role_list, Privilege, Resource, RoleInfo, RoleRef, UserInfo};
+pub use validation::{
+    generate_json_schema, ValidationAction, ValidationCheck, ValidationLevel, ValidationRules,
+};
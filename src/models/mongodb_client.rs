@@ -1,94 +1,331 @@
 use crate::components::{
-    CollectionSelector, Component, ConnectionManager, DatabaseSelector, QueryBuilder, ResultsView,
-    StatusBar, Tab,
+    AdminWindow, Component, ConnectionManager, CurrentOpsPanel, ExplainRequest, ExplorerSidebar,
+    ProfilerWindow, QueryTab, SavedQueriesWindow, ServerMonitor, StatsDashboard, StatsRequest,
+    StatusBar, TopologyWindow, ValidationEditor,
 };
-use crate::services::{DatabaseService, QueryService};
+use crate::models::{ConnectionEntry, ConnectionRegistry, ConnectionStatus, Environment, Topology};
+use crate::services::QueryService;
 use crate::theme::Theme;
-use egui::{
-    epaint, Align, Align2, FontId, Frame, Grid, Layout, Pos2, RichText, Rounding, Stroke, Ui, Vec2,
-};
+use egui::{Align, ComboBox, Frame, Layout, RichText, Stroke, Ui};
+use std::path::Path;
 use std::sync::Arc;
 
 pub struct MongoDBClient {
     connection_manager: ConnectionManager,
-    database_selector: DatabaseSelector,
-    collection_selector: CollectionSelector,
-    query_builder: QueryBuilder,
-    results_view: ResultsView,
+    explorer_sidebar: ExplorerSidebar,
     status_bar: StatusBar,
-    database_service: Arc<DatabaseService>,
+    current_ops: CurrentOpsPanel,
+    stats_dashboard: StatsDashboard,
+    server_monitor: ServerMonitor,
+    topology_window: TopologyWindow,
+    profiler_window: ProfilerWindow,
+    validation_editor: ValidationEditor,
+    admin_window: AdminWindow,
+    saved_queries: SavedQueriesWindow,
+    registry: ConnectionRegistry,
     query_service: Arc<QueryService>,
     theme: Arc<Theme>,
     is_dark_mode: bool,
-    query_tab: Tab<QueryBuilder>,
-    results_tab: Tab<ResultsView>,
+    tabs: Vec<QueryTab>,
+    active_tab: usize,
+    next_tab_number: usize,
 }
 
 impl MongoDBClient {
-    pub fn new(cc: &eframe::CreationContext<'_>) -> Self {
+    pub fn new(cc: &eframe::CreationContext<'_>, data_dir: &Path) -> Self {
         let theme = Arc::new(Theme::google_theme());
         theme.apply(&cc.egui_ctx);
 
-        let mut query_tab = Tab::new("query_tab".to_string(), Arc::clone(&theme));
-        query_tab.add_tab(
-            "Query".to_string(),
-            Box::new(
-                |ui: &mut Ui, query_builder: &mut QueryBuilder, _: &Theme, id_prefix: &str| {
-                    query_builder.render(ui, id_prefix);
-                },
-            ),
-        );
-        query_tab.add_tab(
-            "Aggregation".to_string(),
-            Box::new(|ui: &mut Ui, _: &mut QueryBuilder, _: &Theme, _: &str| {
-                ui.label("Aggregation tab content (to be implemented)");
-            }),
-        );
-
-        let mut results_tab = Tab::new("results_tab".to_string(), Arc::clone(&theme));
-        results_tab.add_tab(
-            "Table View".to_string(),
-            Box::new(
-                |ui: &mut Ui, results_view: &mut ResultsView, _: &Theme, id_prefix: &str| {
-                    results_view.render_table(ui, id_prefix);
-                },
-            ),
-        );
-        results_tab.add_tab(
-            "JSON View".to_string(),
-            Box::new(
-                |ui: &mut Ui, results_view: &mut ResultsView, _: &Theme, id_prefix: &str| {
-                    results_view.render_json(ui, id_prefix);
-                },
-            ),
-        );
-
         Self {
-            connection_manager: ConnectionManager::new(Arc::clone(&theme)),
-            database_selector: DatabaseSelector::new(Arc::clone(&theme)),
-            collection_selector: CollectionSelector::new(Arc::clone(&theme)),
-            query_builder: QueryBuilder::new(Arc::clone(&theme)),
-            results_view: ResultsView::new(Arc::clone(&theme)),
+            connection_manager: ConnectionManager::new(Arc::clone(&theme), data_dir),
+            explorer_sidebar: ExplorerSidebar::new(Arc::clone(&theme)),
             status_bar: StatusBar::new(Arc::clone(&theme)),
-            database_service: Arc::new(DatabaseService::new()),
+            current_ops: CurrentOpsPanel::new(Arc::clone(&theme)),
+            stats_dashboard: StatsDashboard::new(Arc::clone(&theme)),
+            server_monitor: ServerMonitor::new(Arc::clone(&theme), data_dir),
+            topology_window: TopologyWindow::new(Arc::clone(&theme)),
+            profiler_window: ProfilerWindow::new(Arc::clone(&theme)),
+            validation_editor: ValidationEditor::new(Arc::clone(&theme)),
+            admin_window: AdminWindow::new(Arc::clone(&theme)),
+            saved_queries: SavedQueriesWindow::new(Arc::clone(&theme), data_dir),
+            registry: ConnectionRegistry::new(),
             query_service: Arc::new(QueryService::new()),
+            tabs: vec![QueryTab::new(
+                "Query 1".to_string(),
+                None,
+                Arc::clone(&theme),
+            )],
+            active_tab: 0,
+            next_tab_number: 2,
             theme,
             is_dark_mode: false,
-            query_tab,
-            results_tab,
         }
     }
 
     pub fn render(&mut self, ctx: &egui::Context) {
+        egui::TopBottomPanel::bottom("footer").show(ctx, |ui| {
+            self.render_footer(ui);
+        });
+        self.current_ops.show(ctx);
+        egui::SidePanel::left("explorer_sidebar")
+            .resizable(true)
+            .default_width(220.0)
+            .show(ctx, |ui| {
+                let active_connection = self.tabs[self.active_tab].connection_id.clone();
+                self.explorer_sidebar
+                    .render(ui, &self.registry, active_connection.as_deref());
+            });
         egui::CentralPanel::default().show(ctx, |ui| {
             ui.vertical(|ui| {
                 self.render_top_section(ui);
                 ui.add_space(10.0);
                 self.render_main_section(ui);
-                // ui.add_space(10.0);
-                // self.render_footer(ui);
             });
         });
+        self.stats_dashboard.show(ctx);
+        self.server_monitor.show(ctx);
+        self.topology_window.show(ctx);
+        self.profiler_window.show(ctx);
+        self.validation_editor.show(ctx);
+        self.admin_window.show(ctx);
+        self.saved_queries.show(ctx);
+    }
+
+    fn handle_connection_requests(&mut self, ctx: &egui::Context) {
+        if let Some(request) = self.connection_manager.take_connect_request() {
+            let id = self.registry.connect(ctx, request);
+            // The current tab takes the connection unless it's working with
+            // another one that is still open
+            let tab = &mut self.tabs[self.active_tab];
+            match &tab.connection_id {
+                Some(bound) if *bound != id && self.registry.get(bound).is_some() => {
+                    self.open_tab(Some(id))
+                }
+                _ => tab.connection_id = Some(id),
+            }
+        }
+
+        if let Some(id) = self.explorer_sidebar.take_disconnect_request() {
+            self.registry.disconnect(ctx, &id);
+        }
+        if let Some(request) = self.explorer_sidebar.take_new_tab_request() {
+            self.open_tab(Some(request.connection_id));
+            let tab = &mut self.tabs[self.active_tab];
+            if let Some(database) = request.database {
+                tab.database_selector.select(database);
+            }
+            if let Some(collection) = request.collection {
+                tab.collection_selector.select(collection);
+            }
+        }
+        if let Some(id) = self.explorer_sidebar.take_refresh_request() {
+            self.registry.refresh_databases(ctx, &id);
+            for tab in &mut self.tabs {
+                if tab.connection_id.as_deref() == Some(id.as_str()) {
+                    tab.refresh_collections();
+                }
+            }
+        }
+
+        if let Some(request) = self.explorer_sidebar.take_stats_request() {
+            self.open_stats(ctx, request);
+        }
+        if let Some(request) = self.tabs[self.active_tab].take_stats_request() {
+            self.open_stats(ctx, request);
+        }
+        if let Some(request) = self.explorer_sidebar.take_profiler_request() {
+            if let Some(entry) = self
+                .registry
+                .get(&request.connection_id)
+                .filter(|entry| entry.is_connected())
+            {
+                self.profiler_window.open(ctx, entry, request.database);
+            }
+        }
+        if let Some(request) = self.explorer_sidebar.take_validation_request() {
+            if let Some(entry) = self
+                .registry
+                .get(&request.connection_id)
+                .filter(|entry| entry.is_connected())
+            {
+                self.validation_editor
+                    .open(ctx, entry, request.database, request.collection);
+            }
+        }
+        if let Some(request) = self.explorer_sidebar.take_admin_request() {
+            if let Some(entry) = self
+                .registry
+                .get(&request.connection_id)
+                .filter(|entry| entry.is_connected())
+            {
+                self.admin_window.open(ctx, entry, request.database);
+            }
+        }
+        if let Some(name) = self.saved_queries.take_save_request() {
+            let query = self.tabs[self.active_tab].saved_query(name);
+            self.saved_queries.store(query);
+        }
+        if let Some(query) = self.saved_queries.take_open_request() {
+            self.tabs[self.active_tab].load_saved_query(&query);
+        }
+        if let Some(request) = self.profiler_window.take_explain_request() {
+            self.explain_in_new_tab(ctx, request);
+        }
+        if let Some(id) = self.explorer_sidebar.take_topology_request() {
+            if let Some(entry) = self.registry.get(&id).filter(|entry| entry.is_connected()) {
+                self.topology_window.open(ctx, entry);
+            }
+        }
+        if let Some(id) = self.explorer_sidebar.take_monitor_request() {
+            if let Some(entry) = self.registry.get(&id).filter(|entry| entry.is_connected()) {
+                self.server_monitor
+                    .open(Arc::clone(&entry.service), &entry.id, &entry.name);
+            }
+        }
+
+        for tab in &mut self.tabs {
+            if tab.database_selector.take_refresh_request() {
+                if let Some(id) = &tab.connection_id {
+                    self.registry.refresh_databases(ctx, id);
+                }
+            }
+        }
+
+        for profile_id in self.registry.poll(ctx) {
+            self.connection_manager.forget_session_password(&profile_id);
+        }
+
+        for tab in &mut self.tabs {
+            let databases = tab
+                .connection_id
+                .as_deref()
+                .and_then(|id| self.registry.get(id))
+                .map(|entry| entry.database_names())
+                .unwrap_or_default();
+            if tab.database_selector.databases() != databases.as_slice() {
+                tab.database_selector.set_databases(databases);
+            }
+            let connection = tab
+                .connection_id
+                .as_deref()
+                .and_then(|id| self.registry.get(id))
+                .filter(|entry| entry.is_connected());
+            tab.sync_collections(ctx, connection.map(|entry| &entry.service));
+            tab.sync_queries(ctx, connection, &self.query_service);
+        }
+        self.update_status();
+        let active = self.tabs[self.active_tab]
+            .connection_id
+            .as_deref()
+            .and_then(|id| self.registry.get(id));
+        self.current_ops.set_connection(active);
+    }
+
+    /// Opens a tab on the query's collection with the query loaded, and
+    /// explains it.
+    fn explain_in_new_tab(&mut self, ctx: &egui::Context, request: ExplainRequest) {
+        let Some(service) = self
+            .registry
+            .get(&request.connection_id)
+            .filter(|entry| entry.is_connected())
+            .map(|entry| Arc::clone(&entry.service))
+        else {
+            return;
+        };
+        self.open_tab(Some(request.connection_id));
+        let tab = &mut self.tabs[self.active_tab];
+        tab.database_selector.select(request.database);
+        tab.collection_selector.select(request.collection);
+        tab.load_query(
+            &request.filter,
+            request.projection.as_ref(),
+            request.sort.as_ref(),
+        );
+        tab.explain(ctx, Some(&service));
+    }
+
+    fn open_stats(&mut self, ctx: &egui::Context, request: StatsRequest) {
+        let Some(entry) = self
+            .registry
+            .get(&request.connection_id)
+            .filter(|entry| entry.is_connected())
+        else {
+            return;
+        };
+        self.stats_dashboard.open(
+            ctx,
+            Arc::clone(&entry.service),
+            &entry.name,
+            request.database,
+            request.collection,
+        );
+    }
+
+    fn active_connection(&self) -> Option<&ConnectionEntry> {
+        self.tabs[self.active_tab]
+            .connection_id
+            .as_deref()
+            .and_then(|id| self.registry.get(id))
+    }
+
+    /// The status bar describes the connection of the tab in front.
+    fn update_status(&mut self) {
+        let (status, read_only, topology) = match self.active_connection() {
+            Some(entry) => {
+                let status = match &entry.status {
+                    ConnectionStatus::Connecting => format!("Connecting to {}...", entry.name),
+                    ConnectionStatus::Connected => format!(
+                        "Connected to {} ({} databases)",
+                        entry.name,
+                        entry.databases.len()
+                    ),
+                    ConnectionStatus::Failed(error) => format!("Error: {}", error),
+                    ConnectionStatus::Disconnecting => {
+                        format!("Disconnecting from {}...", entry.name)
+                    }
+                };
+                let topology = entry
+                    .topology
+                    .as_ref()
+                    .filter(|_| entry.is_connected())
+                    .map(Topology::summary);
+                (status, entry.read_only(), topology)
+            }
+            None if self.tabs[self.active_tab].connection_id.is_some() => {
+                ("Disconnected".to_string(), false, None)
+            }
+            None => ("Not connected".to_string(), false, None),
+        };
+        self.status_bar.set_status(status);
+        self.status_bar.set_read_only(read_only);
+        self.status_bar.set_topology(topology);
+        self.status_bar
+            .set_query_started(self.tabs[self.active_tab].query_started());
+    }
+
+    fn open_tab(&mut self, connection_id: Option<String>) {
+        let title = format!("Query {}", self.next_tab_number);
+        self.next_tab_number += 1;
+        self.tabs
+            .push(QueryTab::new(title, connection_id, Arc::clone(&self.theme)));
+        self.active_tab = self.tabs.len() - 1;
+    }
+
+    fn close_tab(&mut self, ctx: &egui::Context, index: usize) {
+        if self.tabs.len() == 1 {
+            return;
+        }
+        let mut tab = self.tabs.remove(index);
+        let service = tab
+            .connection_id
+            .as_deref()
+            .and_then(|id| self.registry.get(id))
+            .filter(|entry| entry.is_connected())
+            .map(|entry| &entry.service);
+        tab.cancel(ctx, service);
+        if self.active_tab >= index && self.active_tab > 0 {
+            self.active_tab -= 1;
+        }
     }
 
     pub fn render_mongolite_logo(ui: &mut Ui, theme: Arc<Theme>) {
@@ -104,10 +341,46 @@ impl MongoDBClient {
 
     fn render_top_section(&mut self, ui: &mut Ui) {
         const LABEL_MARGIN_RIGHT: f32 = 5.0;
-        ui.vertical(|ui| {
+
+        // Paint the environment of the tab's connection around the whole
+        // section so a production connection can't be mistaken for a
+        // development one. Before the tab is connected, the selected
+        // profile's environment is shown instead.
+        let environment: Option<(Environment, String)> = match self.active_connection() {
+            Some(entry) => entry
+                .profile
+                .as_ref()
+                .map(|profile| (profile.environment, profile.name.clone())),
+            None => self
+                .connection_manager
+                .active_profile()
+                .map(|profile| (profile.environment, profile.name.clone())),
+        };
+        let environment_color = environment
+            .as_ref()
+            .and_then(|(environment, _)| self.theme.environment_color(*environment));
+        let mut frame = Frame::none()
+            .inner_margin(6.0)
+            .rounding(self.theme.frame_rounding);
+        if let Some(color) = environment_color {
+            frame = frame
+                .fill(color.linear_multiply(0.12))
+                .stroke(Stroke::new(2.0, color));
+        }
+
+        frame.show(ui, |ui| {
             // Logo and theme toggle
             ui.horizontal(|ui| {
                 MongoDBClient::render_mongolite_logo(ui, Arc::clone(&self.theme));
+                if let (Some(color), Some((environment, name))) = (environment_color, &environment)
+                {
+                    ui.add_space(10.0);
+                    ui.label(
+                        RichText::new(format!("{} · {}", environment.label().to_uppercase(), name))
+                            .color(color)
+                            .strong(),
+                    );
+                }
                 ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
                     if ui
                         .button(if self.is_dark_mode {
@@ -130,32 +403,90 @@ impl MongoDBClient {
 
             ui.add_space(10.0);
 
-            // Frame for database and collection selection
-            ui.vertical(|ui| {
-                // Database row
-                self.database_selector.render(ui, "database_selector");
-                ui.add_space(10.0);
+            self.render_tab_strip(ui);
+            ui.add_space(10.0);
 
-                // Collection row
-                self.collection_selector.render(ui, "collection_selector");
-                ui.add_space(10.0);
+            ui.horizontal(|ui| {
+                ui.label(
+                    RichText::new("Tab connection:")
+                        .color(self.theme.text_color)
+                        .strong(),
+                )
+                .on_hover_text("The connection this tab's queries run on");
+                let tab = &mut self.tabs[self.active_tab];
+                let selected = tab
+                    .connection_id
+                    .as_deref()
+                    .map(|id| match self.registry.get(id) {
+                        Some(entry) => entry.name.clone(),
+                        None => "Disconnected".to_string(),
+                    })
+                    .unwrap_or_else(|| "Not connected".to_string());
+                ComboBox::from_id_source("tab_connection")
+                    .width(290.0)
+                    .selected_text(selected)
+                    .show_ui(ui, |ui| {
+                        for entry in self.registry.iter() {
+                            ui.selectable_value(
+                                &mut tab.connection_id,
+                                Some(entry.id.clone()),
+                                &entry.name,
+                            );
+                        }
+                    });
             });
+            ui.add_space(10.0);
+
+            // Database and collection selection
+            self.tabs[self.active_tab].render_selectors(ui);
+            ui.add_space(10.0);
         });
     }
 
-    fn render_main_section(&mut self, ui: &mut Ui) {
-        ui.horizontal(|ui| {
-            ui.vertical(|ui| {
-                let max_width = ui.available_width().min(600.0); // Set a maximum width of 600 pixels
-                ui.set_max_width(max_width);
-                self.query_tab.render(ui, &mut self.query_builder);
-            });
-            ui.add_space(10.0);
-            ui.vertical(|ui| {
-                ui.set_min_width(ui.available_width());
-                self.results_tab.render(ui, &mut self.results_view);
-            });
+    fn render_tab_strip(&mut self, ui: &mut Ui) {
+        let mut close = None;
+        ui.horizontal_wrapped(|ui| {
+            for (index, tab) in self.tabs.iter().enumerate() {
+                let connection = tab
+                    .connection_id
+                    .as_deref()
+                    .and_then(|id| self.registry.get(id));
+                let mut title = RichText::new(match connection {
+                    Some(entry) => format!("{} · {}", tab.title, entry.name),
+                    None => tab.title.clone(),
+                });
+                if let Some(color) =
+                    connection.and_then(|entry| self.theme.environment_color(entry.environment()))
+                {
+                    title = title.color(color);
+                }
+                if ui
+                    .selectable_label(index == self.active_tab, title)
+                    .clicked()
+                {
+                    self.active_tab = index;
+                }
+                if self.tabs.len() > 1 && ui.small_button("×").on_hover_text("Close tab").clicked()
+                {
+                    close = Some(index);
+                }
+                ui.add_space(6.0);
+            }
+            if ui
+                .button("+")
+                .on_hover_text("New query tab (Ctrl+N)")
+                .clicked()
+            {
+                self.new_query_tab();
+            }
         });
+        if let Some(index) = close {
+            self.close_tab(ui.ctx(), index);
+        }
+    }
+
+    fn render_main_section(&mut self, ui: &mut Ui) {
+        self.tabs[self.active_tab].render_workspace(ui);
     }
 
     fn render_footer(&mut self, ui: &mut Ui) {
@@ -166,17 +497,8 @@ impl MongoDBClient {
                     ui.add_space(10.0);
                     self.status_bar.render(ui, "status_bar");
                     ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
-                        if ui
-                            .button(if self.is_dark_mode {
-                                "Light Theme"
-                            } else {
-                                "Dark Theme"
-                            })
-                            .clicked()
-                        {
-                            self.toggle_theme(ui.ctx());
-                        }
                         ui.add_space(10.0);
+                        self.current_ops.render_toggle(ui);
                     });
                 });
             });
@@ -194,30 +516,38 @@ impl MongoDBClient {
 
         // Update theme for all components
         self.connection_manager.update_theme(Arc::clone(&new_theme));
-        self.database_selector.update_theme(Arc::clone(&new_theme));
-        self.collection_selector
-            .update_theme(Arc::clone(&new_theme));
-        self.query_builder.update_theme(Arc::clone(&new_theme));
-        self.results_view.update_theme(Arc::clone(&new_theme));
+        self.explorer_sidebar.update_theme(Arc::clone(&new_theme));
         self.status_bar.update_theme(Arc::clone(&new_theme));
-        self.query_tab.update_theme(Arc::clone(&new_theme));
-        self.results_tab.update_theme(Arc::clone(&new_theme));
+        self.current_ops.update_theme(Arc::clone(&new_theme));
+        self.stats_dashboard.update_theme(Arc::clone(&new_theme));
+        self.server_monitor.update_theme(Arc::clone(&new_theme));
+        self.topology_window.update_theme(Arc::clone(&new_theme));
+        self.profiler_window.update_theme(Arc::clone(&new_theme));
+        self.validation_editor.update_theme(Arc::clone(&new_theme));
+        self.admin_window.update_theme(Arc::clone(&new_theme));
+        self.saved_queries.update_theme(Arc::clone(&new_theme));
+        for tab in &mut self.tabs {
+            tab.update_theme(Arc::clone(&new_theme));
+        }
     }
 
     fn execute_query(&mut self) {
-        // Implementation for executing the current query
+        self.tabs[self.active_tab].request_execute();
     }
 
     fn save_query(&mut self) {
-        // Implementation for saving the current query
+        let title = self.tabs[self.active_tab].title.clone();
+        self.saved_queries.open(&title);
     }
 
     fn open_query(&mut self) {
-        // Implementation for opening a saved query
+        self.saved_queries.open("");
     }
 
+    /// Opens a tab on the same connection as the current one.
     fn new_query_tab(&mut self) {
-        // Implementation for creating a new query tab
+        let connection_id = self.tabs[self.active_tab].connection_id.clone();
+        self.open_tab(connection_id);
     }
 }
 
@@ -235,6 +565,7 @@ impl eframe::App for MongoDBClient {
         if ctx.input(|i| i.modifiers.ctrl && i.key_pressed(egui::Key::N)) {
             self.new_query_tab();
         }
+        self.handle_connection_requests(ctx);
         self.render(ctx);
     }
 }
@@ -0,0 +1,324 @@
+use crate::models::{ConnectionProfile, Environment, SshAuthMethod, SshTunnelSettings};
+use crate::utils::error::{MongoLiteError, Result};
+use bincode::Options;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+/// Prefix of every record written inside an `Envelope`. Records written
+/// before the envelope was introduced are bare bincode with no version.
+const RECORD_MAGIC: [u8; 4] = *b"MLPR";
+
+type Migration = fn(&[u8]) -> bincode::Result<Vec<u8>>;
+
+/// `MIGRATIONS[n]` upgrades a record from version `n + 1` to `n + 2`.
+const MIGRATIONS: &[Migration] = &[v1_to_v2];
+
+/// Version of the stored profile layout. Records are only ever read and
+/// written through the frozen `ProfileVn` structs below, never through the
+/// live `ConnectionProfile` or the types it embeds. Whenever a field is added
+/// or changed, add a new frozen struct for the new layout, a migration to it
+/// and a fixture for it under `tests/fixtures/profiles`.
+pub const CURRENT_VERSION: u32 = MIGRATIONS.len() as u32 + 1;
+
+#[derive(Serialize, Deserialize)]
+struct Envelope {
+    version: u32,
+    data: Vec<u8>,
+}
+
+/// Version 1: the original profile, a name and a connection string, stored
+/// as bare bincode.
+#[derive(Serialize, Deserialize)]
+struct ProfileV1 {
+    id: String,
+    name: String,
+    connection_string: String,
+}
+
+/// Version 2 added the envelope, folders, tags, colors, environments,
+/// read-only mode, the password prompt, SSH tunnels and the default query
+/// timeout.
+#[derive(Serialize, Deserialize)]
+struct ProfileV2 {
+    id: String,
+    name: String,
+    connection_string: String,
+    folder: String,
+    tags: Vec<String>,
+    color: Option<[u8; 3]>,
+    environment: EnvironmentV2,
+    read_only: bool,
+    ask_password: bool,
+    ssh_tunnel: Option<SshTunnelSettingsV2>,
+    default_max_time_ms: Option<u64>,
+}
+
+/// `Environment` as stored since version 2.
+#[derive(Serialize, Deserialize)]
+enum EnvironmentV2 {
+    None,
+    Development,
+    Staging,
+    Production,
+}
+
+/// `SshTunnelSettings` as stored since version 2.
+#[derive(Serialize, Deserialize)]
+struct SshTunnelSettingsV2 {
+    host: String,
+    port: u16,
+    user: String,
+    auth_method: SshAuthMethodV2,
+    password: String,
+    key_file: String,
+    key_passphrase: String,
+}
+
+#[derive(Serialize, Deserialize)]
+enum SshAuthMethodV2 {
+    Password,
+    PrivateKey,
+}
+
+impl From<&ConnectionProfile> for ProfileV2 {
+    fn from(profile: &ConnectionProfile) -> Self {
+        Self {
+            id: profile.id.clone(),
+            name: profile.name.clone(),
+            connection_string: profile.connection_string.clone(),
+            folder: profile.folder.clone(),
+            tags: profile.tags.clone(),
+            color: profile.color,
+            environment: match profile.environment {
+                Environment::None => EnvironmentV2::None,
+                Environment::Development => EnvironmentV2::Development,
+                Environment::Staging => EnvironmentV2::Staging,
+                Environment::Production => EnvironmentV2::Production,
+            },
+            read_only: profile.read_only,
+            ask_password: profile.ask_password,
+            ssh_tunnel: profile
+                .ssh_tunnel
+                .as_ref()
+                .map(|tunnel| SshTunnelSettingsV2 {
+                    host: tunnel.host.clone(),
+                    port: tunnel.port,
+                    user: tunnel.user.clone(),
+                    auth_method: match tunnel.auth_method {
+                        SshAuthMethod::Password => SshAuthMethodV2::Password,
+                        SshAuthMethod::PrivateKey => SshAuthMethodV2::PrivateKey,
+                    },
+                    password: tunnel.password.clone(),
+                    key_file: tunnel.key_file.clone(),
+                    key_passphrase: tunnel.key_passphrase.clone(),
+                }),
+            default_max_time_ms: profile.default_max_time_ms,
+        }
+    }
+}
+
+impl From<ProfileV2> for ConnectionProfile {
+    fn from(v2: ProfileV2) -> Self {
+        Self {
+            id: v2.id,
+            name: v2.name,
+            connection_string: v2.connection_string,
+            folder: v2.folder,
+            tags: v2.tags,
+            color: v2.color,
+            environment: match v2.environment {
+                EnvironmentV2::None => Environment::None,
+                EnvironmentV2::Development => Environment::Development,
+                EnvironmentV2::Staging => Environment::Staging,
+                EnvironmentV2::Production => Environment::Production,
+            },
+            read_only: v2.read_only,
+            ask_password: v2.ask_password,
+            ssh_tunnel: v2.ssh_tunnel.map(|tunnel| SshTunnelSettings {
+                host: tunnel.host,
+                port: tunnel.port,
+                user: tunnel.user,
+                auth_method: match tunnel.auth_method {
+                    SshAuthMethodV2::Password => SshAuthMethod::Password,
+                    SshAuthMethodV2::PrivateKey => SshAuthMethod::PrivateKey,
+                },
+                password: tunnel.password,
+                key_file: tunnel.key_file,
+                key_passphrase: tunnel.key_passphrase,
+            }),
+            default_max_time_ms: v2.default_max_time_ms,
+        }
+    }
+}
+
+// Same encoding as `bincode::serialize`, but decoding fails on trailing bytes
+// so that an old layout can't be mistaken for a prefix of a newer one.
+fn bincode_options() -> impl Options {
+    bincode::DefaultOptions::new()
+        .with_fixint_encoding()
+        .reject_trailing_bytes()
+}
+
+fn decode_exact<T: DeserializeOwned>(bytes: &[u8]) -> bincode::Result<T> {
+    bincode_options().deserialize(bytes)
+}
+
+fn v1_to_v2(bytes: &[u8]) -> bincode::Result<Vec<u8>> {
+    let v1: ProfileV1 = decode_exact(bytes)?;
+    bincode_options().serialize(&ProfileV2 {
+        id: v1.id,
+        name: v1.name,
+        connection_string: v1.connection_string,
+        folder: String::new(),
+        tags: Vec::new(),
+        color: None,
+        environment: EnvironmentV2::None,
+        read_only: false,
+        ask_password: false,
+        ssh_tunnel: None,
+        default_max_time_ms: None,
+    })
+}
+
+/// Records from before the envelope carry no version. Only version 1 was
+/// ever stored that way.
+fn legacy_version(bytes: &[u8]) -> Option<u32> {
+    decode_exact::<ProfileV1>(bytes).ok().map(|_| 1)
+}
+
+/// Serializes a profile in the current version, inside an envelope.
+pub fn encode(profile: &ConnectionProfile) -> Result<Vec<u8>> {
+    let serialize_error = |e: bincode::Error| {
+        MongoLiteError::StorageError(format!("cannot serialize profile: {}", e))
+    };
+    let data = bincode_options()
+        .serialize(&ProfileV2::from(profile))
+        .map_err(serialize_error)?;
+    let envelope = bincode_options()
+        .serialize(&Envelope {
+            version: CURRENT_VERSION,
+            data,
+        })
+        .map_err(serialize_error)?;
+    Ok([RECORD_MAGIC.as_slice(), &envelope].concat())
+}
+
+/// Reads a stored record, upgrading it to the current version if needed.
+/// Returns the profile and the version the record was stored in.
+pub fn decode(key: &str, bytes: &[u8]) -> Result<(ConnectionProfile, u32)> {
+    let corrupted = |detail: String| {
+        MongoLiteError::StorageCorrupted(format!("profile '{}' cannot be read: {}", key, detail))
+    };
+
+    let (version, mut data) = match bytes.strip_prefix(RECORD_MAGIC.as_slice()) {
+        Some(envelope) => {
+            let envelope: Envelope =
+                decode_exact(envelope).map_err(|e| corrupted(e.to_string()))?;
+            (envelope.version, envelope.data)
+        }
+        None => match legacy_version(bytes) {
+            Some(version) => (version, bytes.to_vec()),
+            None => return Err(corrupted("unrecognized record layout".to_string())),
+        },
+    };
+    if version == 0 {
+        return Err(corrupted("record version 0".to_string()));
+    }
+    if version > CURRENT_VERSION {
+        return Err(MongoLiteError::StorageError(format!(
+            "profile '{}' was saved by a newer version of Mongolite (record version {})",
+            key, version
+        )));
+    }
+
+    for migration in &MIGRATIONS[version as usize - 1..] {
+        data = migration(&data).map_err(|e| corrupted(e.to_string()))?;
+    }
+    let profile: ProfileV2 = decode_exact(&data).map_err(|e| corrupted(e.to_string()))?;
+    Ok((profile.into(), version))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const V1_FIXTURE: &[u8] = include_bytes!("../../tests/fixtures/profiles/v1.bin");
+    const V2_FIXTURE: &[u8] = include_bytes!("../../tests/fixtures/profiles/v2.bin");
+
+    fn tunneled_profile() -> ConnectionProfile {
+        ConnectionProfile {
+            id: "9d3f4a62-1c7e-4b8a-a0f5-6e2b7c9d1e30".to_string(),
+            name: "Production".to_string(),
+            connection_string: "mongodb+srv://reporting@cluster0.example.net/sales".to_string(),
+            folder: "Clients/Acme".to_string(),
+            tags: vec!["acme".to_string(), "prod".to_string()],
+            color: Some([234, 67, 53]),
+            environment: Environment::Production,
+            read_only: true,
+            ask_password: true,
+            ssh_tunnel: Some(SshTunnelSettings {
+                host: "bastion.example.net".to_string(),
+                port: 2222,
+                user: "deploy".to_string(),
+                auth_method: SshAuthMethod::PrivateKey,
+                password: String::new(),
+                key_file: "~/.ssh/id_ed25519".to_string(),
+                key_passphrase: "${SSH_KEY_PASSPHRASE}".to_string(),
+            }),
+            default_max_time_ms: Some(30_000),
+        }
+    }
+
+    #[test]
+    fn upgrades_version_1_records() {
+        let (profile, version) = decode("v1", V1_FIXTURE).unwrap();
+        assert_eq!(version, 1);
+        assert_eq!(
+            profile,
+            ConnectionProfile {
+                id: "5b0c9b1e-6a55-4c8a-9a8e-0d3c2f0e7a11".to_string(),
+                name: "Local".to_string(),
+                connection_string: "mongodb://localhost:27017".to_string(),
+                ..ConnectionProfile::default()
+            }
+        );
+    }
+
+    #[test]
+    fn current_format_matches_fixture() {
+        // Fails if the stored layout changed without a new version
+        let profile = tunneled_profile();
+        assert_eq!(encode(&profile).unwrap(), V2_FIXTURE);
+        let (decoded, version) = decode("v2", V2_FIXTURE).unwrap();
+        assert_eq!(version, CURRENT_VERSION);
+        assert_eq!(decoded, profile);
+    }
+
+    #[test]
+    fn rejects_records_from_newer_versions() {
+        let envelope = bincode_options()
+            .serialize(&Envelope {
+                version: CURRENT_VERSION + 1,
+                data: Vec::new(),
+            })
+            .unwrap();
+        let record = [RECORD_MAGIC.as_slice(), &envelope].concat();
+        assert!(matches!(
+            decode("future", &record),
+            Err(MongoLiteError::StorageError(_))
+        ));
+    }
+
+    #[test]
+    fn rejects_unrecognized_records() {
+        assert!(matches!(
+            decode("garbage", b"not a profile"),
+            Err(MongoLiteError::StorageCorrupted(_))
+        ));
+        let truncated = &V2_FIXTURE[..V2_FIXTURE.len() - 4];
+        assert!(matches!(
+            decode("truncated", truncated),
+            Err(MongoLiteError::StorageCorrupted(_))
+        ));
+    }
+}
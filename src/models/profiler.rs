@@ -0,0 +1,317 @@
+use super::database_stats::to_u64;
+use mongodb::bson::{Bson, DateTime, Document};
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProfilingLevel {
+    Off,
+    SlowOperations,
+    All,
+}
+
+impl ProfilingLevel {
+    pub const ALL: [ProfilingLevel; 3] = [
+        ProfilingLevel::Off,
+        ProfilingLevel::SlowOperations,
+        ProfilingLevel::All,
+    ];
+
+    pub fn from_level(level: i64) -> Self {
+        match level {
+            1 => ProfilingLevel::SlowOperations,
+            2 => ProfilingLevel::All,
+            _ => ProfilingLevel::Off,
+        }
+    }
+
+    pub fn level(&self) -> i32 {
+        match self {
+            ProfilingLevel::Off => 0,
+            ProfilingLevel::SlowOperations => 1,
+            ProfilingLevel::All => 2,
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            ProfilingLevel::Off => "Off",
+            ProfilingLevel::SlowOperations => "Slow operations",
+            ProfilingLevel::All => "All operations",
+        }
+    }
+}
+
+/// A database's profiler settings, from `{ profile: -1 }`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProfilerStatus {
+    pub level: ProfilingLevel,
+    /// Operations slower than this are slow, for the profiler and the log.
+    pub slow_ms: i64,
+}
+
+impl ProfilerStatus {
+    pub fn from_document(reply: &Document) -> Self {
+        let number = |key| reply.get(key).and_then(to_u64).unwrap_or(0) as i64;
+        Self {
+            level: ProfilingLevel::from_level(number("was")),
+            slow_ms: match reply.get("slowms").and_then(to_u64) {
+                Some(slow_ms) => slow_ms as i64,
+                None => 100,
+            },
+        }
+    }
+}
+
+/// One `system.profile` entry.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProfiledOperation {
+    pub time: Option<DateTime>,
+    /// `query`, `update`, `remove`, or the command's name for commands.
+    pub operation: String,
+    pub namespace: String,
+    pub millis: u64,
+    pub plan_summary: String,
+    pub keys_examined: u64,
+    pub docs_examined: u64,
+    pub returned: u64,
+    /// The query's filter, sort and projection, when it has a filter.
+    pub filter: Option<Document>,
+    pub sort: Option<Document>,
+    pub projection: Option<Document>,
+}
+
+impl ProfiledOperation {
+    pub fn from_document(entry: &Document) -> Self {
+        let number = |key| entry.get(key).and_then(to_u64).unwrap_or(0);
+        let op = entry.get_str("op").unwrap_or_default();
+        let command = entry.get_document("command").ok();
+        let name = command
+            .and_then(|command| command.keys().next())
+            .map(String::as_str);
+        let operation = match (op, name) {
+            ("command", Some(name)) => name.to_string(),
+            (op, _) => op.to_string(),
+        };
+
+        // A getMore's query is in the command that opened the cursor
+        let query = match op {
+            "getmore" => entry.get_document("originatingCommand").ok(),
+            _ => command,
+        };
+        let (filter, sort, projection) = match query {
+            Some(query) => query_parts(query),
+            None => (None, None, None),
+        };
+
+        Self {
+            time: entry.get_datetime("ts").ok().copied(),
+            operation,
+            namespace: entry.get_str("ns").unwrap_or_default().to_string(),
+            millis: number("millis"),
+            plan_summary: entry.get_str("planSummary").unwrap_or_default().to_string(),
+            keys_examined: number("keysExamined"),
+            docs_examined: number("docsExamined"),
+            returned: number("nreturned"),
+            filter,
+            sort,
+            projection,
+        }
+    }
+
+    /// The collection part of the namespace.
+    pub fn collection(&self) -> &str {
+        self.namespace
+            .split_once('.')
+            .map_or("", |(_, collection)| collection)
+    }
+
+    /// The filter's fields and operators with the values left out, so
+    /// queries that differ only in their values group together.
+    pub fn shape(&self) -> String {
+        let mut shape = Document::new();
+        if let Some(filter) = &self.filter {
+            shape.insert("filter", shape_of(&Bson::Document(filter.clone())));
+        }
+        if let Some(sort) = &self.sort {
+            shape.insert("sort", Bson::Document(sort.clone()));
+        }
+        Bson::Document(shape).into_relaxed_extjson().to_string()
+    }
+}
+
+fn query_parts(command: &Document) -> (Option<Document>, Option<Document>, Option<Document>) {
+    let document = |key| command.get_document(key).ok().cloned();
+    if command.contains_key("find") {
+        (
+            Some(document("filter").unwrap_or_default()),
+            document("sort"),
+            document("projection"),
+        )
+    } else if command.contains_key("count") || command.contains_key("distinct") {
+        (Some(document("query").unwrap_or_default()), None, None)
+    } else if command.contains_key("aggregate") {
+        let first_match = command
+            .get_array("pipeline")
+            .ok()
+            .and_then(|pipeline| pipeline.first())
+            .and_then(Bson::as_document)
+            .and_then(|stage| stage.get_document("$match").ok())
+            .cloned();
+        (first_match, None, None)
+    } else if let Some(query) = document("q") {
+        // A single update or delete statement
+        (Some(query), None, None)
+    } else {
+        (None, None, None)
+    }
+}
+
+fn shape_of(value: &Bson) -> Bson {
+    match value {
+        Bson::Document(document) => Bson::Document(
+            document
+                .iter()
+                .map(|(key, value)| (key.clone(), shape_of(value)))
+                .collect(),
+        ),
+        // $and, $or and $nor hold documents; other arrays are values
+        Bson::Array(values) if values.iter().all(|value| value.as_document().is_some()) => {
+            Bson::Array(values.iter().map(shape_of).collect())
+        }
+        _ => Bson::String("?".to_string()),
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProfileSort {
+    TotalDuration,
+    AverageDuration,
+}
+
+/// Profiled operations on one namespace with the same query shape.
+#[derive(Debug, Clone, PartialEq)]
+pub struct QueryShapeGroup {
+    pub namespace: String,
+    pub operation: String,
+    pub shape: String,
+    /// Slowest first.
+    pub operations: Vec<ProfiledOperation>,
+    pub total_millis: u64,
+}
+
+impl QueryShapeGroup {
+    pub fn average_millis(&self) -> f64 {
+        self.total_millis as f64 / self.operations.len().max(1) as f64
+    }
+
+    pub fn max_millis(&self) -> u64 {
+        self.operations
+            .first()
+            .map_or(0, |operation| operation.millis)
+    }
+}
+
+/// Groups the operations by namespace, operation and query shape, slowest
+/// group first by `sort`.
+pub fn group_by_shape(
+    operations: Vec<ProfiledOperation>,
+    sort: ProfileSort,
+) -> Vec<QueryShapeGroup> {
+    let mut groups: Vec<QueryShapeGroup> = Vec::new();
+    let mut positions = HashMap::new();
+    for operation in operations {
+        let shape = operation.shape();
+        let key = (
+            operation.namespace.clone(),
+            operation.operation.clone(),
+            shape.clone(),
+        );
+        let position = *positions.entry(key).or_insert_with(|| {
+            groups.push(QueryShapeGroup {
+                namespace: operation.namespace.clone(),
+                operation: operation.operation.clone(),
+                shape,
+                operations: Vec::new(),
+                total_millis: 0,
+            });
+            groups.len() - 1
+        });
+        let group = &mut groups[position];
+        group.total_millis += operation.millis;
+        group.operations.push(operation);
+    }
+    for group in &mut groups {
+        group
+            .operations
+            .sort_by_key(|operation| std::cmp::Reverse(operation.millis));
+    }
+    match sort {
+        ProfileSort::TotalDuration => {
+            groups.sort_by_key(|group| std::cmp::Reverse(group.total_millis))
+        }
+        ProfileSort::AverageDuration => {
+            groups.sort_by(|a, b| b.average_millis().total_cmp(&a.average_millis()))
+        }
+    }
+    groups
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mongodb::bson::doc;
+
+    fn find(total: i32, millis: i64) -> Document {
+        doc! {
+            "op": "query",
+            "ns": "shop.orders",
+            "command": {
+                "find": "orders",
+                "filter": { "status": "open", "total": { "$gt": total } },
+                "sort": { "created": -1 },
+                "$db": "shop",
+            },
+            "millis": millis,
+            "planSummary": "COLLSCAN",
+            "docsExamined": 50000,
+            "nreturned": 12,
+            "ts": DateTime::from_millis(1_700_000_000_000),
+        }
+    }
+
+    #[test]
+    fn groups_queries_that_differ_only_in_values() {
+        let operations = vec![
+            ProfiledOperation::from_document(&find(100, 40)),
+            ProfiledOperation::from_document(&find(500, 300)),
+            ProfiledOperation::from_document(&doc! {
+                "op": "command",
+                "ns": "shop.orders",
+                "command": { "count": "orders", "query": { "status": "open" } },
+                "millis": 200_i64,
+            }),
+        ];
+        assert_eq!(operations[0].collection(), "orders");
+        assert_eq!(operations[2].operation, "count");
+
+        let groups = group_by_shape(operations.clone(), ProfileSort::TotalDuration);
+        assert_eq!(groups.len(), 2);
+        assert_eq!(groups[0].operations.len(), 2);
+        assert_eq!(groups[0].total_millis, 340);
+        assert_eq!(groups[0].max_millis(), 300);
+        assert_eq!(
+            groups[0].operations[0].filter,
+            Some(doc! { "status": "open", "total": { "$gt": 500 } })
+        );
+
+        let groups = group_by_shape(operations, ProfileSort::AverageDuration);
+        assert_eq!(groups[0].operation, "count");
+    }
+
+    #[test]
+    fn reads_profiler_status() {
+        let status = ProfilerStatus::from_document(&doc! { "was": 1, "slowms": 50, "ok": 1.0 });
+        assert_eq!(status.level, ProfilingLevel::SlowOperations);
+        assert_eq!(status.slow_ms, 50);
+    }
+}
@@ -0,0 +1,321 @@
+use crate::models::ReadPreferenceMode;
+use crate::utils::extended_json::parse_document;
+use mongodb::bson::{self, Bson, Document};
+use mongodb::options::{
+    Acknowledgment, Collation, CollectionOptions, FindOptions, Hint, ReadConcern, ReadPreference,
+    ReadPreferenceOptions, SelectionCriteria, TagSet, WriteConcern,
+};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// The smallest `maxStalenessSeconds` the server accepts.
+const MIN_MAX_STALENESS_SECONDS: u64 = 90;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ReadConcernMode {
+    Local,
+    Available,
+    Majority,
+    Linearizable,
+    Snapshot,
+}
+
+impl ReadConcernMode {
+    pub const ALL: [ReadConcernMode; 5] = [
+        ReadConcernMode::Local,
+        ReadConcernMode::Available,
+        ReadConcernMode::Majority,
+        ReadConcernMode::Linearizable,
+        ReadConcernMode::Snapshot,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            ReadConcernMode::Local => "local",
+            ReadConcernMode::Available => "available",
+            ReadConcernMode::Majority => "majority",
+            ReadConcernMode::Linearizable => "linearizable",
+            ReadConcernMode::Snapshot => "snapshot",
+        }
+    }
+
+    fn read_concern(&self) -> ReadConcern {
+        match self {
+            ReadConcernMode::Local => ReadConcern::local(),
+            ReadConcernMode::Available => ReadConcern::available(),
+            ReadConcernMode::Majority => ReadConcern::majority(),
+            ReadConcernMode::Linearizable => ReadConcern::linearizable(),
+            ReadConcernMode::Snapshot => ReadConcern::snapshot(),
+        }
+    }
+}
+
+/// Options for one query, as typed in the query builder. Empty text and
+/// `None` leave the connection's or server's default in place.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct QueryOptions {
+    pub read_preference: Option<ReadPreferenceMode>,
+    /// Tag sets tried in order, separated by `;`, each a comma-separated
+    /// list of `key:value` tags, e.g. `dc:east,rack:1; dc:west`.
+    pub read_preference_tags: String,
+    pub max_staleness_seconds: Option<u64>,
+    pub read_concern: Option<ReadConcernMode>,
+    /// `majority`, a number of members or a custom write concern name.
+    pub write_concern_w: String,
+    pub write_concern_journal: bool,
+    pub write_concern_timeout_ms: Option<u64>,
+    pub max_time_ms: Option<u64>,
+    /// An index name, or its key pattern as JSON.
+    pub hint: String,
+    /// JSON, e.g. `{ "locale": "en", "strength": 2 }`.
+    pub collation: String,
+    pub comment: String,
+    pub batch_size: Option<u32>,
+    pub allow_disk_use: bool,
+    pub no_cursor_timeout: bool,
+}
+
+impl QueryOptions {
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+
+    pub fn selection_criteria(&self) -> Result<Option<SelectionCriteria>, String> {
+        let tag_sets = parse_tag_sets(&self.read_preference_tags)?;
+        if let Some(seconds) = self.max_staleness_seconds {
+            if seconds < MIN_MAX_STALENESS_SECONDS {
+                return Err(format!(
+                    "Max staleness must be at least {} seconds",
+                    MIN_MAX_STALENESS_SECONDS
+                ));
+            }
+        }
+        let options = ReadPreferenceOptions::builder()
+            .tag_sets(Some(tag_sets.clone()).filter(|tag_sets| !tag_sets.is_empty()))
+            .max_staleness(self.max_staleness_seconds.map(Duration::from_secs))
+            .build();
+        let read_preference = match self.read_preference {
+            None if tag_sets.is_empty() && self.max_staleness_seconds.is_none() => return Ok(None),
+            None => return Err("Tags and max staleness need a read preference".to_string()),
+            Some(ReadPreferenceMode::Primary) => {
+                if !tag_sets.is_empty() || self.max_staleness_seconds.is_some() {
+                    return Err("Tags and max staleness don't apply to primary".to_string());
+                }
+                ReadPreference::Primary
+            }
+            Some(ReadPreferenceMode::PrimaryPreferred) => {
+                ReadPreference::PrimaryPreferred { options }
+            }
+            Some(ReadPreferenceMode::Secondary) => ReadPreference::Secondary { options },
+            Some(ReadPreferenceMode::SecondaryPreferred) => {
+                ReadPreference::SecondaryPreferred { options }
+            }
+            Some(ReadPreferenceMode::Nearest) => ReadPreference::Nearest { options },
+        };
+        Ok(Some(SelectionCriteria::ReadPreference(read_preference)))
+    }
+
+    pub fn write_concern(&self) -> Result<Option<WriteConcern>, String> {
+        let w = match self.write_concern_w.trim() {
+            "" => None,
+            "majority" => Some(Acknowledgment::Majority),
+            w => match w.parse::<u32>() {
+                Ok(nodes) => Some(Acknowledgment::Nodes(nodes)),
+                Err(_) => Some(Acknowledgment::Custom(w.to_string())),
+            },
+        };
+        let journal = Some(true).filter(|_| self.write_concern_journal);
+        if w.is_none() && journal.is_none() && self.write_concern_timeout_ms.is_none() {
+            return Ok(None);
+        }
+        Ok(Some(
+            WriteConcern::builder()
+                .w(w)
+                .journal(journal)
+                .w_timeout(self.write_concern_timeout_ms.map(Duration::from_millis))
+                .build(),
+        ))
+    }
+
+    /// Read preference, read concern and write concern for the collection
+    /// the query runs on, so writes through it use the same settings.
+    pub fn collection_options(&self) -> Result<CollectionOptions, String> {
+        Ok(CollectionOptions::builder()
+            .selection_criteria(self.selection_criteria()?)
+            .read_concern(self.read_concern.map(|mode| mode.read_concern()))
+            .write_concern(self.write_concern()?)
+            .build())
+    }
+
+    pub fn hint(&self) -> Result<Option<Hint>, String> {
+        let hint = self.hint.trim();
+        if hint.is_empty() {
+            Ok(None)
+        } else if hint.starts_with('{') {
+            parse_document(hint)
+                .map(|keys| Some(Hint::Keys(keys)))
+                .map_err(|e| format!("Hint: {}", e))
+        } else {
+            Ok(Some(Hint::Name(hint.to_string())))
+        }
+    }
+
+    fn collation_document(&self) -> Result<Option<Document>, String> {
+        if self.collation.trim().is_empty() {
+            return Ok(None);
+        }
+        parse_document(&self.collation)
+            .map(Some)
+            .map_err(|e| format!("Collation: {}", e))
+    }
+
+    pub fn collation(&self) -> Result<Option<Collation>, String> {
+        self.collation_document()?
+            .map(|collation| {
+                bson::from_document(collation).map_err(|e| format!("Collation: {}", e))
+            })
+            .transpose()
+    }
+
+    pub fn find_options(
+        &self,
+        projection: Option<Document>,
+        sort: Option<Document>,
+    ) -> Result<FindOptions, String> {
+        let mut options = FindOptions::default();
+        options.projection = projection;
+        options.sort = sort;
+        options.selection_criteria = self.selection_criteria()?;
+        options.read_concern = self.read_concern.map(|mode| mode.read_concern());
+        options.max_time = self.max_time_ms.map(Duration::from_millis);
+        options.hint = self.hint()?;
+        options.collation = self.collation()?;
+        options.comment = Some(self.comment.clone()).filter(|comment| !comment.is_empty());
+        options.batch_size = self.batch_size;
+        options.allow_disk_use = Some(true).filter(|_| self.allow_disk_use);
+        options.no_cursor_timeout = Some(true).filter(|_| self.no_cursor_timeout);
+        Ok(options)
+    }
+
+    /// Adds the options that change a query's plan or limits to a `find`
+    /// command that is explained.
+    pub fn apply_to_find_command(&self, find: &mut Document) -> Result<(), String> {
+        match self.hint()? {
+            Some(Hint::Keys(keys)) => {
+                find.insert("hint", keys);
+            }
+            Some(Hint::Name(name)) => {
+                find.insert("hint", name);
+            }
+            _ => {}
+        }
+        if let Some(collation) = self.collation_document()? {
+            find.insert("collation", collation);
+        }
+        if let Some(max_time_ms) = self.max_time_ms {
+            find.insert("maxTimeMS", Bson::Int64(max_time_ms as i64));
+        }
+        if !self.comment.is_empty() {
+            find.insert("comment", &self.comment);
+        }
+        if self.allow_disk_use {
+            find.insert("allowDiskUse", true);
+        }
+        Ok(())
+    }
+}
+
+fn parse_tag_sets(text: &str) -> Result<Vec<TagSet>, String> {
+    text.split(';')
+        .map(str::trim)
+        .filter(|tag_set| !tag_set.is_empty())
+        .map(|tag_set| {
+            tag_set
+                .split(',')
+                .map(str::trim)
+                .filter(|tag| !tag.is_empty())
+                .map(|tag| match tag.split_once(':') {
+                    Some((key, value)) if !key.trim().is_empty() => {
+                        Ok((key.trim().to_string(), value.trim().to_string()))
+                    }
+                    _ => Err(format!("Tag '{}' is not key:value", tag)),
+                })
+                .collect()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mongodb::bson::doc;
+
+    #[test]
+    fn builds_driver_options() {
+        let options = QueryOptions {
+            read_preference: Some(ReadPreferenceMode::Secondary),
+            read_preference_tags: "dc:east, rack:1; dc:west".to_string(),
+            max_staleness_seconds: Some(120),
+            read_concern: Some(ReadConcernMode::Majority),
+            max_time_ms: Some(5000),
+            hint: r#"{ "status": 1 }"#.to_string(),
+            collation: r#"{ "locale": "en", "strength": 2 }"#.to_string(),
+            comment: "report".to_string(),
+            batch_size: Some(50),
+            ..Default::default()
+        };
+        let find = options.find_options(None, None).unwrap();
+        let Some(SelectionCriteria::ReadPreference(ReadPreference::Secondary { options: read })) =
+            find.selection_criteria
+        else {
+            panic!("expected a secondary read preference");
+        };
+        let tag_sets = read.tag_sets.unwrap();
+        assert_eq!(tag_sets.len(), 2);
+        assert_eq!(tag_sets[0].get("rack").map(String::as_str), Some("1"));
+        assert_eq!(read.max_staleness, Some(Duration::from_secs(120)));
+        assert_eq!(find.read_concern, Some(ReadConcern::majority()));
+        assert_eq!(find.max_time, Some(Duration::from_millis(5000)));
+        assert!(matches!(find.hint, Some(Hint::Keys(_))));
+        assert_eq!(
+            find.collation.map(|collation| collation.locale),
+            Some("en".to_string())
+        );
+
+        let mut command = doc! { "find": "orders" };
+        options.apply_to_find_command(&mut command).unwrap();
+        assert_eq!(command.get_i64("maxTimeMS"), Ok(5000));
+        assert_eq!(command.get_document("hint"), Ok(&doc! { "status": 1 }));
+    }
+
+    #[test]
+    fn rejects_options_that_dont_fit_together() {
+        let primary_with_tags = QueryOptions {
+            read_preference: Some(ReadPreferenceMode::Primary),
+            read_preference_tags: "dc:east".to_string(),
+            ..Default::default()
+        };
+        assert!(primary_with_tags.selection_criteria().is_err());
+
+        let short_staleness = QueryOptions {
+            read_preference: Some(ReadPreferenceMode::Nearest),
+            max_staleness_seconds: Some(10),
+            ..Default::default()
+        };
+        assert!(short_staleness.selection_criteria().is_err());
+
+        let majority = QueryOptions {
+            write_concern_w: "majority".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(
+            majority
+                .write_concern()
+                .unwrap()
+                .and_then(|concern| concern.w),
+            Some(Acknowledgment::Majority)
+        );
+        assert_eq!(QueryOptions::default().write_concern(), Ok(None));
+    }
+}
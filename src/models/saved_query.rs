@@ -0,0 +1,115 @@
+use crate::models::QueryOptions;
+use crate::utils::error::{MongoLiteError, Result};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+pub const SAVED_QUERIES_FILE_NAME: &str = "saved_queries.json";
+
+/// A query tab's query as it was typed, with where it runs and its options.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SavedQuery {
+    pub name: String,
+    pub database: String,
+    pub collection: String,
+    pub filter: String,
+    pub projection: String,
+    pub sort: String,
+    #[serde(default)]
+    pub options: QueryOptions,
+}
+
+fn store_error(path: &Path, error: impl std::fmt::Display) -> MongoLiteError {
+    MongoLiteError::SavedQueryError(format!("{}: {}", path.display(), error))
+}
+
+/// Saved queries, kept as JSON in the data directory and sorted by name.
+pub struct SavedQueryStore {
+    path: PathBuf,
+    queries: Vec<SavedQuery>,
+}
+
+impl SavedQueryStore {
+    /// Reads the saved queries. A missing file means there are none yet.
+    pub fn open(data_dir: &Path) -> Result<Self> {
+        let path = data_dir.join(SAVED_QUERIES_FILE_NAME);
+        let queries = match fs::read_to_string(&path) {
+            Ok(contents) => serde_json::from_str(&contents).map_err(|e| store_error(&path, e))?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+            Err(e) => return Err(store_error(&path, e)),
+        };
+        Ok(Self { path, queries })
+    }
+
+    pub fn queries(&self) -> &[SavedQuery] {
+        &self.queries
+    }
+
+    /// Saves the query, replacing one with the same name.
+    pub fn save(&mut self, query: SavedQuery) -> Result<()> {
+        match self
+            .queries
+            .iter_mut()
+            .find(|saved| saved.name == query.name)
+        {
+            Some(saved) => *saved = query,
+            None => self.queries.push(query),
+        }
+        self.queries.sort_by(|a, b| a.name.cmp(&b.name));
+        self.write()
+    }
+
+    pub fn delete(&mut self, name: &str) -> Result<()> {
+        self.queries.retain(|saved| saved.name != name);
+        self.write()
+    }
+
+    fn write(&self) -> Result<()> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent).map_err(|e| store_error(parent, e))?;
+        }
+        let contents =
+            serde_json::to_string_pretty(&self.queries).map_err(|e| store_error(&self.path, e))?;
+        fs::write(&self.path, contents).map_err(|e| store_error(&self.path, e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{ReadConcernMode, ReadPreferenceMode};
+
+    #[test]
+    fn saves_queries_with_their_options() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut store = SavedQueryStore::open(dir.path()).unwrap();
+        assert!(store.queries().is_empty());
+
+        let query = SavedQuery {
+            name: "Open orders".to_string(),
+            database: "shop".to_string(),
+            collection: "orders".to_string(),
+            filter: r#"{ "status": "open" }"#.to_string(),
+            projection: String::new(),
+            sort: r#"{ "created": -1 }"#.to_string(),
+            options: QueryOptions {
+                read_preference: Some(ReadPreferenceMode::SecondaryPreferred),
+                read_concern: Some(ReadConcernMode::Majority),
+                max_time_ms: Some(2000),
+                ..Default::default()
+            },
+        };
+        store.save(query.clone()).unwrap();
+        store
+            .save(SavedQuery {
+                name: "All customers".to_string(),
+                ..query.clone()
+            })
+            .unwrap();
+
+        let store = SavedQueryStore::open(dir.path()).unwrap();
+        assert_eq!(store.queries().len(), 2);
+        assert_eq!(store.queries()[0].name, "All customers");
+        assert_eq!(store.queries()[1], query);
+    }
+}
@@ -0,0 +1,259 @@
+use super::database_stats::to_u64;
+use mongodb::bson::{DateTime, Document};
+use std::collections::VecDeque;
+
+/// The `serverStatus` figures the monitor charts. Counters are cumulative
+/// since the server started.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ServerStatusSample {
+    /// The server's clock when the sample was taken.
+    pub time: DateTime,
+    pub uptime_millis: u64,
+    pub opcounters: Opcounters,
+    pub connections_current: u64,
+    pub connections_available: u64,
+    pub network_bytes_in: u64,
+    pub network_bytes_out: u64,
+    /// WiredTiger cache figures; other storage engines don't report them.
+    pub cache_bytes: Option<u64>,
+    pub cache_max_bytes: Option<u64>,
+    pub queued_readers: u64,
+    pub queued_writers: u64,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Opcounters {
+    pub insert: u64,
+    pub query: u64,
+    pub update: u64,
+    pub delete: u64,
+    pub getmore: u64,
+    pub command: u64,
+}
+
+impl Opcounters {
+    pub const NAMES: [&'static str; 6] =
+        ["insert", "query", "update", "delete", "getmore", "command"];
+
+    pub fn values(&self) -> [u64; 6] {
+        [
+            self.insert,
+            self.query,
+            self.update,
+            self.delete,
+            self.getmore,
+            self.command,
+        ]
+    }
+}
+
+impl ServerStatusSample {
+    pub fn from_document(status: &Document) -> Self {
+        let opcounters = |key| number_at(status, &["opcounters", key]);
+        Self {
+            time: status
+                .get_datetime("localTime")
+                .copied()
+                .unwrap_or_else(|_| DateTime::now()),
+            uptime_millis: number_at(status, &["uptimeMillis"]),
+            opcounters: Opcounters {
+                insert: opcounters("insert"),
+                query: opcounters("query"),
+                update: opcounters("update"),
+                delete: opcounters("delete"),
+                getmore: opcounters("getmore"),
+                command: opcounters("command"),
+            },
+            connections_current: number_at(status, &["connections", "current"]),
+            connections_available: number_at(status, &["connections", "available"]),
+            network_bytes_in: number_at(status, &["network", "bytesIn"]),
+            network_bytes_out: number_at(status, &["network", "bytesOut"]),
+            cache_bytes: value_at(
+                status,
+                &["wiredTiger", "cache", "bytes currently in the cache"],
+            ),
+            cache_max_bytes: value_at(status, &["wiredTiger", "cache", "maximum bytes configured"]),
+            queued_readers: number_at(status, &["globalLock", "currentQueue", "readers"]),
+            queued_writers: number_at(status, &["globalLock", "currentQueue", "writers"]),
+        }
+    }
+}
+
+fn value_at(document: &Document, path: &[&str]) -> Option<u64> {
+    let (last, parents) = path.split_last()?;
+    let mut document = document;
+    for key in parents {
+        document = document.get_document(key).ok()?;
+    }
+    document.get(last).and_then(to_u64)
+}
+
+fn number_at(document: &Document, path: &[&str]) -> u64 {
+    value_at(document, path).unwrap_or(0)
+}
+
+/// What happened between two consecutive samples: counters turned into
+/// rates per second, gauges as of the later sample.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MonitorPoint {
+    pub time: DateTime,
+    /// Operations per second, in the order of [`Opcounters::NAMES`].
+    pub opcounters: [f64; 6],
+    pub connections_current: u64,
+    pub connections_available: u64,
+    pub network_in_per_second: f64,
+    pub network_out_per_second: f64,
+    pub cache_bytes: Option<u64>,
+    pub cache_max_bytes: Option<u64>,
+    pub queued_readers: u64,
+    pub queued_writers: u64,
+}
+
+/// Rolling history of the last `capacity` samples.
+pub struct ServerStatusHistory {
+    samples: VecDeque<ServerStatusSample>,
+    capacity: usize,
+}
+
+impl ServerStatusHistory {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            samples: VecDeque::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    pub fn push(&mut self, sample: ServerStatusSample) {
+        // The counters start over when the server restarts, so rates across
+        // the restart would be meaningless
+        if let Some(last) = self.samples.back() {
+            if sample.uptime_millis < last.uptime_millis {
+                self.samples.clear();
+            }
+        }
+        if self.samples.len() == self.capacity {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(sample);
+    }
+
+    pub fn clear(&mut self) {
+        self.samples.clear();
+    }
+
+    pub fn len(&self) -> usize {
+        self.samples.len()
+    }
+
+    /// One point per pair of consecutive samples, oldest first.
+    pub fn points(&self) -> Vec<MonitorPoint> {
+        self.samples
+            .iter()
+            .zip(self.samples.iter().skip(1))
+            .map(|(previous, sample)| {
+                let seconds = (sample.time.timestamp_millis() - previous.time.timestamp_millis())
+                    .max(1) as f64
+                    / 1000.0;
+                let rate = |now: u64, before: u64| now.saturating_sub(before) as f64 / seconds;
+                let now = sample.opcounters.values();
+                let before = previous.opcounters.values();
+                MonitorPoint {
+                    time: sample.time,
+                    opcounters: std::array::from_fn(|i| rate(now[i], before[i])),
+                    connections_current: sample.connections_current,
+                    connections_available: sample.connections_available,
+                    network_in_per_second: rate(sample.network_bytes_in, previous.network_bytes_in),
+                    network_out_per_second: rate(
+                        sample.network_bytes_out,
+                        previous.network_bytes_out,
+                    ),
+                    cache_bytes: sample.cache_bytes,
+                    cache_max_bytes: sample.cache_max_bytes,
+                    queued_readers: sample.queued_readers,
+                    queued_writers: sample.queued_writers,
+                }
+            })
+            .collect()
+    }
+
+    /// The history as CSV, one row per point.
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("time");
+        for name in Opcounters::NAMES {
+            csv.push_str(&format!(",{}_per_second", name));
+        }
+        csv.push_str(
+            ",connections_current,connections_available,network_in_bytes_per_second,\
+             network_out_bytes_per_second,cache_bytes,cache_max_bytes,queued_readers,\
+             queued_writers\n",
+        );
+        let optional =
+            |value: Option<u64>| value.map(|value| value.to_string()).unwrap_or_default();
+        for point in self.points() {
+            csv.push_str(&point.time.try_to_rfc3339_string().unwrap_or_default());
+            for rate in point.opcounters {
+                csv.push_str(&format!(",{:.2}", rate));
+            }
+            csv.push_str(&format!(
+                ",{},{},{:.0},{:.0},{},{},{},{}\n",
+                point.connections_current,
+                point.connections_available,
+                point.network_in_per_second,
+                point.network_out_per_second,
+                optional(point.cache_bytes),
+                optional(point.cache_max_bytes),
+                point.queued_readers,
+                point.queued_writers,
+            ));
+        }
+        csv
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mongodb::bson::doc;
+
+    fn status(millis: i64, inserts: i64, bytes_in: i64) -> Document {
+        doc! {
+            "localTime": DateTime::from_millis(millis),
+            "uptimeMillis": millis,
+            "opcounters": { "insert": inserts, "query": 10, "update": 0, "delete": 0, "getmore": 0, "command": 4 },
+            "connections": { "current": 3, "available": 816 },
+            "network": { "bytesIn": bytes_in, "bytesOut": 2048_i64 },
+            "globalLock": { "currentQueue": { "total": 1, "readers": 1, "writers": 0 } },
+        }
+    }
+
+    #[test]
+    fn turns_counters_into_rates() {
+        let mut history = ServerStatusHistory::new(2);
+        history.push(ServerStatusSample::from_document(&status(
+            1_000, 100, 1_000,
+        )));
+        history.push(ServerStatusSample::from_document(&status(
+            3_000, 150, 5_000,
+        )));
+        let points = history.points();
+        assert_eq!(points.len(), 1);
+        assert_eq!(points[0].opcounters[0], 25.0);
+        assert_eq!(points[0].opcounters[1], 0.0);
+        assert_eq!(points[0].network_in_per_second, 2_000.0);
+        assert_eq!(points[0].queued_readers, 1);
+        assert_eq!(points[0].cache_bytes, None);
+
+        // The oldest sample makes room, and a restart starts over
+        history.push(ServerStatusSample::from_document(&status(
+            5_000, 170, 6_000,
+        )));
+        assert_eq!(history.len(), 2);
+        assert_eq!(history.points()[0].opcounters[0], 10.0);
+        history.push(ServerStatusSample::from_document(&status(500, 0, 0)));
+        assert_eq!(history.len(), 1);
+
+        let csv = history.to_csv();
+        assert!(csv.starts_with("time,insert_per_second,"));
+        assert_eq!(csv.lines().count(), 1);
+    }
+}
@@ -0,0 +1,338 @@
+use super::database_stats::to_u64;
+use mongodb::bson::{Bson, Document};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TopologyKind {
+    Standalone,
+    ReplicaSet,
+    Sharded,
+}
+
+impl TopologyKind {
+    pub fn label(&self) -> &'static str {
+        match self {
+            TopologyKind::Standalone => "Standalone",
+            TopologyKind::ReplicaSet => "Replica set",
+            TopologyKind::Sharded => "Sharded cluster",
+        }
+    }
+}
+
+/// What kind of deployment a connection is to, from `hello`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Topology {
+    pub kind: TopologyKind,
+    pub set_name: Option<String>,
+    /// The replica set's primary, as the server last saw it.
+    pub primary: Option<String>,
+    /// The member or mongos the connection talks to.
+    pub host: Option<String>,
+}
+
+impl Topology {
+    pub fn from_hello(hello: &Document) -> Self {
+        let text = |key| hello.get_str(key).ok().map(str::to_string);
+        let kind = if hello.get_str("msg") == Ok("isdbgrid") {
+            TopologyKind::Sharded
+        } else if hello.contains_key("setName") {
+            TopologyKind::ReplicaSet
+        } else {
+            TopologyKind::Standalone
+        };
+        Self {
+            kind,
+            set_name: text("setName"),
+            primary: text("primary"),
+            host: text("me"),
+        }
+    }
+
+    /// A line for the status bar, e.g. `Replica set rs0 · primary db1:27017`.
+    pub fn summary(&self) -> String {
+        let mut summary = self.kind.label().to_string();
+        if let Some(set_name) = &self.set_name {
+            summary.push_str(&format!(" {}", set_name));
+        }
+        match (self.kind, &self.primary, &self.host) {
+            (TopologyKind::ReplicaSet, Some(primary), _) => {
+                summary.push_str(&format!(" · primary {}", primary))
+            }
+            (TopologyKind::ReplicaSet, None, _) => summary.push_str(" · no primary"),
+            (TopologyKind::Sharded, _, Some(host)) => summary.push_str(&format!(" · via {}", host)),
+            _ => {}
+        }
+        summary
+    }
+}
+
+/// A replica set member from `replSetGetStatus`, with its priority and
+/// votes from `replSetGetConfig`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReplicaSetMember {
+    pub name: String,
+    /// `PRIMARY`, `SECONDARY`, `ARBITER`, ...
+    pub state: String,
+    pub healthy: bool,
+    /// How far the member's last applied operation is behind the primary's,
+    /// when both are known.
+    pub lag_seconds: Option<i64>,
+    pub priority: f64,
+    pub votes: u64,
+    pub hidden: bool,
+    /// Whether this is the member the connection talks to.
+    pub is_self: bool,
+}
+
+impl ReplicaSetMember {
+    pub fn is_primary(&self) -> bool {
+        self.state == "PRIMARY"
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReplicaSetStatus {
+    pub set_name: String,
+    pub members: Vec<ReplicaSetMember>,
+}
+
+impl ReplicaSetStatus {
+    pub fn from_documents(status: &Document, config: &Document) -> Self {
+        let documents = |document: &Document, key| -> Vec<Document> {
+            document
+                .get_array(key)
+                .map(|values| {
+                    values
+                        .iter()
+                        .filter_map(Bson::as_document)
+                        .cloned()
+                        .collect()
+                })
+                .unwrap_or_default()
+        };
+        let statuses = documents(status, "members");
+        // The reply of `replSetGetConfig` has the config in `config`
+        let configs = documents(config.get_document("config").unwrap_or(config), "members");
+
+        let optime = |member: &Document| member.get_datetime("optimeDate").ok().copied();
+        let primary_optime = statuses
+            .iter()
+            .find(|member| member.get_str("stateStr") == Ok("PRIMARY"))
+            .and_then(optime);
+
+        let members = statuses
+            .iter()
+            .map(|member| {
+                let name = member.get_str("name").unwrap_or_default().to_string();
+                let config = configs
+                    .iter()
+                    .find(|config| config.get_str("host") == Ok(name.as_str()));
+                let lag_seconds = match (primary_optime, optime(member)) {
+                    (Some(primary), Some(optime))
+                        if member.get_str("stateStr") != Ok("ARBITER") =>
+                    {
+                        Some((primary.timestamp_millis() - optime.timestamp_millis()).max(0) / 1000)
+                    }
+                    _ => None,
+                };
+                ReplicaSetMember {
+                    state: member.get_str("stateStr").unwrap_or_default().to_string(),
+                    healthy: member.get("health").and_then(to_u64) == Some(1),
+                    lag_seconds,
+                    priority: config
+                        .and_then(|config| match config.get("priority") {
+                            Some(Bson::Double(priority)) => Some(*priority),
+                            Some(priority) => to_u64(priority).map(|priority| priority as f64),
+                            None => None,
+                        })
+                        .unwrap_or(1.0),
+                    votes: config
+                        .and_then(|config| config.get("votes"))
+                        .and_then(to_u64)
+                        .unwrap_or(1),
+                    hidden: config
+                        .and_then(|config| config.get_bool("hidden").ok())
+                        .unwrap_or(false),
+                    is_self: member.get_bool("self").unwrap_or(false),
+                    name,
+                }
+            })
+            .collect();
+
+        Self {
+            set_name: status.get_str("set").unwrap_or_default().to_string(),
+            members,
+        }
+    }
+}
+
+/// A shard from `listShards`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ShardInfo {
+    pub id: String,
+    /// `replicaSet/host1,host2` for replica set shards.
+    pub host: String,
+    pub draining: bool,
+}
+
+impl ShardInfo {
+    pub fn from_document(shard: &Document) -> Self {
+        Self {
+            id: shard.get_str("_id").unwrap_or_default().to_string(),
+            host: shard.get_str("host").unwrap_or_default().to_string(),
+            draining: shard.get_bool("draining").unwrap_or(false),
+        }
+    }
+}
+
+/// The balancer's state, from `balancerStatus`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BalancerStatus {
+    /// `full` or `off`.
+    pub mode: String,
+    pub in_round: bool,
+}
+
+impl BalancerStatus {
+    pub fn from_document(reply: &Document) -> Self {
+        Self {
+            mode: reply.get_str("mode").unwrap_or_default().to_string(),
+            in_round: reply.get_bool("inBalancerRound").unwrap_or(false),
+        }
+    }
+
+    pub fn enabled(&self) -> bool {
+        self.mode != "off"
+    }
+}
+
+/// How a sharded collection's chunks are spread over the shards.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChunkDistribution {
+    pub namespace: String,
+    /// Chunk counts by shard, in shard order.
+    pub shards: Vec<(String, u64)>,
+}
+
+impl ChunkDistribution {
+    pub fn total(&self) -> u64 {
+        self.shards.iter().map(|(_, chunks)| chunks).sum()
+    }
+}
+
+/// Builds the distributions from `config.chunks` grouped by
+/// `{ ns, uuid, shard }`. Chunks name their collection by `uuid` since
+/// MongoDB 5.0, so those are matched against `config.collections`.
+pub fn chunk_distribution(
+    chunk_counts: &[Document],
+    collections: &[Document],
+) -> Vec<ChunkDistribution> {
+    let mut distributions: Vec<ChunkDistribution> = Vec::new();
+    for count in chunk_counts {
+        let Ok(group) = count.get_document("_id") else {
+            continue;
+        };
+        let namespace = match group.get_str("ns") {
+            Ok(namespace) => namespace.to_string(),
+            Err(_) => {
+                let uuid = group.get("uuid");
+                match collections
+                    .iter()
+                    .find(|collection| uuid.is_some() && collection.get("uuid") == uuid)
+                    .and_then(|collection| collection.get_str("_id").ok())
+                {
+                    Some(namespace) => namespace.to_string(),
+                    None => continue,
+                }
+            }
+        };
+        let shard = group.get_str("shard").unwrap_or_default().to_string();
+        let chunks = count.get("chunks").and_then(to_u64).unwrap_or(0);
+
+        match distributions
+            .iter_mut()
+            .find(|distribution| distribution.namespace == namespace)
+        {
+            Some(distribution) => distribution.shards.push((shard, chunks)),
+            None => distributions.push(ChunkDistribution {
+                namespace,
+                shards: vec![(shard, chunks)],
+            }),
+        }
+    }
+    for distribution in &mut distributions {
+        distribution.shards.sort();
+    }
+    distributions.sort_by(|a, b| a.namespace.cmp(&b.namespace));
+    distributions
+}
+
+/// A sharded cluster as seen from the mongos the connection talks to.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ShardedClusterStatus {
+    pub mongos: String,
+    pub shards: Vec<ShardInfo>,
+    pub balancer: BalancerStatus,
+    pub chunks: Vec<ChunkDistribution>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mongodb::bson::{doc, spec::BinarySubtype, Binary, DateTime};
+
+    #[test]
+    fn reads_replica_set_members() {
+        let status = doc! {
+            "set": "rs0",
+            "members": [
+                { "name": "db1:27017", "health": 1.0, "stateStr": "PRIMARY",
+                  "optimeDate": DateTime::from_millis(100_000), "self": true },
+                { "name": "db2:27017", "health": 1.0, "stateStr": "SECONDARY",
+                  "optimeDate": DateTime::from_millis(88_000) },
+                { "name": "db3:27017", "health": 0.0, "stateStr": "(not reachable/healthy)" },
+            ],
+        };
+        let config = doc! { "config": { "members": [
+            { "host": "db1:27017", "priority": 2.0, "votes": 1 },
+            { "host": "db2:27017", "priority": 1, "votes": 1 },
+            { "host": "db3:27017", "priority": 0.0, "votes": 0, "hidden": true },
+        ] } };
+        let replica_set = ReplicaSetStatus::from_documents(&status, &config);
+        assert_eq!(replica_set.set_name, "rs0");
+        let members = &replica_set.members;
+        assert!(members[0].is_primary() && members[0].is_self);
+        assert_eq!(members[0].priority, 2.0);
+        assert_eq!(members[1].lag_seconds, Some(12));
+        assert_eq!(members[1].priority, 1.0);
+        assert!(!members[2].healthy && members[2].hidden);
+        assert_eq!(members[2].lag_seconds, None);
+
+        let topology = Topology::from_hello(&doc! {
+            "setName": "rs0", "primary": "db1:27017", "me": "db2:27017",
+        });
+        assert_eq!(topology.summary(), "Replica set rs0 · primary db1:27017");
+    }
+
+    #[test]
+    fn groups_chunks_by_collection() {
+        let uuid = Bson::Binary(Binary {
+            subtype: BinarySubtype::Uuid,
+            bytes: vec![7; 16],
+        });
+        let distribution = chunk_distribution(
+            &[
+                doc! { "_id": { "uuid": uuid.clone(), "shard": "shard02" }, "chunks": 3 },
+                doc! { "_id": { "uuid": uuid.clone(), "shard": "shard01" }, "chunks": 5 },
+                doc! { "_id": { "ns": "old.events", "shard": "shard01" }, "chunks": 1 },
+            ],
+            &[doc! { "_id": "shop.orders", "uuid": uuid }],
+        );
+        assert_eq!(distribution.len(), 2);
+        assert_eq!(distribution[0].namespace, "old.events");
+        assert_eq!(
+            distribution[1].shards,
+            vec![("shard01".to_string(), 5), ("shard02".to_string(), 3)]
+        );
+        assert_eq!(distribution[1].total(), 8);
+    }
+}
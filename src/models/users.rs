@@ -0,0 +1,265 @@
+use mongodb::bson::{doc, Bson, Document};
+use std::fmt;
+
+/// A role by name and the database it is defined in.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RoleRef {
+    pub role: String,
+    pub db: String,
+}
+
+impl RoleRef {
+    pub fn from_document(role: &Document) -> Option<Self> {
+        Some(Self {
+            role: role.get_str("role").ok()?.to_string(),
+            db: role.get_str("db").ok()?.to_string(),
+        })
+    }
+
+    pub fn to_document(&self) -> Document {
+        doc! { "role": &self.role, "db": &self.db }
+    }
+}
+
+impl fmt::Display for RoleRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}@{}", self.role, self.db)
+    }
+}
+
+/// Parses a comma-separated list like `readWrite, read@reporting`. Roles
+/// without a database are in `default_db`.
+pub fn parse_role_list(text: &str, default_db: &str) -> Result<Vec<RoleRef>, String> {
+    text.split(',')
+        .map(str::trim)
+        .filter(|role| !role.is_empty())
+        .map(|role| {
+            let (role, db) = role.split_once('@').unwrap_or((role, default_db));
+            let (role, db) = (role.trim(), db.trim());
+            if role.is_empty() || db.is_empty() {
+                return Err(format!("'{}' is not a role, or role@database", role));
+            }
+            Ok(RoleRef {
+                role: role.to_string(),
+                db: db.to_string(),
+            })
+        })
+        .collect()
+}
+
+fn role_refs(document: &Document, key: &str) -> Vec<RoleRef> {
+    document
+        .get_array(key)
+        .map(|roles| {
+            roles
+                .iter()
+                .filter_map(Bson::as_document)
+                .filter_map(RoleRef::from_document)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// A user from `usersInfo`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct UserInfo {
+    pub user: String,
+    pub db: String,
+    pub roles: Vec<RoleRef>,
+    pub mechanisms: Vec<String>,
+}
+
+impl UserInfo {
+    pub fn from_document(user: &Document) -> Self {
+        Self {
+            user: user.get_str("user").unwrap_or_default().to_string(),
+            db: user.get_str("db").unwrap_or_default().to_string(),
+            roles: role_refs(user, "roles"),
+            mechanisms: user
+                .get_array("mechanisms")
+                .map(|mechanisms| {
+                    mechanisms
+                        .iter()
+                        .filter_map(|mechanism| mechanism.as_str().map(str::to_string))
+                        .collect()
+                })
+                .unwrap_or_default(),
+        }
+    }
+}
+
+/// What a privilege applies to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Resource {
+    /// A collection, every collection of a database when `collection` is
+    /// empty, or of every database when `db` is empty too.
+    Namespace {
+        db: String,
+        collection: String,
+    },
+    Cluster,
+    Any,
+}
+
+impl Resource {
+    pub fn from_document(resource: &Document) -> Self {
+        if resource.get_bool("cluster").unwrap_or(false) {
+            Resource::Cluster
+        } else if resource.get_bool("anyResource").unwrap_or(false) {
+            Resource::Any
+        } else {
+            Resource::Namespace {
+                db: resource.get_str("db").unwrap_or_default().to_string(),
+                collection: resource
+                    .get_str("collection")
+                    .unwrap_or_default()
+                    .to_string(),
+            }
+        }
+    }
+
+    pub fn to_document(&self) -> Document {
+        match self {
+            Resource::Namespace { db, collection } => doc! { "db": db, "collection": collection },
+            Resource::Cluster => doc! { "cluster": true },
+            Resource::Any => doc! { "anyResource": true },
+        }
+    }
+}
+
+impl fmt::Display for Resource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Resource::Namespace { db, collection } => {
+                let db = if db.is_empty() { "*" } else { db };
+                let collection = if collection.is_empty() {
+                    "*"
+                } else {
+                    collection
+                };
+                write!(f, "{}.{}", db, collection)
+            }
+            Resource::Cluster => write!(f, "cluster"),
+            Resource::Any => write!(f, "any resource"),
+        }
+    }
+}
+
+/// Actions allowed on a resource, e.g. `find` and `insert` on `shop.orders`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Privilege {
+    pub resource: Resource,
+    pub actions: Vec<String>,
+}
+
+impl Privilege {
+    pub fn from_document(privilege: &Document) -> Self {
+        Self {
+            resource: privilege
+                .get_document("resource")
+                .map(Resource::from_document)
+                .unwrap_or(Resource::Any),
+            actions: privilege
+                .get_array("actions")
+                .map(|actions| {
+                    actions
+                        .iter()
+                        .filter_map(|action| action.as_str().map(str::to_string))
+                        .collect()
+                })
+                .unwrap_or_default(),
+        }
+    }
+
+    pub fn to_document(&self) -> Document {
+        doc! { "resource": self.resource.to_document(), "actions": &self.actions }
+    }
+}
+
+fn privileges(document: &Document, key: &str) -> Vec<Privilege> {
+    document
+        .get_array(key)
+        .map(|privileges| {
+            privileges
+                .iter()
+                .filter_map(Bson::as_document)
+                .map(Privilege::from_document)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// A role from `rolesInfo` with its privileges shown.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RoleInfo {
+    pub role: String,
+    pub db: String,
+    pub built_in: bool,
+    /// The roles this one inherits from.
+    pub roles: Vec<RoleRef>,
+    /// Granted by the role itself.
+    pub privileges: Vec<Privilege>,
+    /// Granted by the role and everything it inherits.
+    pub inherited_privileges: Vec<Privilege>,
+}
+
+impl RoleInfo {
+    pub fn from_document(role: &Document) -> Self {
+        Self {
+            role: role.get_str("role").unwrap_or_default().to_string(),
+            db: role.get_str("db").unwrap_or_default().to_string(),
+            built_in: role.get_bool("isBuiltin").unwrap_or(false),
+            roles: role_refs(role, "roles"),
+            privileges: privileges(role, "privileges"),
+            inherited_privileges: privileges(role, "inheritedPrivileges"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_role_lists() {
+        assert_eq!(
+            parse_role_list("readWrite, read@reporting,", "shop"),
+            Ok(vec![
+                RoleRef {
+                    role: "readWrite".to_string(),
+                    db: "shop".to_string()
+                },
+                RoleRef {
+                    role: "read".to_string(),
+                    db: "reporting".to_string()
+                },
+            ])
+        );
+        assert!(parse_role_list("read@", "shop").is_err());
+    }
+
+    #[test]
+    fn reads_roles_with_privileges() {
+        let role = RoleInfo::from_document(&doc! {
+            "role": "orderClerk",
+            "db": "shop",
+            "isBuiltin": false,
+            "roles": [{ "role": "read", "db": "shop" }],
+            "privileges": [
+                { "resource": { "db": "shop", "collection": "orders" }, "actions": ["insert", "update"] },
+            ],
+            "inheritedPrivileges": [
+                { "resource": { "db": "shop", "collection": "" }, "actions": ["find"] },
+                { "resource": { "cluster": true }, "actions": ["serverStatus"] },
+            ],
+        });
+        assert_eq!(role.roles[0].to_string(), "read@shop");
+        assert_eq!(role.privileges[0].resource.to_string(), "shop.orders");
+        assert_eq!(role.inherited_privileges[0].resource.to_string(), "shop.*");
+        assert_eq!(role.inherited_privileges[1].resource, Resource::Cluster);
+        assert_eq!(
+            role.privileges[0].to_document(),
+            doc! { "resource": { "db": "shop", "collection": "orders" }, "actions": ["insert", "update"] }
+        );
+    }
+}
@@ -0,0 +1,291 @@
+use mongodb::bson::{doc, Bson, Document};
+use std::collections::BTreeSet;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ValidationLevel {
+    Off,
+    Strict,
+    Moderate,
+}
+
+impl ValidationLevel {
+    pub const ALL: [ValidationLevel; 3] = [
+        ValidationLevel::Strict,
+        ValidationLevel::Moderate,
+        ValidationLevel::Off,
+    ];
+
+    pub fn parse(value: &str) -> Self {
+        match value {
+            "off" => ValidationLevel::Off,
+            "moderate" => ValidationLevel::Moderate,
+            _ => ValidationLevel::Strict,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ValidationLevel::Off => "off",
+            ValidationLevel::Strict => "strict",
+            ValidationLevel::Moderate => "moderate",
+        }
+    }
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            ValidationLevel::Off => "No validation",
+            ValidationLevel::Strict => "Validate all inserts and updates",
+            ValidationLevel::Moderate => "Leave updates to already invalid documents alone",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ValidationAction {
+    Error,
+    Warn,
+}
+
+impl ValidationAction {
+    pub const ALL: [ValidationAction; 2] = [ValidationAction::Error, ValidationAction::Warn];
+
+    pub fn parse(value: &str) -> Self {
+        match value {
+            "warn" => ValidationAction::Warn,
+            _ => ValidationAction::Error,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ValidationAction::Error => "error",
+            ValidationAction::Warn => "warn",
+        }
+    }
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            ValidationAction::Error => "Reject invalid documents",
+            ValidationAction::Warn => "Accept them and log a warning",
+        }
+    }
+}
+
+/// A collection's validation settings, as `collMod` takes them.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ValidationRules {
+    /// Empty when the collection has no validator.
+    pub validator: Document,
+    pub level: ValidationLevel,
+    pub action: ValidationAction,
+}
+
+impl ValidationRules {
+    /// Reads the settings from the collection's `listCollections` options.
+    pub fn from_options(options: &Document) -> Self {
+        Self {
+            validator: options
+                .get_document("validator")
+                .cloned()
+                .unwrap_or_default(),
+            level: ValidationLevel::parse(options.get_str("validationLevel").unwrap_or_default()),
+            action: ValidationAction::parse(
+                options.get_str("validationAction").unwrap_or_default(),
+            ),
+        }
+    }
+
+    pub fn coll_mod(&self, collection: &str) -> Document {
+        doc! {
+            "collMod": collection,
+            "validator": self.validator.clone(),
+            "validationLevel": self.level.as_str(),
+            "validationAction": self.action.as_str(),
+        }
+    }
+}
+
+/// Documents that don't match a validator.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ValidationCheck {
+    /// How many documents were sampled, or `None` when the whole collection
+    /// was checked.
+    pub sampled: Option<u64>,
+    pub failing: u64,
+    /// The first of the failing documents.
+    pub examples: Vec<Document>,
+}
+
+/// A starter `$jsonSchema` validator describing the sampled documents:
+/// each field's BSON types, nested objects and array items, with the fields
+/// present in every sample marked required.
+pub fn generate_json_schema(samples: &[Document]) -> Document {
+    let mut shape = ObjectShape::default();
+    for sample in samples {
+        shape.add(sample);
+    }
+    doc! { "$jsonSchema": shape.schema() }
+}
+
+#[derive(Default)]
+struct ObjectShape {
+    documents: usize,
+    /// In the order the fields were first seen.
+    fields: Vec<(String, FieldShape)>,
+}
+
+#[derive(Default)]
+struct FieldShape {
+    count: usize,
+    types: BTreeSet<&'static str>,
+    object: Option<Box<ObjectShape>>,
+    items: Option<Box<FieldShape>>,
+}
+
+impl ObjectShape {
+    fn add(&mut self, document: &Document) {
+        self.documents += 1;
+        for (key, value) in document {
+            let position = match self.fields.iter().position(|(name, _)| name == key) {
+                Some(position) => position,
+                None => {
+                    self.fields.push((key.clone(), FieldShape::default()));
+                    self.fields.len() - 1
+                }
+            };
+            self.fields[position].1.add(value);
+        }
+    }
+
+    fn schema(&self) -> Document {
+        let mut properties = Document::new();
+        let mut required = Vec::new();
+        for (name, field) in &self.fields {
+            properties.insert(name.clone(), field.schema());
+            if field.count == self.documents {
+                required.push(Bson::String(name.clone()));
+            }
+        }
+        let mut schema = doc! { "bsonType": "object" };
+        if !required.is_empty() {
+            schema.insert("required", required);
+        }
+        schema.insert("properties", properties);
+        schema
+    }
+}
+
+impl FieldShape {
+    fn add(&mut self, value: &Bson) {
+        self.count += 1;
+        self.types.insert(bson_type(value));
+        match value {
+            Bson::Document(document) => self
+                .object
+                .get_or_insert_with(Default::default)
+                .add(document),
+            Bson::Array(values) => {
+                let items = self.items.get_or_insert_with(Default::default);
+                for value in values {
+                    items.add(value);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn schema(&self) -> Document {
+        let mut schema = match self.types.len() {
+            1 => doc! { "bsonType": self.types.first().copied().unwrap_or("null") },
+            _ => doc! { "bsonType": self.types.iter().copied().collect::<Vec<_>>() },
+        };
+        if let Some(object) = &self.object {
+            for (key, value) in object.schema() {
+                if key != "bsonType" {
+                    schema.insert(key, value);
+                }
+            }
+        }
+        if let Some(items) = self.items.as_ref().filter(|items| items.count > 0) {
+            schema.insert("items", items.schema());
+        }
+        schema
+    }
+}
+
+/// The `$jsonSchema` `bsonType` alias of a value.
+fn bson_type(value: &Bson) -> &'static str {
+    match value {
+        Bson::Double(_) => "double",
+        Bson::String(_) | Bson::Symbol(_) => "string",
+        Bson::Array(_) => "array",
+        Bson::Document(_) => "object",
+        Bson::Boolean(_) => "bool",
+        Bson::Null | Bson::Undefined => "null",
+        Bson::RegularExpression(_) => "regex",
+        Bson::JavaScriptCode(_) => "javascript",
+        Bson::JavaScriptCodeWithScope(_) => "javascriptWithScope",
+        Bson::Int32(_) => "int",
+        Bson::Int64(_) => "long",
+        Bson::Timestamp(_) => "timestamp",
+        Bson::Binary(_) => "binData",
+        Bson::ObjectId(_) => "objectId",
+        Bson::DateTime(_) => "date",
+        Bson::Decimal128(_) => "decimal",
+        Bson::MaxKey => "maxKey",
+        Bson::MinKey => "minKey",
+        Bson::DbPointer(_) => "dbPointer",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn generates_a_schema_from_samples() {
+        let schema = generate_json_schema(&[
+            doc! { "name": "Ada", "age": 36, "tags": ["a", "b"], "address": { "city": "London" } },
+            doc! { "name": "Alan", "age": 41.5, "address": { "city": "Wilmslow", "zip": "SK9" } },
+        ]);
+        assert_eq!(
+            schema,
+            doc! { "$jsonSchema": {
+                "bsonType": "object",
+                "required": ["name", "age", "address"],
+                "properties": {
+                    "name": { "bsonType": "string" },
+                    "age": { "bsonType": ["double", "int"] },
+                    "tags": { "bsonType": "array", "items": { "bsonType": "string" } },
+                    "address": {
+                        "bsonType": "object",
+                        "required": ["city"],
+                        "properties": {
+                            "city": { "bsonType": "string" },
+                            "zip": { "bsonType": "string" },
+                        },
+                    },
+                },
+            } }
+        );
+    }
+
+    #[test]
+    fn reads_validation_options() {
+        let rules = ValidationRules::from_options(&doc! {
+            "validator": { "age": { "$gte": 0 } },
+            "validationLevel": "moderate",
+            "validationAction": "warn",
+        });
+        assert_eq!(rules.level, ValidationLevel::Moderate);
+        assert_eq!(rules.action, ValidationAction::Warn);
+        assert_eq!(
+            rules.coll_mod("people").get_str("validationLevel"),
+            Ok("moderate")
+        );
+
+        let none = ValidationRules::from_options(&Document::new());
+        assert!(none.validator.is_empty());
+        assert_eq!(none.level, ValidationLevel::Strict);
+    }
+}
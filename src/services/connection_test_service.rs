@@ -0,0 +1,408 @@
+use crate::models::{ConnectionSettings, SshTunnelSettings};
+use crate::services::route_through_tunnel;
+use crate::utils::connection_string::resolve_placeholders;
+use crate::utils::tls;
+use mongodb::bson::{doc, Document};
+use mongodb::error::{Error as DriverError, ErrorKind};
+use mongodb::options::{ClientOptions, ConnectionString, Credential, ServerAddress, Tls};
+use mongodb::Client;
+use std::future::Future;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::net::TcpStream;
+use tokio::time::timeout;
+use tokio_rustls::rustls::ServerName;
+use tokio_rustls::TlsConnector;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TestStep {
+    ParseUri,
+    SshTunnel,
+    ResolveDns,
+    TcpConnect,
+    TlsHandshake,
+    ServerSelection,
+    Authenticate,
+    Hello,
+    ListDatabases,
+}
+
+impl TestStep {
+    pub const ALL: [TestStep; 9] = [
+        TestStep::ParseUri,
+        TestStep::SshTunnel,
+        TestStep::ResolveDns,
+        TestStep::TcpConnect,
+        TestStep::TlsHandshake,
+        TestStep::ServerSelection,
+        TestStep::Authenticate,
+        TestStep::Hello,
+        TestStep::ListDatabases,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            TestStep::ParseUri => "Parse connection string",
+            TestStep::SshTunnel => "SSH tunnel",
+            TestStep::ResolveDns => "DNS / SRV resolution",
+            TestStep::TcpConnect => "TCP connect",
+            TestStep::TlsHandshake => "TLS handshake",
+            TestStep::ServerSelection => "Server selection",
+            TestStep::Authenticate => "Authentication",
+            TestStep::Hello => "hello",
+            TestStep::ListDatabases => "List databases permission",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StepOutcome {
+    Passed,
+    Failed,
+    Skipped,
+}
+
+#[derive(Clone, Debug)]
+pub struct StepResult {
+    pub step: TestStep,
+    pub outcome: StepOutcome,
+    pub detail: String,
+    /// Server error code, when the failure came back from the server.
+    pub error_code: Option<i32>,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct ConnectionTestReport {
+    pub steps: Vec<StepResult>,
+}
+
+impl ConnectionTestReport {
+    pub fn result(&self, step: TestStep) -> Option<&StepResult> {
+        self.steps.iter().find(|result| result.step == step)
+    }
+
+    pub fn failed_step(&self) -> Option<&StepResult> {
+        self.steps
+            .iter()
+            .find(|result| result.outcome == StepOutcome::Failed)
+    }
+
+    fn pass(&mut self, step: TestStep, detail: impl Into<String>) {
+        self.push(step, StepOutcome::Passed, detail.into(), None);
+    }
+
+    fn skip(&mut self, step: TestStep, detail: impl Into<String>) {
+        self.push(step, StepOutcome::Skipped, detail.into(), None);
+    }
+
+    fn fail(&mut self, step: TestStep, detail: impl Into<String>) -> Self {
+        self.push(step, StepOutcome::Failed, detail.into(), None);
+        self.clone()
+    }
+
+    fn fail_with(&mut self, step: TestStep, error: &DriverError) -> Self {
+        let (error_code, detail) = describe_error(error);
+        self.push(step, StepOutcome::Failed, detail, error_code);
+        self.clone()
+    }
+
+    fn push(
+        &mut self,
+        step: TestStep,
+        outcome: StepOutcome,
+        detail: String,
+        error_code: Option<i32>,
+    ) {
+        self.steps.push(StepResult {
+            step,
+            outcome,
+            detail,
+            error_code,
+        });
+    }
+}
+
+/// Splits a driver error into the server's error code, if any, and a message.
+fn describe_error(error: &DriverError) -> (Option<i32>, String) {
+    match error.kind.as_ref() {
+        ErrorKind::Command(command_error) => (
+            Some(command_error.code),
+            format!("{}: {}", command_error.code_name, command_error.message),
+        ),
+        ErrorKind::Authentication { message, .. } | ErrorKind::ServerSelection { message, .. } => {
+            (None, message.clone())
+        }
+        _ => (None, error.kind.to_string()),
+    }
+}
+
+async fn with_timeout<T, F>(limit: Duration, future: F) -> Result<T, String>
+where
+    F: Future<Output = Result<T, String>>,
+{
+    timeout(limit, future)
+        .await
+        .unwrap_or_else(|_| Err(format!("timed out after {}s", limit.as_secs())))
+}
+
+/// The step a failed first command is reported under: the driver only
+/// authenticates once it has selected a server, so a selection failure means
+/// the server was never reached.
+fn first_command_step(error: &DriverError) -> TestStep {
+    match error.kind.as_ref() {
+        ErrorKind::ServerSelection { .. } => TestStep::ServerSelection,
+        _ => TestStep::Authenticate,
+    }
+}
+
+/// Checks a connection string one layer at a time so that a failure can be
+/// pinned on the network, TLS, credentials or permissions. The checks go
+/// through the same SSH tunnel and password as `DatabaseService::connect`.
+pub struct ConnectionTestService;
+
+impl ConnectionTestService {
+    pub fn new() -> Self {
+        Self
+    }
+
+    pub async fn run(
+        &self,
+        connection_string: &str,
+        password: Option<String>,
+        ssh_tunnel: Option<&SshTunnelSettings>,
+        step_timeout: Duration,
+    ) -> ConnectionTestReport {
+        let mut report = ConnectionTestReport::default();
+
+        // Parse
+        let resolved = match resolve_placeholders(connection_string) {
+            Ok(resolved) => resolved,
+            Err(e) => return report.fail(TestStep::ParseUri, e.to_string()),
+        };
+        let connection_string = resolved.as_str();
+        let mut settings = match ConnectionSettings::from_uri(connection_string) {
+            Ok(settings) => settings,
+            Err(e) => return report.fail(TestStep::ParseUri, e.to_string()),
+        };
+        if let Err(e) = ConnectionString::parse(tls::driver_uri(&settings)) {
+            return report.fail_with(TestStep::ParseUri, &e);
+        }
+        report.pass(TestStep::ParseUri, "connection string is valid");
+
+        // SSH tunnel: the later steps run against its local end, and it is
+        // closed when `_tunnel` is dropped, however the test ends
+        let _tunnel = match ssh_tunnel {
+            Some(ssh_tunnel) => match route_through_tunnel(&mut settings, ssh_tunnel).await {
+                Ok(tunnel) => {
+                    report.pass(
+                        TestStep::SshTunnel,
+                        format!(
+                            "forwarding {} through {}",
+                            tunnel.local_address(),
+                            ssh_tunnel.host.trim()
+                        ),
+                    );
+                    Some(tunnel)
+                }
+                Err(e) => return report.fail(TestStep::SshTunnel, e.to_string()),
+            },
+            None => {
+                report.skip(TestStep::SshTunnel, "no SSH tunnel configured");
+                None
+            }
+        };
+
+        // DNS / SRV: the driver resolves SRV records while building options
+        let driver_uri = tls::driver_uri(&settings);
+        let mut options = match timeout(step_timeout, ClientOptions::parse_async(&driver_uri)).await
+        {
+            Ok(Ok(options)) => options,
+            Ok(Err(e)) => return report.fail_with(TestStep::ResolveDns, &e),
+            Err(_) => {
+                return report.fail(
+                    TestStep::ResolveDns,
+                    format!("SRV lookup timed out after {}s", step_timeout.as_secs()),
+                )
+            }
+        };
+        let mut resolved: Vec<(String, Vec<SocketAddr>)> = Vec::new();
+        let mut unresolved = Vec::new();
+        for address in &options.hosts {
+            let (host, port) = match address {
+                ServerAddress::Tcp { host, port } => (host.clone(), port.unwrap_or(27017)),
+                other => {
+                    unresolved.push(format!("{}: unsupported address type", other));
+                    continue;
+                }
+            };
+            let lookup = with_timeout(step_timeout, async {
+                tokio::net::lookup_host((host.as_str(), port))
+                    .await
+                    .map(|addresses| addresses.collect::<Vec<_>>())
+                    .map_err(|e| e.to_string())
+            })
+            .await;
+            match lookup {
+                Ok(addresses) if !addresses.is_empty() => resolved.push((host, addresses)),
+                Ok(_) => unresolved.push(format!("{}: no addresses", host)),
+                Err(e) => unresolved.push(format!("{}: {}", host, e)),
+            }
+        }
+        if resolved.is_empty() {
+            return report.fail(TestStep::ResolveDns, unresolved.join("; "));
+        }
+        let mut detail = format!(
+            "{} of {} host(s) resolved{}",
+            resolved.len(),
+            options.hosts.len(),
+            if settings.srv { " via SRV" } else { "" }
+        );
+        if !unresolved.is_empty() {
+            detail.push_str(&format!(" (failed: {})", unresolved.join("; ")));
+        }
+        report.pass(TestStep::ResolveDns, detail);
+
+        // TCP: the first host that accepts a connection is used for TLS
+        let mut connected = None;
+        let mut tcp_errors = Vec::new();
+        'hosts: for (host, addresses) in &resolved {
+            for address in addresses {
+                match with_timeout(step_timeout, async {
+                    TcpStream::connect(address).await.map_err(|e| e.to_string())
+                })
+                .await
+                {
+                    Ok(stream) => {
+                        connected = Some((host.clone(), *address, stream));
+                        break 'hosts;
+                    }
+                    Err(e) => tcp_errors.push(format!("{}: {}", address, e)),
+                }
+            }
+        }
+        let (host, address, stream) = match connected {
+            Some(connection) => connection,
+            None => return report.fail(TestStep::TcpConnect, tcp_errors.join("; ")),
+        };
+        report.pass(
+            TestStep::TcpConnect,
+            format!("connected to {} ({})", host, address),
+        );
+
+        // TLS
+        match &options.tls {
+            Some(Tls::Enabled(_)) => {
+                let config = match tls::client_config(&settings.tls) {
+                    Ok(config) => config,
+                    Err(e) => return report.fail(TestStep::TlsHandshake, e.to_string()),
+                };
+                let server_name = match ServerName::try_from(host.as_str()) {
+                    Ok(name) => name,
+                    Err(e) => return report.fail(TestStep::TlsHandshake, e.to_string()),
+                };
+                let connector = TlsConnector::from(Arc::new(config));
+                match with_timeout(step_timeout, async {
+                    connector
+                        .connect(server_name, stream)
+                        .await
+                        .map_err(|e| e.to_string())
+                })
+                .await
+                {
+                    Ok(_) => report.pass(TestStep::TlsHandshake, "handshake completed"),
+                    Err(e) => return report.fail(TestStep::TlsHandshake, e),
+                }
+            }
+            _ => report.skip(TestStep::TlsHandshake, "TLS is not enabled"),
+        }
+
+        // The remaining steps go through the driver
+        options.connect_timeout = Some(step_timeout);
+        options.server_selection_timeout = Some(step_timeout);
+        if let Some(password) = password {
+            options
+                .credential
+                .get_or_insert_with(Credential::default)
+                .password = Some(password);
+        }
+        let auth_source = options.credential.as_ref().map(|credential| {
+            credential
+                .source
+                .clone()
+                .unwrap_or_else(|| "admin".to_string())
+        });
+        let client = match &options.tls {
+            Some(Tls::Enabled(_)) => match tls::driver_options(&settings.tls) {
+                Ok(driver_tls) => driver_tls.build_client(options),
+                Err(e) => return report.fail(TestStep::ServerSelection, e.to_string()),
+            },
+            _ => Client::with_options(options),
+        };
+        let client = match client {
+            Ok(client) => client,
+            Err(e) => return report.fail_with(TestStep::ServerSelection, &e),
+        };
+
+        // The first command selects a server and then authenticates on it
+        let ping = client
+            .database(auth_source.as_deref().unwrap_or("admin"))
+            .run_command(doc! { "ping": 1 }, None)
+            .await;
+        if let Err(e) = &ping {
+            let step = first_command_step(e);
+            if step == TestStep::ServerSelection || auth_source.is_none() {
+                return report.fail_with(TestStep::ServerSelection, e);
+            }
+        }
+        report.pass(TestStep::ServerSelection, "server selected");
+        match (auth_source, ping) {
+            (Some(source), Ok(_)) => report.pass(
+                TestStep::Authenticate,
+                format!("authenticated against '{}'", source),
+            ),
+            (Some(_), Err(e)) => return report.fail_with(TestStep::Authenticate, &e),
+            (None, _) => report.skip(TestStep::Authenticate, "no credentials configured"),
+        }
+
+        match client
+            .database("admin")
+            .run_command(doc! { "hello": 1 }, None)
+            .await
+        {
+            Ok(reply) => report.pass(TestStep::Hello, describe_hello(&reply)),
+            Err(e) => return report.fail_with(TestStep::Hello, &e),
+        }
+
+        match client.list_database_names(None, None).await {
+            Ok(names) => report.pass(
+                TestStep::ListDatabases,
+                format!("{} database(s) visible", names.len()),
+            ),
+            Err(e) => return report.fail_with(TestStep::ListDatabases, &e),
+        }
+
+        report
+    }
+}
+
+fn describe_hello(reply: &Document) -> String {
+    let role = if reply.get_str("msg").ok() == Some("isdbgrid") {
+        "mongos".to_string()
+    } else if let Ok(set_name) = reply.get_str("setName") {
+        let state = if reply.get_bool("isWritablePrimary").unwrap_or(false) {
+            "primary"
+        } else if reply.get_bool("secondary").unwrap_or(false) {
+            "secondary"
+        } else {
+            "member"
+        };
+        format!("{} of replica set '{}'", state, set_name)
+    } else {
+        "standalone".to_string()
+    };
+    match reply.get_i32("maxWireVersion") {
+        Ok(wire_version) => format!("{}, wire version {}", role, wire_version),
+        Err(_) => role,
+    }
+}
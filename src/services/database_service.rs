@@ -1,30 +1,931 @@
+use crate::models::{
+    chunk_distribution, BalancerStatus, CollectionStats, ConnectionSettings, CurrentOpFilter,
+    CurrentOperation, DatabaseStats, ExplainSummary, Privilege, ProfiledOperation, ProfilerStatus,
+    ProfilingLevel, QueryOptions, ReplicaSetStatus, RoleInfo, RoleRef, ServerStatusSample,
+    ShardInfo, ShardedClusterStatus, SshTunnelSettings, Topology, TopologyKind, UserInfo,
+    ValidationCheck, ValidationRules,
+};
+use crate::services::{route_through_tunnel, SshTunnel};
+use crate::utils::connection_string::resolve_placeholders;
 use crate::utils::error::{MongoLiteError, Result};
-use mongodb::{Client, Database};
+use crate::utils::tls;
+use futures_util::{stream, StreamExt, TryStreamExt};
+use mongodb::bson::{doc, Bson, Document};
+use mongodb::options::{
+    ClientOptions, CreateCollectionOptions, Credential, FindOptions, GridFsBucketOptions, Tls,
+};
+use mongodb::results::CollectionType;
+use mongodb::{Client, Collection, Database};
+use std::sync::RwLock;
+
+// How many collections `list_collections` counts the documents of at once
+const CONCURRENT_COUNTS: usize = 8;
+
+// Commands that never modify data or server state. Anything else sent through
+// `run_command` is treated as a write or admin command.
+const READ_ONLY_COMMANDS: &[&str] = &[
+    "aggregate",
+    "balancerStatus",
+    "buildInfo",
+    "collStats",
+    "connectionStatus",
+    "count",
+    "currentOp",
+    "dbStats",
+    "distinct",
+    "explain",
+    "find",
+    "getCmdLineOpts",
+    "getMore",
+    "getParameter",
+    "hello",
+    "hostInfo",
+    "isMaster",
+    "killCursors",
+    "listCollections",
+    "listDatabases",
+    "listIndexes",
+    "listShards",
+    "ping",
+    "replSetGetConfig",
+    "replSetGetStatus",
+    "rolesInfo",
+    "serverStatus",
+    "usersInfo",
+];
+
+/// A database as reported by `listDatabases`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DatabaseInfo {
+    pub name: String,
+    pub size_on_disk: u64,
+    pub empty: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CollectionKind {
+    Collection,
+    View,
+    Timeseries,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CollectionInfo {
+    pub name: String,
+    pub kind: CollectionKind,
+    /// Taken from the collection's metadata, so it can be slightly off.
+    /// Views have no count.
+    pub document_count: Option<u64>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct IndexInfo {
+    pub name: String,
+    pub keys: Document,
+    pub unique: bool,
+    /// Size on disk, when the server reports it.
+    pub size: Option<u64>,
+}
+
+struct Connection {
+    client: Client,
+    read_only: bool,
+    /// Closed when the connection is dropped.
+    tunnel: Option<SshTunnel>,
+}
 
 pub struct DatabaseService {
-    client: Option<Client>,
+    connection: RwLock<Option<Connection>>,
 }
 
 impl DatabaseService {
     pub fn new() -> Self {
-        Self { client: None }
+        Self {
+            connection: RwLock::new(None),
+        }
     }
 
-    pub async fn connect(&mut self, connection_string: &str) -> Result<()> {
-        self.client = Some(Client::with_uri_str(connection_string).await?);
+    /// Connects using `connection_string` after resolving its `${...}`
+    /// placeholders. The resolved string is only kept for as long as it
+    /// takes to build the client. `password`, when given, replaces any
+    /// password in the connection string. TLS settings are passed to the
+    /// driver as `TlsOptions`. With `ssh_tunnel`, the connection goes through
+    /// a port forward on the bastion host.
+    pub async fn connect(
+        &self,
+        connection_string: &str,
+        password: Option<String>,
+        ssh_tunnel: Option<&SshTunnelSettings>,
+        read_only: bool,
+    ) -> Result<()> {
+        let resolved = resolve_placeholders(connection_string)?;
+        let mut settings = ConnectionSettings::from_uri(&resolved)?;
+        let tunnel = match ssh_tunnel {
+            Some(ssh_tunnel) => Some(route_through_tunnel(&mut settings, ssh_tunnel).await?),
+            None => None,
+        };
+        let mut options = ClientOptions::parse(tls::driver_uri(&settings)).await?;
+        if let Some(password) = password {
+            options
+                .credential
+                .get_or_insert_with(Credential::default)
+                .password = Some(password);
+        }
+        let client = match &options.tls {
+            Some(Tls::Enabled(_)) => tls::driver_options(&settings.tls)?.build_client(options)?,
+            _ => Client::with_options(options)?,
+        };
+        *self.connection.write().expect("Connection lock poisoned") = Some(Connection {
+            client,
+            read_only,
+            tunnel,
+        });
         Ok(())
     }
 
-    pub async fn list_databases(&self) -> Result<Vec<String>> {
-        if let Some(client) = &self.client {
-            let db_names = client.list_database_names(None, None).await?;
-            Ok(db_names)
+    /// Closes the client and the SSH tunnel behind it, if any.
+    pub async fn disconnect(&self) {
+        let connection = self
+            .connection
+            .write()
+            .expect("Connection lock poisoned")
+            .take();
+        if let Some(connection) = connection {
+            connection.client.shutdown().await;
+            if let Some(tunnel) = connection.tunnel {
+                tunnel.close().await;
+            }
+        }
+    }
+
+    fn client(&self) -> Result<Client> {
+        self.connection
+            .read()
+            .expect("Connection lock poisoned")
+            .as_ref()
+            .map(|connection| connection.client.clone())
+            .ok_or_else(|| MongoLiteError::from("Not connected to any database"))
+    }
+
+    pub fn is_connected(&self) -> bool {
+        self.connection
+            .read()
+            .expect("Connection lock poisoned")
+            .is_some()
+    }
+
+    /// Whether the current connection was opened from a read-only profile.
+    pub fn is_read_only(&self) -> bool {
+        self.connection
+            .read()
+            .expect("Connection lock poisoned")
+            .as_ref()
+            .is_some_and(|connection| connection.read_only)
+    }
+
+    /// Guard for every operation that writes data or changes server state.
+    pub fn ensure_writable(&self, operation: &str) -> Result<()> {
+        if self.is_read_only() {
+            Err(MongoLiteError::ReadOnlyViolation(operation.to_string()))
         } else {
-            Err(MongoLiteError::from("Not connected to any database"))
+            Ok(())
         }
     }
 
+    pub async fn list_databases(&self) -> Result<Vec<DatabaseInfo>> {
+        let databases = self
+            .client()?
+            .list_databases(None, None)
+            .await?
+            .into_iter()
+            .map(|database| DatabaseInfo {
+                name: database.name,
+                size_on_disk: database.size_on_disk,
+                empty: database.empty,
+            })
+            .collect();
+        Ok(databases)
+    }
+
+    pub async fn list_collection_names(&self, database: &str) -> Result<Vec<String>> {
+        let mut names = self
+            .client()?
+            .database(database)
+            .list_collection_names(None)
+            .await?;
+        names.sort();
+        Ok(names)
+    }
+
+    /// Lists the collections and views of a database, sorted by name, with
+    /// their estimated document counts.
+    pub async fn list_collections(&self, database: &str) -> Result<Vec<CollectionInfo>> {
+        let database = self.client()?.database(database);
+        let specifications: Vec<_> = database
+            .list_collections(None, None)
+            .await?
+            .try_collect()
+            .await?;
+
+        let database = &database;
+        let mut collections: Vec<CollectionInfo> = stream::iter(specifications)
+            .map(|specification| async move {
+                let kind = match specification.collection_type {
+                    CollectionType::View => CollectionKind::View,
+                    CollectionType::Timeseries => CollectionKind::Timeseries,
+                    _ => CollectionKind::Collection,
+                };
+                // A count that can't be read only costs the badge
+                let document_count = match kind {
+                    CollectionKind::View => None,
+                    _ => database
+                        .collection::<Document>(&specification.name)
+                        .estimated_document_count(None)
+                        .await
+                        .ok(),
+                };
+                CollectionInfo {
+                    name: specification.name,
+                    kind,
+                    document_count,
+                }
+            })
+            .buffer_unordered(CONCURRENT_COUNTS)
+            .collect()
+            .await;
+        collections.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(collections)
+    }
+
+    pub async fn list_indexes(&self, database: &str, collection: &str) -> Result<Vec<IndexInfo>> {
+        let models: Vec<_> = self
+            .client()?
+            .database(database)
+            .collection::<Document>(collection)
+            .list_indexes(None)
+            .await?
+            .try_collect()
+            .await?;
+        // Sizes only decorate the listing, so a failure to read them isn't one
+        let stats = self
+            .storage_stats(database, collection)
+            .await
+            .unwrap_or_default();
+
+        Ok(models
+            .into_iter()
+            .map(|model| {
+                let options = model.options.unwrap_or_default();
+                let name = options.name.unwrap_or_default();
+                IndexInfo {
+                    size: stats
+                        .indexes
+                        .iter()
+                        .find(|index| index.name == name)
+                        .map(|index| index.size),
+                    unique: options.unique.unwrap_or(false),
+                    keys: model.keys,
+                    name,
+                }
+            })
+            .collect())
+    }
+
+    pub async fn database_stats(&self, database: &str) -> Result<DatabaseStats> {
+        let stats = self
+            .run_command(database, doc! { "dbStats": 1, "freeStorage": 1 })
+            .await?;
+        Ok(DatabaseStats::from_document(&stats))
+    }
+
+    /// Storage figures of a collection with the size and usage of each index.
+    pub async fn collection_stats(
+        &self,
+        database: &str,
+        collection: &str,
+    ) -> Result<CollectionStats> {
+        let mut stats = self.storage_stats(database, collection).await?;
+        // $indexStats needs a privilege read-only users often lack; the rest
+        // of the stats are still worth showing without it
+        let usage = self
+            .client()?
+            .database(database)
+            .collection::<Document>(collection)
+            .aggregate([doc! { "$indexStats": {} }], None)
+            .await;
+        if let Ok(cursor) = usage {
+            if let Ok(usage) = cursor.try_collect::<Vec<_>>().await {
+                stats.add_index_usage(&usage);
+            }
+        }
+        Ok(stats)
+    }
+
+    async fn storage_stats(&self, database: &str, collection: &str) -> Result<CollectionStats> {
+        let shards: Vec<Document> = self
+            .client()?
+            .database(database)
+            .collection::<Document>(collection)
+            .aggregate([doc! { "$collStats": { "storageStats": {} } }], None)
+            .await?
+            .try_collect()
+            .await?;
+        Ok(CollectionStats::from_coll_stats(&shards))
+    }
+
+    pub async fn create_collection(
+        &self,
+        database: &str,
+        name: &str,
+        options: CreateCollectionOptions,
+    ) -> Result<()> {
+        self.ensure_writable("create")?;
+        self.client()?
+            .database(database)
+            .create_collection(name, options)
+            .await?;
+        Ok(())
+    }
+
+    /// Creates a read-only view of `view_on` through an aggregation pipeline.
+    pub async fn create_view(
+        &self,
+        database: &str,
+        name: &str,
+        view_on: &str,
+        pipeline: Vec<Document>,
+    ) -> Result<()> {
+        let mut options = CreateCollectionOptions::default();
+        options.view_on = Some(view_on.to_string());
+        options.pipeline = Some(pipeline);
+        self.create_collection(database, name, options).await
+    }
+
+    pub async fn drop_database(&self, database: &str) -> Result<()> {
+        self.ensure_writable("dropDatabase")?;
+        self.client()?.database(database).drop(None).await?;
+        Ok(())
+    }
+
+    /// Drops a collection or a view.
+    pub async fn drop_collection(&self, database: &str, collection: &str) -> Result<()> {
+        self.ensure_writable("drop")?;
+        self.client()?
+            .database(database)
+            .collection::<Document>(collection)
+            .drop(None)
+            .await?;
+        Ok(())
+    }
+
+    /// Drops the `files` and `chunks` collections of a GridFS bucket.
+    pub async fn drop_gridfs_bucket(&self, database: &str, bucket: &str) -> Result<()> {
+        self.ensure_writable("drop")?;
+        let options = GridFsBucketOptions::builder()
+            .bucket_name(bucket.to_string())
+            .build();
+        self.client()?
+            .database(database)
+            .gridfs_bucket(options)
+            .drop()
+            .await?;
+        Ok(())
+    }
+
+    /// Renames a collection within its database.
+    pub async fn rename_collection(&self, database: &str, from: &str, to: &str) -> Result<()> {
+        self.run_command(
+            "admin",
+            doc! {
+                "renameCollection": format!("{}.{}", database, from),
+                "to": format!("{}.{}", database, to),
+            },
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// A collection handle that reads and writes with the query's read
+    /// preference, read concern and write concern.
+    pub fn collection(
+        &self,
+        database: &str,
+        collection: &str,
+        options: &QueryOptions,
+    ) -> Result<Collection<Document>> {
+        let options = options
+            .collection_options()
+            .map_err(MongoLiteError::QueryError)?;
+        Ok(self
+            .client()?
+            .database(database)
+            .collection_with_options(collection, options))
+    }
+
     pub fn get_database(&self, name: &str) -> Option<Database> {
-        self.client.as_ref().map(|client| client.database(name))
+        self.client().ok().map(|client| client.database(name))
+    }
+
+    /// Counters and gauges from `serverStatus`, leaving out the sections the
+    /// monitor doesn't chart.
+    pub async fn server_status(&self) -> Result<ServerStatusSample> {
+        let status = self
+            .run_command(
+                "admin",
+                doc! { "serverStatus": 1, "repl": 0, "metrics": 0, "locks": 0 },
+            )
+            .await?;
+        Ok(ServerStatusSample::from_document(&status))
+    }
+
+    /// The kind of deployment connected to, and for a sharded cluster which
+    /// mongos the connection talks to.
+    pub async fn topology(&self) -> Result<Topology> {
+        let hello = self.run_command("admin", doc! { "hello": 1 }).await?;
+        let mut topology = Topology::from_hello(&hello);
+        if topology.kind == TopologyKind::Sharded {
+            let status = self
+                .run_command(
+                    "admin",
+                    doc! { "serverStatus": 1, "repl": 0, "metrics": 0, "locks": 0 },
+                )
+                .await?;
+            topology.host = status.get_str("host").ok().map(str::to_string);
+        }
+        Ok(topology)
+    }
+
+    pub async fn replica_set_status(&self) -> Result<ReplicaSetStatus> {
+        let status = self
+            .run_command("admin", doc! { "replSetGetStatus": 1 })
+            .await?;
+        let config = self
+            .run_command("admin", doc! { "replSetGetConfig": 1 })
+            .await?;
+        Ok(ReplicaSetStatus::from_documents(&status, &config))
+    }
+
+    pub async fn sharded_cluster_status(&self) -> Result<ShardedClusterStatus> {
+        let mongos = self.topology().await?.host.unwrap_or_default();
+        let shards = self
+            .run_command("admin", doc! { "listShards": 1 })
+            .await?
+            .get_array("shards")
+            .map(|shards| {
+                shards
+                    .iter()
+                    .filter_map(Bson::as_document)
+                    .map(ShardInfo::from_document)
+                    .collect()
+            })
+            .unwrap_or_default();
+        let balancer = self
+            .run_command("admin", doc! { "balancerStatus": 1 })
+            .await?;
+
+        let config = self.client()?.database("config");
+        let chunk_counts: Vec<Document> = config
+            .collection::<Document>("chunks")
+            .aggregate(
+                [doc! { "$group": {
+                    "_id": { "ns": "$ns", "uuid": "$uuid", "shard": "$shard" },
+                    "chunks": { "$sum": 1 },
+                } }],
+                None,
+            )
+            .await?
+            .try_collect()
+            .await?;
+        let collections: Vec<Document> = config
+            .collection::<Document>("collections")
+            .find(doc! { "dropped": { "$ne": true } }, None)
+            .await?
+            .try_collect()
+            .await?;
+
+        Ok(ShardedClusterStatus {
+            mongos,
+            shards,
+            balancer: BalancerStatus::from_document(&balancer),
+            chunks: chunk_distribution(&chunk_counts, &collections),
+        })
+    }
+
+    /// Operations in progress on the server, from `$currentOp`.
+    pub async fn current_operations(
+        &self,
+        filter: &CurrentOpFilter,
+    ) -> Result<Vec<CurrentOperation>> {
+        let operations: Vec<Document> = self
+            .client()?
+            .database("admin")
+            .aggregate(filter.pipeline(), None)
+            .await?
+            .try_collect()
+            .await?;
+        Ok(operations
+            .iter()
+            .map(CurrentOperation::from_document)
+            .collect())
+    }
+
+    /// Kills an operation by its `opid` from `$currentOp`.
+    pub async fn kill_operation(&self, opid: Bson) -> Result<()> {
+        self.run_command("admin", doc! { "killOp": 1, "op": opid })
+            .await?;
+        Ok(())
+    }
+
+    /// Kills what is left on the server of a query tagged by `tag`: the
+    /// `find` or `getMore` still running, and its cursor if idle. Allowed on
+    /// read-only connections, as it only stops the app's own query.
+    pub async fn cancel_query(&self, tag: &str) -> Result<()> {
+        let client = self.client()?;
+        let admin = client.database("admin");
+        // The tag is letters, digits, `:` and `-`, all literal in a regex
+        let pipeline = vec![
+            doc! { "$currentOp": { "idleCursors": true } },
+            doc! { "$match": { "$or": [
+                { "command.comment": { "$regex": tag } },
+                { "cursor.originatingCommand.comment": { "$regex": tag } },
+            ] } },
+        ];
+        let operations: Vec<Document> =
+            admin.aggregate(pipeline, None).await?.try_collect().await?;
+        for operation in operations {
+            if let Some((database, command)) = kill_command(&operation) {
+                client.database(database).run_command(command, None).await?;
+            }
+        }
+        Ok(())
+    }
+
+    pub async fn profiler_status(&self, database: &str) -> Result<ProfilerStatus> {
+        // `profile` only changes settings when given a level, so reading them
+        // is allowed on read-only connections
+        let reply = self
+            .client()?
+            .database(database)
+            .run_command(doc! { "profile": -1 }, None)
+            .await?;
+        Ok(ProfilerStatus::from_document(&reply))
+    }
+
+    pub async fn set_profiler(
+        &self,
+        database: &str,
+        level: ProfilingLevel,
+        slow_ms: i64,
+    ) -> Result<()> {
+        self.run_command(
+            database,
+            doc! { "profile": level.level(), "slowms": slow_ms },
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// The most recent `system.profile` entries, newest first.
+    pub async fn profiled_operations(
+        &self,
+        database: &str,
+        limit: i64,
+    ) -> Result<Vec<ProfiledOperation>> {
+        let options = FindOptions::builder()
+            .sort(doc! { "ts": -1 })
+            .limit(limit)
+            .build();
+        let entries: Vec<Document> = self
+            .client()?
+            .database(database)
+            .collection::<Document>("system.profile")
+            .find(None, options)
+            .await?
+            .try_collect()
+            .await?;
+        Ok(entries
+            .iter()
+            .map(ProfiledOperation::from_document)
+            .collect())
+    }
+
+    /// Explains a find with execution stats.
+    pub async fn explain_find(
+        &self,
+        database: &str,
+        collection: &str,
+        filter: Document,
+        sort: Option<Document>,
+        projection: Option<Document>,
+        options: &QueryOptions,
+    ) -> Result<ExplainSummary> {
+        let mut find = doc! { "find": collection, "filter": filter };
+        if let Some(sort) = sort {
+            find.insert("sort", sort);
+        }
+        if let Some(projection) = projection {
+            find.insert("projection", projection);
+        }
+        options
+            .apply_to_find_command(&mut find)
+            .map_err(MongoLiteError::QueryError)?;
+        let explain = self
+            .run_command(
+                database,
+                doc! { "explain": find, "verbosity": "executionStats" },
+            )
+            .await?;
+        Ok(ExplainSummary::from_document(&explain))
+    }
+
+    pub async fn validation_rules(
+        &self,
+        database: &str,
+        collection: &str,
+    ) -> Result<ValidationRules> {
+        let reply = self
+            .run_command(
+                database,
+                doc! { "listCollections": 1, "filter": { "name": collection } },
+            )
+            .await?;
+        let options = reply
+            .get_document("cursor")
+            .ok()
+            .and_then(|cursor| cursor.get_array("firstBatch").ok())
+            .and_then(|batch| batch.first())
+            .and_then(Bson::as_document)
+            .ok_or_else(|| {
+                MongoLiteError::QueryError(format!("Collection '{}' not found", collection))
+            })?
+            .get_document("options")
+            .cloned()
+            .unwrap_or_default();
+        Ok(ValidationRules::from_options(&options))
+    }
+
+    pub async fn set_validation_rules(
+        &self,
+        database: &str,
+        collection: &str,
+        rules: &ValidationRules,
+    ) -> Result<()> {
+        self.run_command(database, rules.coll_mod(collection))
+            .await?;
+        Ok(())
+    }
+
+    pub async fn sample_documents(
+        &self,
+        database: &str,
+        collection: &str,
+        size: i64,
+    ) -> Result<Vec<Document>> {
+        let documents = self
+            .client()?
+            .database(database)
+            .collection::<Document>(collection)
+            .aggregate([doc! { "$sample": { "size": size } }], None)
+            .await?
+            .try_collect()
+            .await?;
+        Ok(documents)
+    }
+
+    /// Finds documents that don't match `validator`, in a random sample of
+    /// `sample` documents or, without one, in the whole collection.
+    pub async fn check_validator(
+        &self,
+        database: &str,
+        collection: &str,
+        validator: Document,
+        sample: Option<i64>,
+        limit: i64,
+    ) -> Result<ValidationCheck> {
+        let collection = self
+            .client()?
+            .database(database)
+            .collection::<Document>(collection);
+        let failing = doc! { "$nor": [validator] };
+        match sample {
+            Some(size) => {
+                let documents: Vec<Document> = collection
+                    .aggregate(
+                        [
+                            doc! { "$sample": { "size": size } },
+                            doc! { "$match": failing },
+                        ],
+                        None,
+                    )
+                    .await?
+                    .try_collect()
+                    .await?;
+                Ok(ValidationCheck {
+                    sampled: Some(size as u64),
+                    failing: documents.len() as u64,
+                    examples: documents.into_iter().take(limit as usize).collect(),
+                })
+            }
+            None => {
+                let count = collection.count_documents(failing.clone(), None).await?;
+                let options = FindOptions::builder().limit(limit).build();
+                let examples = collection
+                    .find(failing, options)
+                    .await?
+                    .try_collect()
+                    .await?;
+                Ok(ValidationCheck {
+                    sampled: None,
+                    failing: count,
+                    examples,
+                })
+            }
+        }
+    }
+
+    /// The users defined in `database`.
+    pub async fn users(&self, database: &str) -> Result<Vec<UserInfo>> {
+        let reply = self.run_command(database, doc! { "usersInfo": 1 }).await?;
+        Ok(reply
+            .get_array("users")
+            .map(|users| {
+                users
+                    .iter()
+                    .filter_map(Bson::as_document)
+                    .map(UserInfo::from_document)
+                    .collect()
+            })
+            .unwrap_or_default())
+    }
+
+    /// The roles defined in `database` with their own and inherited
+    /// privileges, built-in roles included.
+    pub async fn roles(&self, database: &str) -> Result<Vec<RoleInfo>> {
+        let reply = self
+            .run_command(
+                database,
+                doc! { "rolesInfo": 1, "showPrivileges": true, "showBuiltinRoles": true },
+            )
+            .await?;
+        Ok(reply
+            .get_array("roles")
+            .map(|roles| {
+                roles
+                    .iter()
+                    .filter_map(Bson::as_document)
+                    .map(RoleInfo::from_document)
+                    .collect()
+            })
+            .unwrap_or_default())
+    }
+
+    pub async fn create_user(
+        &self,
+        database: &str,
+        user: &str,
+        password: &str,
+        roles: &[RoleRef],
+    ) -> Result<()> {
+        self.run_command(
+            database,
+            doc! { "createUser": user, "pwd": password, "roles": role_documents(roles) },
+        )
+        .await?;
+        Ok(())
+    }
+
+    pub async fn drop_user(&self, database: &str, user: &str) -> Result<()> {
+        self.run_command(database, doc! { "dropUser": user })
+            .await?;
+        Ok(())
+    }
+
+    pub async fn change_password(&self, database: &str, user: &str, password: &str) -> Result<()> {
+        self.run_command(database, doc! { "updateUser": user, "pwd": password })
+            .await?;
+        Ok(())
+    }
+
+    pub async fn grant_roles(&self, database: &str, user: &str, roles: &[RoleRef]) -> Result<()> {
+        self.run_command(
+            database,
+            doc! { "grantRolesToUser": user, "roles": role_documents(roles) },
+        )
+        .await?;
+        Ok(())
+    }
+
+    pub async fn revoke_roles(&self, database: &str, user: &str, roles: &[RoleRef]) -> Result<()> {
+        self.run_command(
+            database,
+            doc! { "revokeRolesFromUser": user, "roles": role_documents(roles) },
+        )
+        .await?;
+        Ok(())
+    }
+
+    pub async fn create_role(
+        &self,
+        database: &str,
+        role: &str,
+        privileges: &[Privilege],
+        roles: &[RoleRef],
+    ) -> Result<()> {
+        let privileges: Vec<Document> = privileges.iter().map(Privilege::to_document).collect();
+        self.run_command(
+            database,
+            doc! { "createRole": role, "privileges": privileges, "roles": role_documents(roles) },
+        )
+        .await?;
+        Ok(())
+    }
+
+    pub async fn drop_role(&self, database: &str, role: &str) -> Result<()> {
+        self.run_command(database, doc! { "dropRole": role })
+            .await?;
+        Ok(())
+    }
+
+    /// Runs a database command, rejecting anything that isn't known to be
+    /// read-only when connected through a read-only profile.
+    pub async fn run_command(&self, database: &str, command: Document) -> Result<Document> {
+        let name =
+            command.keys().next().cloned().ok_or_else(|| {
+                MongoLiteError::QueryError("Command document is empty".to_string())
+            })?;
+        if !READ_ONLY_COMMANDS.contains(&name.as_str()) || writes_output(&command) {
+            self.ensure_writable(&name)?;
+        }
+        let reply = self
+            .client()?
+            .database(database)
+            .run_command(command, None)
+            .await?;
+        Ok(reply)
+    }
+}
+
+fn role_documents(roles: &[RoleRef]) -> Vec<Document> {
+    roles.iter().map(RoleRef::to_document).collect()
+}
+
+// An aggregation with a $out or $merge stage writes its results to a collection.
+fn writes_output(command: &Document) -> bool {
+    let pipeline = match command.get_array("pipeline") {
+        Ok(pipeline) => pipeline,
+        Err(_) => return false,
+    };
+    pipeline.iter().any(|stage| match stage {
+        Bson::Document(stage) => stage.contains_key("$out") || stage.contains_key("$merge"),
+        _ => false,
+    })
+}
+
+/// The database and command that stop an operation reported by
+/// `$currentOp`: `killCursors` for an idle cursor, `killOp` otherwise.
+fn kill_command(operation: &Document) -> Option<(&str, Document)> {
+    if operation.get_str("type") == Ok("idleCursor") {
+        let (database, collection) = operation.get_str("ns").ok()?.split_once('.')?;
+        let cursor_id = operation.get_document("cursor").ok()?.get("cursorId")?;
+        Some((
+            database,
+            doc! { "killCursors": collection, "cursors": [cursor_id.clone()] },
+        ))
+    } else {
+        let opid = operation.get("opid")?;
+        Some(("admin", doc! { "killOp": 1, "op": opid.clone() }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn kills_idle_cursors_and_running_operations() {
+        let idle = doc! {
+            "type": "idleCursor",
+            "ns": "shop.orders.archive",
+            "cursor": { "cursorId": 8_123_456_789_i64 },
+        };
+        assert_eq!(
+            kill_command(&idle),
+            Some((
+                "shop",
+                doc! { "killCursors": "orders.archive", "cursors": [8_123_456_789_i64] }
+            ))
+        );
+
+        let running = doc! { "type": "op", "opid": "shard0:42" };
+        assert_eq!(
+            kill_command(&running),
+            Some(("admin", doc! { "killOp": 1, "op": "shard0:42" }))
+        );
+
+        assert_eq!(
+            kill_command(&doc! { "type": "idleCursor", "ns": "shop" }),
+            None
+        );
+        assert_eq!(kill_command(&doc! { "type": "op" }), None);
     }
 }
@@ -1,5 +1,13 @@
+mod connection_test_service;
 mod database_service;
 mod query_service;
+mod ssh_tunnel;
 
-pub use database_service::DatabaseService;
-pub use query_service::QueryService;
+pub use connection_test_service::{
+    ConnectionTestReport, ConnectionTestService, StepOutcome, TestStep,
+};
+pub use database_service::{
+    CollectionInfo, CollectionKind, DatabaseInfo, DatabaseService, IndexInfo,
+};
+pub use query_service::{CancellationHandle, QueryService};
+pub use ssh_tunnel::{askpass_reply, check_host, route_through_tunnel, tunnel_target, SshTunnel};
@@ -1,6 +1,48 @@
-use crate::utils::error::Result;
+use crate::utils::error::{MongoLiteError, Result};
 use futures_util::TryStreamExt;
+use mongodb::options::FindOptions;
 use mongodb::{bson::Document, Collection};
+use std::future::Future;
+use std::sync::Arc;
+use tokio::sync::Notify;
+use uuid::Uuid;
+
+/// Stops one running query. The query's comment carries the handle's tag so
+/// that `DatabaseService::cancel_query` can find what is left of it on the
+/// server.
+#[derive(Clone)]
+pub struct CancellationHandle {
+    tag: String,
+    cancelled: Arc<Notify>,
+}
+
+impl CancellationHandle {
+    pub fn new() -> Self {
+        Self {
+            tag: format!("mongolite:{}", Uuid::new_v4()),
+            cancelled: Arc::new(Notify::new()),
+        }
+    }
+
+    pub fn tag(&self) -> &str {
+        &self.tag
+    }
+
+    /// Makes the query return, dropping its cursor.
+    pub fn cancel(&self) {
+        self.cancelled.notify_one();
+    }
+
+    /// The comment to send with the query: the user's comment, if any,
+    /// followed by the tag.
+    fn comment(&self, comment: &str) -> String {
+        if comment.is_empty() {
+            self.tag.clone()
+        } else {
+            format!("{} [{}]", comment, self.tag)
+        }
+    }
+}
 
 pub struct QueryService;
 
@@ -9,19 +51,88 @@ impl QueryService {
         Self
     }
 
+    /// Runs a find, returning its documents or an error once
+    /// `cancellation` is cancelled.
     pub async fn execute_query(
         &self,
         collection: &Collection<Document>,
         query: Document,
-        projection: Option<Document>,
-        sort: Option<Document>,
+        mut options: FindOptions,
+        cancellation: &CancellationHandle,
     ) -> Result<Vec<Document>> {
-        let mut options = mongodb::options::FindOptions::default();
-        options.projection = projection;
-        options.sort = sort;
+        options.comment = Some(cancellation.comment(options.comment.as_deref().unwrap_or("")));
+
+        let find = async {
+            let cursor = collection.find(query, options).await?;
+            let results: Vec<Document> = cursor.try_collect().await?;
+            Ok(results)
+        };
+        // Dropping `find` drops the cursor, which the driver then kills
+        cancellable(find, cancellation).await
+    }
+}
+
+/// Runs `future` to completion, or drops it and returns an error once
+/// `cancellation` is cancelled, even if that happened before it started.
+async fn cancellable<T>(
+    future: impl Future<Output = Result<T>>,
+    cancellation: &CancellationHandle,
+) -> Result<T> {
+    tokio::select! {
+        result = future => result,
+        _ = cancellation.cancelled.notified() => {
+            Err(MongoLiteError::QueryError("Query cancelled".to_string()))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn comment_includes_the_tag() {
+        let cancellation = CancellationHandle::new();
+        assert_eq!(cancellation.comment(""), cancellation.tag());
+        assert_eq!(
+            cancellation.comment("report"),
+            format!("report [{}]", cancellation.tag())
+        );
+    }
+
+    #[tokio::test]
+    async fn cancelling_drops_the_running_query() {
+        let cancellation = CancellationHandle::new();
+        let handle = cancellation.clone();
+        let query = tokio::spawn(async move {
+            cancellable(std::future::pending::<Result<()>>(), &cancellation).await
+        });
+        tokio::task::yield_now().await;
+        handle.cancel();
+        let result = query.await.unwrap();
+        assert!(matches!(result, Err(MongoLiteError::QueryError(_))));
+    }
+
+    #[tokio::test]
+    async fn cancelling_before_the_query_starts_still_stops_it() {
+        let cancellation = CancellationHandle::new();
+        cancellation.cancel();
+        let result = cancellable(std::future::pending::<Result<()>>(), &cancellation).await;
+        assert!(matches!(result, Err(MongoLiteError::QueryError(_))));
+    }
+
+    #[tokio::test]
+    async fn finished_queries_return_their_results() {
+        let cancellation = CancellationHandle::new();
+        let result = cancellable(async { Ok(42) }, &cancellation).await;
+        assert_eq!(result.unwrap(), 42);
+    }
 
-        let cursor = collection.find(query, options).await?;
-        let results: Vec<Document> = cursor.try_collect().await?;
-        Ok(results)
+    #[test]
+    fn tags_are_literal_in_a_regex() {
+        let tag = CancellationHandle::new().tag().to_string();
+        assert!(tag
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == ':' || c == '-'));
     }
 }
@@ -0,0 +1,408 @@
+use crate::models::{
+    ConnectionSettings, HostEntry, SshAuthMethod, SshTunnelSettings, DEFAULT_PORT,
+};
+use crate::utils::connection_string::resolve_value_placeholders;
+use crate::utils::error::{MongoLiteError, Result};
+use std::fs;
+use std::io::Write;
+use std::net::{Ipv4Addr, SocketAddr, TcpListener};
+use std::process::Stdio;
+use std::time::{Duration, Instant};
+use tempfile::NamedTempFile;
+use tokio::io::AsyncReadExt;
+use tokio::net::TcpStream;
+use tokio::process::{Child, Command};
+
+const SSH_PROGRAM: &str = "ssh";
+const READY_TIMEOUT: Duration = Duration::from_secs(20);
+const READY_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+// ssh asks `SSH_ASKPASS` for passwords and passphrases. It is pointed at this
+// executable, which answers with the secret from a file only the current user
+// can read, and deletes the file; see `askpass_reply`. Only the file's path
+// is in ssh's environment.
+const ASKPASS_MODE_ENV: &str = "MONGOLITE_SSH_ASKPASS";
+const ASKPASS_SECRET_FILE_ENV: &str = "MONGOLITE_SSH_SECRET_FILE";
+
+/// When this process was started by ssh as its askpass program, returns the
+/// secret to print in answer to the prompt. The secret can be read once; any
+/// later prompt gets an empty answer.
+pub fn askpass_reply() -> Option<String> {
+    std::env::var_os(ASKPASS_MODE_ENV)?;
+    let Some(path) = std::env::var_os(ASKPASS_SECRET_FILE_ENV) else {
+        return Some(String::new());
+    };
+    let secret = fs::read_to_string(&path).unwrap_or_default();
+    let _ = fs::remove_file(&path);
+    Some(secret)
+}
+
+/// The host and port a tunnel forwards to for these connection settings.
+/// Only a single host can be forwarded, and TLS can't verify the server
+/// through a tunnel: the driver connects to a local port, which the
+/// server's certificate isn't issued for.
+pub fn tunnel_target(settings: &ConnectionSettings) -> Result<(String, u16)> {
+    let tunnel_error = |message: &str| Err(MongoLiteError::SshTunnelError(message.to_string()));
+    if settings.srv {
+        return tunnel_error(
+            "mongodb+srv:// connection strings can't be tunneled; use the host name instead",
+        );
+    }
+    let [target] = settings.hosts.as_slice() else {
+        return tunnel_error("a tunnel forwards a single host; remove the other hosts");
+    };
+    if settings.tls.enabled.unwrap_or(false) && !settings.tls.allow_invalid_certificates {
+        return tunnel_error(
+            "TLS can't verify the server through a tunnel, since the connection goes to a \
+             local port its certificate isn't issued for; turn TLS off if the network behind \
+             the bastion is trusted, or allow invalid certificates to encrypt without \
+             verifying the server",
+        );
+    }
+    Ok((target.host.clone(), target.port.unwrap_or(DEFAULT_PORT)))
+}
+
+/// Opens a tunnel to the connection string's host and points `settings` at
+/// its local end.
+pub async fn route_through_tunnel(
+    settings: &mut ConnectionSettings,
+    ssh_tunnel: &SshTunnelSettings,
+) -> Result<SshTunnel> {
+    let (host, port) = tunnel_target(settings)?;
+    let tunnel = SshTunnel::open(ssh_tunnel, &host, port).await?;
+
+    let local_address = tunnel.local_address();
+    settings.hosts = vec![HostEntry {
+        host: local_address.ip().to_string(),
+        port: Some(local_address.port()),
+    }];
+    // Replica set members announce addresses only the bastion can reach, so
+    // the driver has to stay on the forwarded one
+    settings.direct_connection.get_or_insert(true);
+    Ok(tunnel)
+}
+
+/// A local port forward through an SSH bastion, run by the system's OpenSSH
+/// client. The forward is closed when the tunnel is dropped.
+pub struct SshTunnel {
+    child: Child,
+    local_address: SocketAddr,
+}
+
+impl SshTunnel {
+    /// Starts `ssh -L` forwarding a free local port to `target_host:target_port`
+    /// as seen from the bastion, and waits until the forward accepts
+    /// connections.
+    pub async fn open(
+        settings: &SshTunnelSettings,
+        target_host: &str,
+        target_port: u16,
+    ) -> Result<Self> {
+        let tunnel_error = |message: String| MongoLiteError::SshTunnelError(message);
+        check_host(&settings.host)?;
+
+        let local_address = free_local_address()
+            .map_err(|e| tunnel_error(format!("cannot pick a local port: {}", e)))?;
+        let mut command = Command::new(SSH_PROGRAM);
+        command
+            .arg("-N")
+            .arg("-T")
+            .arg("-L")
+            .arg(format!(
+                "{}:{}:{}",
+                local_address,
+                bracket_ipv6(target_host),
+                target_port
+            ))
+            .arg("-p")
+            .arg(settings.port.to_string())
+            .args(["-o", "ExitOnForwardFailure=yes"])
+            .args(["-o", "ServerAliveInterval=30"])
+            .args(["-o", "NumberOfPasswordPrompts=1"])
+            .args(["-o", "LogLevel=ERROR"])
+            // There's no terminal to confirm a new host key on, so the
+            // bastion's key has to be in known_hosts already
+            .args(["-o", "StrictHostKeyChecking=yes"]);
+        if !settings.user.trim().is_empty() {
+            command.arg("-l").arg(settings.user.trim());
+        }
+
+        // Deleted by the askpass program once read, or when the tunnel is
+        // ready if ssh never asked
+        let mut secret_file = None;
+        let secret = match settings.auth_method {
+            SshAuthMethod::Password => {
+                command.args([
+                    "-o",
+                    "PreferredAuthentications=password,keyboard-interactive",
+                ]);
+                command.args(["-o", "PubkeyAuthentication=no"]);
+                resolve_value_placeholders(&settings.password)?
+            }
+            SshAuthMethod::PrivateKey => {
+                command.args(["-o", "PreferredAuthentications=publickey"]);
+                if !settings.key_file.trim().is_empty() {
+                    command.arg("-i").arg(settings.key_file.trim());
+                    command.args(["-o", "IdentitiesOnly=yes"]);
+                }
+                resolve_value_placeholders(&settings.key_passphrase)?
+            }
+        };
+        if secret.is_empty() {
+            command.args(["-o", "BatchMode=yes"]);
+        } else {
+            let program = std::env::current_exe()
+                .map_err(|e| tunnel_error(format!("cannot locate the askpass program: {}", e)))?;
+            let file = write_secret_file(&secret)
+                .map_err(|e| tunnel_error(format!("cannot write the askpass file: {}", e)))?;
+            // OpenSSH before 8.4 ignores SSH_ASKPASS_REQUIRE and only uses
+            // the askpass program when DISPLAY is set
+            command
+                .env("SSH_ASKPASS", program)
+                .env("SSH_ASKPASS_REQUIRE", "force")
+                .env(ASKPASS_MODE_ENV, "1")
+                .env(ASKPASS_SECRET_FILE_ENV, file.path());
+            secret_file = Some(file);
+        }
+        // Nothing after `--` is read as an option
+        command.arg("--").arg(settings.host.trim());
+
+        let child = command
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(|e| tunnel_error(format!("cannot run '{}': {}", SSH_PROGRAM, e)))?;
+        let mut tunnel = SshTunnel {
+            child,
+            local_address,
+        };
+        let ready = tunnel.wait_until_ready().await;
+        drop(secret_file);
+        ready.map_err(|e| match e {
+            MongoLiteError::SshTunnelError(message) if is_host_key_failure(&message) => {
+                let destination = match settings.user.trim() {
+                    "" => settings.host.trim().to_string(),
+                    user => format!("{}@{}", user, settings.host.trim()),
+                };
+                tunnel_error(format!(
+                    "{}; check the bastion's host key and add it to known_hosts, for example \
+                     by connecting once with `ssh -p {} {}` from a terminal",
+                    message, settings.port, destination
+                ))
+            }
+            e => e,
+        })?;
+        Ok(tunnel)
+    }
+
+    pub fn local_address(&self) -> SocketAddr {
+        self.local_address
+    }
+
+    async fn wait_until_ready(&mut self) -> Result<()> {
+        let started = Instant::now();
+        loop {
+            if let Some(status) = self.child.try_wait().map_err(|e| {
+                MongoLiteError::SshTunnelError(format!("cannot check on ssh: {}", e))
+            })? {
+                let mut output = String::new();
+                if let Some(stderr) = &mut self.child.stderr {
+                    let _ = stderr.read_to_string(&mut output).await;
+                }
+                let output = output.trim();
+                return Err(MongoLiteError::SshTunnelError(if output.is_empty() {
+                    format!("ssh exited with {}", status)
+                } else {
+                    output.to_string()
+                }));
+            }
+            if TcpStream::connect(self.local_address).await.is_ok() {
+                return Ok(());
+            }
+            if started.elapsed() > READY_TIMEOUT {
+                return Err(MongoLiteError::SshTunnelError(format!(
+                    "the tunnel was not ready after {}s",
+                    READY_TIMEOUT.as_secs()
+                )));
+            }
+            tokio::time::sleep(READY_POLL_INTERVAL).await;
+        }
+    }
+
+    /// Stops the port forward and waits for ssh to exit.
+    pub async fn close(mut self) {
+        let _ = self.child.kill().await;
+    }
+}
+
+/// Rejects SSH hosts that ssh would read as an option, such as
+/// `-oProxyCommand=...`.
+pub fn check_host(host: &str) -> Result<()> {
+    let host = host.trim();
+    if host.is_empty() {
+        return Err(MongoLiteError::SshTunnelError(
+            "no SSH host given".to_string(),
+        ));
+    }
+    if host.starts_with('-') {
+        return Err(MongoLiteError::SshTunnelError(format!(
+            "'{}' is not a valid SSH host",
+            host
+        )));
+    }
+    Ok(())
+}
+
+fn is_host_key_failure(message: &str) -> bool {
+    message.contains("Host key verification failed")
+}
+
+/// Writes the secret to a new temporary file, which only the current user
+/// can read.
+fn write_secret_file(secret: &str) -> std::io::Result<NamedTempFile> {
+    let mut file = NamedTempFile::new()?;
+    file.write_all(secret.as_bytes())?;
+    file.flush()?;
+    Ok(file)
+}
+
+/// Asks the OS for a free port. ssh binds it a moment later, so another
+/// process could take it in between; ssh then fails with a clear error.
+fn free_local_address() -> std::io::Result<SocketAddr> {
+    TcpListener::bind((Ipv4Addr::LOCALHOST, 0))?.local_addr()
+}
+
+fn bracket_ipv6(host: &str) -> String {
+    if host.contains(':') && !host.starts_with('[') {
+        format!("[{}]", host)
+    } else {
+        host.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    const KEY_FIXTURE: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/ssh/id_ed25519");
+
+    fn ssh_available() -> bool {
+        std::process::Command::new(SSH_PROGRAM)
+            .arg("-V")
+            .output()
+            .is_ok()
+    }
+
+    #[test]
+    fn tunnels_a_single_host_without_tls_verification() {
+        let target = |uri: &str| tunnel_target(&ConnectionSettings::from_uri(uri).unwrap());
+        assert_eq!(
+            target("mongodb://db1.internal/").unwrap(),
+            ("db1.internal".to_string(), DEFAULT_PORT)
+        );
+        assert!(target("mongodb+srv://cluster0.example.net/").is_err());
+        assert!(target("mongodb://db1,db2/").is_err());
+        assert!(matches!(
+            target("mongodb://db1:27018/?tls=true"),
+            Err(MongoLiteError::SshTunnelError(message)) if message.contains("TLS")
+        ));
+        assert_eq!(
+            target("mongodb://db1:27018/?tls=true&tlsAllowInvalidCertificates=true").unwrap(),
+            ("db1".to_string(), 27018)
+        );
+    }
+
+    #[test]
+    fn rejects_hosts_that_look_like_options() {
+        assert!(check_host("bastion.example.net").is_ok());
+        assert!(check_host(" ").is_err());
+        assert!(check_host("-oProxyCommand=touch /tmp/pwned").is_err());
+        assert!(check_host(" -p22").is_err());
+    }
+
+    #[test]
+    fn askpass_secret_file_is_private() {
+        let file = write_secret_file("s3cret").unwrap();
+        assert_eq!(fs::read_to_string(file.path()).unwrap(), "s3cret");
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(file.path()).unwrap().permissions().mode();
+            assert_eq!(mode & 0o077, 0);
+        }
+    }
+
+    #[tokio::test]
+    async fn reports_why_ssh_failed() {
+        if !ssh_available() {
+            eprintln!("skipped: no ssh client installed");
+            return;
+        }
+        // Nothing listens on a port that was just freed
+        let closed_port = free_local_address().unwrap().port();
+        let settings = SshTunnelSettings {
+            host: "127.0.0.1".to_string(),
+            port: closed_port,
+            auth_method: SshAuthMethod::PrivateKey,
+            key_file: KEY_FIXTURE.to_string(),
+            ..SshTunnelSettings::default()
+        };
+        match SshTunnel::open(&settings, "localhost", 27017).await {
+            Err(MongoLiteError::SshTunnelError(message)) => {
+                assert!(message.contains("Connection refused"), "{}", message)
+            }
+            Err(e) => panic!("unexpected error: {}", e),
+            Ok(_) => panic!("the tunnel opened without an SSH server"),
+        }
+    }
+
+    /// Needs an sshd with TCP forwarding that accepts the fixture key:
+    ///
+    /// ```sh
+    /// docker run -d --rm --name mongolite-sshd -p 2222:2222 \
+    ///   -e USER_NAME=mongolite \
+    ///   -e PUBLIC_KEY="$(cat tests/fixtures/ssh/id_ed25519.pub)" \
+    ///   -e DOCKER_MODS=linuxserver/mods:openssh-server-ssh-tunnel \
+    ///   lscr.io/linuxserver/openssh-server
+    /// ssh-keyscan -p 2222 127.0.0.1 >> ~/.ssh/known_hosts
+    /// cargo test -- --ignored forwards_through_sshd
+    /// ```
+    ///
+    /// The tunnel is pointed back at the container's own sshd, whose banner
+    /// must come through it.
+    #[tokio::test]
+    #[ignore]
+    async fn forwards_through_sshd() {
+        // ssh refuses keys other users can read, which a checkout doesn't
+        // guarantee
+        let key = tempfile::NamedTempFile::new().unwrap();
+        fs::copy(KEY_FIXTURE, key.path()).unwrap();
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(key.path(), fs::Permissions::from_mode(0o600)).unwrap();
+        }
+
+        let settings = SshTunnelSettings {
+            host: "127.0.0.1".to_string(),
+            port: 2222,
+            user: "mongolite".to_string(),
+            auth_method: SshAuthMethod::PrivateKey,
+            key_file: key.path().to_string_lossy().into_owned(),
+            ..SshTunnelSettings::default()
+        };
+        let tunnel = SshTunnel::open(&settings, "localhost", 2222).await.unwrap();
+
+        let mut stream = TcpStream::connect(tunnel.local_address()).await.unwrap();
+        let mut banner = [0u8; 8];
+        stream.read_exact(&mut banner).await.unwrap();
+        assert_eq!(&banner, b"SSH-2.0-");
+
+        let local_address = tunnel.local_address();
+        tunnel.close().await;
+        assert!(TcpStream::connect(local_address).await.is_err());
+    }
+}
@@ -1,3 +1,4 @@
+use crate::models::Environment;
 use egui::{Color32, Rounding, Stroke, Vec2};
 
 pub struct Theme {
@@ -5,6 +6,7 @@ pub struct Theme {
     pub bg_color: Color32,
     pub text_color: Color32,
     pub danger_color: Color32,
+    pub warning_color: Color32,
     pub separator_color: Color32,
     pub button_rounding: Rounding,
     pub frame_rounding: Rounding,
@@ -21,6 +23,7 @@ impl Theme {
             bg_color: Color32::from_rgb(248, 249, 250),   // Light Gray
             text_color: Color32::from_rgb(60, 64, 67),    // Dark Gray
             danger_color: Color32::from_rgb(234, 67, 53), // Google Red
+            warning_color: Color32::from_rgb(251, 188, 4), // Google Yellow
             separator_color: Color32::from_gray(200),
             button_rounding: Rounding::same(4.0),
             frame_rounding: Rounding::same(4.0),
@@ -43,6 +46,15 @@ impl Theme {
         }
     }
 
+    pub fn environment_color(&self, environment: Environment) -> Option<Color32> {
+        match environment {
+            Environment::None => None,
+            Environment::Development => Some(self.accent_color),
+            Environment::Staging => Some(self.warning_color),
+            Environment::Production => Some(self.danger_color),
+        }
+    }
+
     pub fn apply(&self, ctx: &egui::Context) {
         let mut style = (*ctx.style()).clone();
         style.spacing.item_spacing = self.spacing;
@@ -0,0 +1,150 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+pub const DATA_DIR_FLAG: &str = "--data-dir";
+pub const DATA_DIR_ENV: &str = "MONGOLITE_DATA_DIR";
+const APP_DIR_NAME: &str = "mongolite";
+
+/// Picks the directory Mongolite keeps its files in: the `--data-dir` flag,
+/// then the `MONGOLITE_DATA_DIR` environment variable, then the per-user data
+/// directory (`$XDG_DATA_HOME/mongolite` or `~/.local/share/mongolite` on
+/// Linux).
+pub fn resolve(args: impl IntoIterator<Item = String>) -> Result<PathBuf, String> {
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        if arg == DATA_DIR_FLAG {
+            return args
+                .next()
+                .filter(|value| !value.is_empty())
+                .map(PathBuf::from)
+                .ok_or_else(|| format!("{} requires a directory", DATA_DIR_FLAG));
+        }
+        if let Some(value) = arg.strip_prefix(&format!("{}=", DATA_DIR_FLAG)) {
+            if value.is_empty() {
+                return Err(format!("{} requires a directory", DATA_DIR_FLAG));
+            }
+            return Ok(PathBuf::from(value));
+        }
+    }
+
+    if let Some(dir) = std::env::var_os(DATA_DIR_ENV).filter(|dir| !dir.is_empty()) {
+        return Ok(PathBuf::from(dir));
+    }
+
+    // Without a home directory there's nowhere better than the old location
+    Ok(dirs::data_dir()
+        .map(|dir| dir.join(APP_DIR_NAME))
+        .unwrap_or_else(|| PathBuf::from(".")))
+}
+
+/// Moves a file that older versions kept in the working directory into the
+/// data directory. Nothing happens if the data directory already has the file,
+/// which includes the case where it is the working directory.
+/// Returns the new location when a file was moved.
+pub fn migrate_from_working_dir(file_name: &str, data_dir: &Path) -> io::Result<Option<PathBuf>> {
+    migrate(Path::new("."), file_name, data_dir)
+}
+
+fn migrate(old_dir: &Path, file_name: &str, data_dir: &Path) -> io::Result<Option<PathBuf>> {
+    let legacy = old_dir.join(file_name);
+    let target = data_dir.join(file_name);
+    if !legacy.is_file() || target.exists() {
+        return Ok(None);
+    }
+
+    fs::create_dir_all(data_dir)?;
+    // A rename fails across filesystems, so fall back to copying
+    if fs::rename(&legacy, &target).is_err() {
+        fs::copy(&legacy, &target)?;
+        fs::remove_file(&legacy)?;
+    }
+    Ok(Some(target))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn resolves_the_data_dir_flag() {
+        assert_eq!(
+            resolve(args(&["mongolite", "--data-dir", "/srv/mongolite"])),
+            Ok(PathBuf::from("/srv/mongolite"))
+        );
+        assert_eq!(
+            resolve(args(&["mongolite", "--data-dir=/srv/mongolite"])),
+            Ok(PathBuf::from("/srv/mongolite"))
+        );
+        assert!(resolve(args(&["mongolite", "--data-dir"])).is_err());
+        assert!(resolve(args(&["mongolite", "--data-dir="])).is_err());
+    }
+
+    // The only test that touches the environment variable, since tests share
+    // the process environment
+    #[test]
+    fn falls_back_to_the_environment_and_the_user_data_dir() {
+        std::env::set_var(DATA_DIR_ENV, "/var/lib/mongolite");
+        assert_eq!(
+            resolve(args(&["mongolite"])),
+            Ok(PathBuf::from("/var/lib/mongolite"))
+        );
+        // The flag wins over the environment
+        assert_eq!(
+            resolve(args(&["mongolite", "--data-dir", "/srv/mongolite"])),
+            Ok(PathBuf::from("/srv/mongolite"))
+        );
+
+        std::env::remove_var(DATA_DIR_ENV);
+        let expected = dirs::data_dir()
+            .map(|dir| dir.join(APP_DIR_NAME))
+            .unwrap_or_else(|| PathBuf::from("."));
+        assert_eq!(resolve(args(&["mongolite"])), Ok(expected));
+    }
+
+    #[test]
+    fn moves_files_from_the_old_directory() {
+        let old_dir = tempdir().unwrap();
+        let parent = tempdir().unwrap();
+        // Not created yet, as on a first run
+        let data_dir = parent.path().join("data");
+        fs::write(old_dir.path().join("profiles.redb"), "profiles").unwrap();
+
+        let moved = migrate(old_dir.path(), "profiles.redb", &data_dir).unwrap();
+        assert_eq!(moved, Some(data_dir.join("profiles.redb")));
+        assert!(!old_dir.path().join("profiles.redb").exists());
+        assert_eq!(
+            fs::read_to_string(data_dir.join("profiles.redb")).unwrap(),
+            "profiles"
+        );
+
+        // Nothing left to move
+        assert_eq!(
+            migrate(old_dir.path(), "profiles.redb", &data_dir).unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn leaves_an_existing_target_untouched() {
+        let old_dir = tempdir().unwrap();
+        let data_dir = tempdir().unwrap();
+        fs::write(old_dir.path().join("profiles.redb"), "old").unwrap();
+        fs::write(data_dir.path().join("profiles.redb"), "current").unwrap();
+
+        assert_eq!(
+            migrate(old_dir.path(), "profiles.redb", data_dir.path()).unwrap(),
+            None
+        );
+        assert_eq!(
+            fs::read_to_string(data_dir.path().join("profiles.redb")).unwrap(),
+            "current"
+        );
+        assert!(old_dir.path().join("profiles.redb").exists());
+    }
+}
@@ -1,4 +1,4 @@
-use crate::errors::EncryptionError;
+use crate::utils::error::EncryptionError;
 use aes::{
     cipher::{block_padding::Pkcs7, BlockDecryptMut},
     Aes256,
@@ -7,7 +7,6 @@ use cbc::{
     cipher::{BlockEncryptMut, KeyIvInit},
     Decryptor, Encryptor,
 };
-use hex;
 use rand::{thread_rng as generate_random_number, Rng};
 
 // IV length is always 16 bytes irrespective of the key size
@@ -8,6 +8,39 @@ pub enum MongoLiteError {
     #[error("Connection error: {0}")]
     ConnectionError(String),
 
+    #[error("Invalid connection string: {0}")]
+    ConnectionStringError(String),
+
+    #[error("Cannot resolve placeholder {0}")]
+    PlaceholderError(String),
+
+    #[error("TLS error: {0}")]
+    TlsError(String),
+
+    #[error("SSH tunnel error: {0}")]
+    SshTunnelError(String),
+
+    #[error("'{0}' is not allowed on a read-only connection")]
+    ReadOnlyViolation(String),
+
+    #[error("Profile store is locked: {0}")]
+    StorageLocked(String),
+
+    #[error("Profile store is corrupted: {0}")]
+    StorageCorrupted(String),
+
+    #[error("Profile store error: {0}")]
+    StorageError(String),
+
+    #[error("Encryption error: {0}")]
+    EncryptionError(#[from] EncryptionError),
+
+    #[error("Import error: {0}")]
+    ImportError(String),
+
+    #[error("Saved queries error: {0}")]
+    SavedQueryError(String),
+
     #[error("Query error: {0}")]
     QueryError(String),
 
@@ -18,6 +51,30 @@ pub enum MongoLiteError {
     StringError(String),
 }
 
+#[derive(Error, Debug)]
+pub enum EncryptionError {
+    #[error("key must be 32 bytes long")]
+    InvalidKeyLength,
+
+    #[error("failed to initialise the cipher")]
+    CipherCreationFailed,
+
+    #[error("encryption failed")]
+    EncryptionFailed,
+
+    #[error("ciphertext is not valid hex")]
+    InvalidHex,
+
+    #[error("ciphertext is too short")]
+    InvalidCiphertext,
+
+    #[error("decryption failed")]
+    DecryptionFailed,
+
+    #[error("decrypted data is not valid UTF-8")]
+    InvalidUtf8,
+}
+
 impl From<&str> for MongoLiteError {
     fn from(error: &str) -> Self {
         MongoLiteError::StringError(error.to_string())
@@ -0,0 +1,55 @@
+use mongodb::bson::{Bson, Document};
+
+/// Parses a document written as MongoDB extended JSON, relaxed or canonical,
+/// e.g. `{ "age": { "$gt": 21 } }`.
+pub fn parse_document(text: &str) -> Result<Document, String> {
+    match parse(text)? {
+        Bson::Document(document) => Ok(document),
+        _ => Err("expected a JSON object".to_string()),
+    }
+}
+
+/// Parses an aggregation pipeline, a JSON array of stage documents.
+pub fn parse_pipeline(text: &str) -> Result<Vec<Document>, String> {
+    let Bson::Array(stages) = parse(text)? else {
+        return Err("expected a JSON array of stages".to_string());
+    };
+    stages
+        .into_iter()
+        .enumerate()
+        .map(|(index, stage)| match stage {
+            Bson::Document(stage) => Ok(stage),
+            _ => Err(format!("stage {} is not a JSON object", index + 1)),
+        })
+        .collect()
+}
+
+fn parse(text: &str) -> Result<Bson, String> {
+    let value: serde_json::Value =
+        serde_json::from_str(text).map_err(|e| format!("invalid JSON: {}", e))?;
+    Bson::try_from(value).map_err(|e| format!("invalid extended JSON: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mongodb::bson::{doc, oid::ObjectId};
+
+    #[test]
+    fn parses_extended_json() {
+        let id = ObjectId::parse_str("65a1b2c3d4e5f60718293a4b").unwrap();
+        assert_eq!(
+            parse_document(r#"{ "_id": { "$oid": "65a1b2c3d4e5f60718293a4b" }, "n": 1 }"#).unwrap(),
+            doc! { "_id": id, "n": 1 }
+        );
+        assert_eq!(
+            parse_pipeline(r#"[{ "$match": { "n": 1 } }, { "$limit": 5 }]"#).unwrap(),
+            vec![doc! { "$match": { "n": 1 } }, doc! { "$limit": 5 }]
+        );
+        assert_eq!(
+            parse_pipeline(r#"[{ "$limit": 5 }, 3]"#).unwrap_err(),
+            "stage 2 is not a JSON object"
+        );
+        assert!(parse_document("[]").is_err());
+    }
+}
@@ -0,0 +1,63 @@
+/// Formats a byte count with a binary unit, e.g. `1.5 MiB`.
+pub fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["KiB", "MiB", "GiB", "TiB", "PiB"];
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+    let mut value = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", value, UNITS[unit])
+}
+
+/// Formats a count with thousands separators, e.g. `12,345`.
+pub fn format_count(count: u64) -> String {
+    let digits = count.to_string();
+    let mut formatted = String::with_capacity(digits.len() + digits.len() / 3);
+    for (index, digit) in digits.chars().enumerate() {
+        if index > 0 && (digits.len() - index).is_multiple_of(3) {
+            formatted.push(',');
+        }
+        formatted.push(digit);
+    }
+    formatted
+}
+
+/// Formats a duration given in microseconds, e.g. `850 ms` or `2 min 5 s`.
+pub fn format_duration(micros: u64) -> String {
+    let millis = micros / 1000;
+    match millis {
+        0..=999 => format!("{} ms", millis),
+        1000..=59_999 => format!("{:.1} s", millis as f64 / 1000.0),
+        _ => {
+            let seconds = millis / 1000;
+            if seconds < 3600 {
+                format!("{} min {} s", seconds / 60, seconds % 60)
+            } else {
+                format!("{} h {} min", seconds / 3600, seconds % 3600 / 60)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats_sizes_and_counts() {
+        assert_eq!(format_bytes(512), "512 B");
+        assert_eq!(format_bytes(1536), "1.5 KiB");
+        assert_eq!(format_bytes(5 * 1024 * 1024 * 1024), "5.0 GiB");
+        assert_eq!(format_count(7), "7");
+        assert_eq!(format_count(1000), "1,000");
+        assert_eq!(format_count(1234567), "1,234,567");
+        assert_eq!(format_duration(850_000), "850 ms");
+        assert_eq!(format_duration(12_340_000), "12.3 s");
+        assert_eq!(format_duration(125_000_000), "2 min 5 s");
+        assert_eq!(format_duration(7_500_000_000), "2 h 5 min");
+    }
+}
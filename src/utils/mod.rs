@@ -1 +1,8 @@
+pub mod connection_string;
+pub mod data_dir;
+pub mod encryption;
 pub mod error;
+pub mod extended_json;
+pub mod format;
+pub mod task;
+pub mod tls;
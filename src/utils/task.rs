@@ -0,0 +1,34 @@
+use std::future::Future;
+use std::sync::mpsc::{channel, Receiver, TryRecvError};
+
+/// Handle to a future running on the background tokio runtime.
+///
+/// The UI thread polls it once per frame; the task asks egui for a repaint
+/// when it finishes so the result is picked up without waiting for input.
+pub struct BackgroundTask<T> {
+    receiver: Receiver<T>,
+}
+
+impl<T: Send + 'static> BackgroundTask<T> {
+    pub fn spawn<F>(ctx: &egui::Context, future: F) -> Self
+    where
+        F: Future<Output = T> + Send + 'static,
+    {
+        let (sender, receiver) = channel();
+        let ctx = ctx.clone();
+        tokio::spawn(async move {
+            let _ = sender.send(future.await);
+            ctx.request_repaint();
+        });
+        Self { receiver }
+    }
+
+    /// Returns the result once the task has finished. A task whose future
+    /// panicked never yields a result.
+    pub fn poll(&mut self) -> Option<T> {
+        match self.receiver.try_recv() {
+            Ok(value) => Some(value),
+            Err(TryRecvError::Empty) | Err(TryRecvError::Disconnected) => None,
+        }
+    }
+}